@@ -5,24 +5,33 @@
  * Rust spawns one bridge per ConversationView session.
  *
  * Inbound (Rust → Bridge):
- *   { type: "init", projectPath: "..." }
+ *   { type: "init", projectPath: "...", resume?: sessionId, forkAt?: entryUuid, options: {
+ *       model?, maxTurns?, appendSystemPrompt?, allowedTools?, disallowedTools?,
+ *       additionalDirectories?, mcpServers? } }
  *   { type: "message", text: "..." }
  *   { type: "permission_response", id: "...", allowed: bool, updatedInput?: {...} }
  *   { type: "abort" }
  *
  * Outbound (Bridge → Rust):
  *   { type: "ready" }
- *   { type: "system", session_id, model }
+ *   { type: "system", session_id, model, permission_mode, tools, mcp_servers, options }
  *   { type: "message_start" }
- *   { type: "text", text }
- *   { type: "thinking", text }
- *   { type: "tool_use", id, name, input }
- *   { type: "tool_result", tool_use_id, content, is_error }
+ *   { type: "text_delta", text, parent_tool_use_id }
+ *   { type: "thinking_delta", text, parent_tool_use_id }
+ *   { type: "text", text, parent_tool_use_id }
+ *   { type: "thinking", text, parent_tool_use_id }
+ *   { type: "tool_use", id, name, input, parent_tool_use_id }
+ *   { type: "tool_result", tool_use_id, content, is_error, parent_tool_use_id }
  *   { type: "permission_request", id, tool, input, description }
  *   { type: "user_question", id, questions }
- *   { type: "result", subtype, session_id, duration_ms, num_turns, model_usage, is_error }
+ *   { type: "compacted", trigger, pre_tokens }
+ *   { type: "result", subtype, session_id, duration_ms, num_turns, total_cost_usd, model_usage, is_error }
  *   { type: "error", message }
+ *   { type: "sdk_message", message }   (SDK messages with no mapping above)
  *   { type: "message_stop" }
+ *
+ * parent_tool_use_id is set on events from a subagent, naming the Task tool call that
+ * started it, and null otherwise.
  */
 
 import { query } from "@anthropic-ai/claude-agent-sdk";
@@ -30,6 +39,10 @@ import { createInterface } from "readline";
 
 let projectPath = null;
 let sessionId = null;
+/** Transcript entry to fork the resumed session from, used by the first query only. */
+let forkAt = null;
+/** Options from init, applied to every query of the session. */
+let sessionOptions = {};
 let currentQuery = null;
 
 /** Pending permission/question responses: id → { resolve } */
@@ -93,9 +106,6 @@ async function canUseTool(toolName, input, { signal }) {
 async function runQuery(text, permissionMode) {
   emit({ type: "message_start" });
 
-  /** Accumulate tool_use input from stream deltas: blockIndex → { id, name, inputJson } */
-  const pendingToolBlocks = new Map();
-
   const options = {
     cwd: projectPath,
     canUseTool,
@@ -105,6 +115,23 @@ async function runQuery(text, permissionMode) {
     settingSources: ["user", "project"],
   };
 
+  const {
+    model,
+    maxTurns,
+    appendSystemPrompt,
+    allowedTools,
+    disallowedTools,
+    additionalDirectories,
+    mcpServers,
+  } = sessionOptions;
+  if (model) options.model = model;
+  if (maxTurns) options.maxTurns = maxTurns;
+  if (appendSystemPrompt) options.systemPrompt.append = appendSystemPrompt;
+  if (allowedTools?.length) options.allowedTools = allowedTools;
+  if (disallowedTools?.length) options.disallowedTools = disallowedTools;
+  if (additionalDirectories?.length) options.additionalDirectories = additionalDirectories;
+  if (mcpServers) options.mcpServers = mcpServers;
+
   if (permissionMode) {
     options.permissionMode = permissionMode;
   }
@@ -113,10 +140,18 @@ async function runQuery(text, permissionMode) {
     options.resume = sessionId;
   }
 
+  if (forkAt) {
+    // the fork gets its own session id, which the init message below picks up
+    options.resumeSessionAt = forkAt;
+    options.forkSession = true;
+    forkAt = null;
+  }
+
   currentQuery = query({ prompt: text, options });
 
   try {
     for await (const msg of currentQuery) {
+      const parent_tool_use_id = msg.parent_tool_use_id ?? null;
       switch (msg.type) {
         case "system": {
           if (msg.subtype === "init") {
@@ -127,15 +162,42 @@ async function runQuery(text, permissionMode) {
               model: msg.model || "",
               permission_mode: msg.permissionMode || "default",
               tools: msg.tools || [],
+              mcp_servers: msg.mcp_servers || [],
+              options: sessionOptions,
+            });
+          } else if (msg.subtype === "compact_boundary") {
+            emit({
+              type: "compacted",
+              trigger: msg.compact_metadata?.trigger || "auto",
+              pre_tokens: msg.compact_metadata?.pre_tokens || 0,
             });
+          } else {
+            emit({ type: "sdk_message", message: msg });
           }
           break;
         }
 
         case "assistant": {
-          // Skip — text, thinking, and tool_use are all handled via
-          // stream_event to avoid double-emission. The full assistant
-          // message would duplicate what we already streamed.
+          // Whole blocks, after their deltas went out as stream_events. Tool
+          // input only parses once complete, so tool_use comes from here too.
+          const content = msg.message?.content;
+          if (Array.isArray(content)) {
+            for (const block of content) {
+              if (block.type === "text") {
+                emit({ type: "text", text: block.text, parent_tool_use_id });
+              } else if (block.type === "thinking") {
+                emit({ type: "thinking", text: block.thinking, parent_tool_use_id });
+              } else if (block.type === "tool_use") {
+                emit({
+                  type: "tool_use",
+                  id: block.id,
+                  name: block.name,
+                  input: block.input || {},
+                  parent_tool_use_id,
+                });
+              }
+            }
+          }
           break;
         }
 
@@ -159,6 +221,7 @@ async function runQuery(text, permissionMode) {
                   tool_use_id: block.tool_use_id,
                   content: contentText,
                   is_error: block.is_error || false,
+                  parent_tool_use_id,
                 });
               }
             }
@@ -174,6 +237,7 @@ async function runQuery(text, permissionMode) {
             session_id: msg.session_id || sessionId || "",
             duration_ms: msg.duration_ms || 0,
             num_turns: msg.num_turns || 0,
+            total_cost_usd: msg.total_cost_usd || 0,
             is_error: msg.subtype !== "success",
             model_usage: mu,
           });
@@ -182,54 +246,20 @@ async function runQuery(text, permissionMode) {
 
         case "stream_event": {
           const ev = msg.event;
-          if (!ev) break;
-
-          if (ev.type === "content_block_start") {
-            const block = ev.content_block;
-            if (block?.type === "thinking") {
-              emit({ type: "thinking", text: block.thinking || "" });
-            } else if (block?.type === "tool_use") {
-              // Start accumulating input JSON for this tool_use block
-              pendingToolBlocks.set(ev.index, {
-                id: block.id,
-                name: block.name,
-                inputJson: "",
-              });
-            }
-          } else if (ev.type === "content_block_delta") {
-            const delta = ev.delta;
-            if (delta?.type === "text_delta") {
-              emit({ type: "text", text: delta.text });
-            } else if (delta?.type === "thinking_delta") {
-              emit({ type: "thinking", text: delta.thinking });
-            } else if (delta?.type === "input_json_delta") {
-              // Accumulate partial JSON for the pending tool_use block
-              const pending = pendingToolBlocks.get(ev.index);
-              if (pending) {
-                pending.inputJson += delta.partial_json;
-              }
-            }
-          } else if (ev.type === "content_block_stop") {
-            // Emit complete tool_use with fully accumulated input
-            const pending = pendingToolBlocks.get(ev.index);
-            if (pending) {
-              pendingToolBlocks.delete(ev.index);
-              let input = {};
-              try {
-                input = JSON.parse(pending.inputJson);
-              } catch {
-                // Partial or invalid JSON — emit with empty input
-              }
-              emit({
-                type: "tool_use",
-                id: pending.id,
-                name: pending.name,
-                input,
-              });
-            }
+          if (ev?.type !== "content_block_delta") break;
+          const delta = ev.delta;
+          if (delta?.type === "text_delta") {
+            emit({ type: "text_delta", text: delta.text, parent_tool_use_id });
+          } else if (delta?.type === "thinking_delta") {
+            emit({ type: "thinking_delta", text: delta.thinking, parent_tool_use_id });
           }
           break;
         }
+
+        default: {
+          emit({ type: "sdk_message", message: msg });
+          break;
+        }
       }
     }
   } catch (err) {
@@ -258,6 +288,9 @@ rl.on("line", (line) => {
   switch (cmd.type) {
     case "init": {
       projectPath = cmd.projectPath;
+      sessionId = cmd.resume || null;
+      forkAt = (sessionId && cmd.forkAt) || null;
+      sessionOptions = cmd.options || {};
       emit({ type: "ready" });
       break;
     }
@@ -1,4 +1,4 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::io::{BufRead, BufReader, Write};
 use std::process::{Child, Command, Stdio};
@@ -13,27 +13,92 @@ pub struct ModelUsage {
     pub cache_read_input_tokens: u32,
     pub cache_creation_input_tokens: u32,
     pub context_window: u32,
+    pub cost_usd: f64,
 }
 
+#[derive(Debug, Clone, Serialize)]
+pub struct McpServerStatus {
+    pub name: String,
+    pub status: String,
+}
+
+/// Per-session settings for an SDK chat, fixed when the session starts.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SessionOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_turns: Option<u32>,
+    /// Added after Claude Code's own system prompt rather than replacing it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub append_system_prompt: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub allowed_tools: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub disallowed_tools: Vec<String>,
+    /// Directories besides the project the session may read and edit.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub additional_directories: Vec<String>,
+    /// Server name to config, shaped like `mcpServers` in `.mcp.json`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mcp_servers: Option<serde_json::Value>,
+}
+
+impl SessionOptions {
+    fn validate(&self) -> Result<(), String> {
+        if self.max_turns == Some(0) {
+            return Err("max turns must be at least 1".to_string());
+        }
+        if let Some(servers) = &self.mcp_servers {
+            if !servers.is_object() {
+                return Err("MCP servers must map server names to configs".to_string());
+            }
+        }
+        for dir in &self.additional_directories {
+            if !std::path::Path::new(dir).is_dir() {
+                return Err(format!("Additional directory not found: {}", dir));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Content events carry `parent_tool_use_id` when they come from a subagent (the Task
+/// tool), naming the tool call that started it.
 #[derive(Debug, Clone, Serialize)]
 #[serde(tag = "type", content = "data")]
 pub enum ClaudeEvent {
     MessageStart,
+    /// Streamed piece of a text block; the whole block follows as `Text`.
+    TextDelta {
+        text: String,
+        parent_tool_use_id: Option<String>,
+    },
+    /// Streamed piece of a thinking block; the whole block follows as `Thinking`.
+    ThinkingDelta {
+        text: String,
+        parent_tool_use_id: Option<String>,
+    },
     Text {
         text: String,
+        parent_tool_use_id: Option<String>,
     },
     Thinking {
         text: String,
+        parent_tool_use_id: Option<String>,
     },
     ToolUse {
         id: String,
         name: String,
         input: serde_json::Value,
+        parent_tool_use_id: Option<String>,
     },
     ToolResult {
         tool_use_id: String,
         content: String,
         is_error: bool,
+        parent_tool_use_id: Option<String>,
     },
     PermissionRequest {
         id: String,
@@ -45,13 +110,20 @@ pub enum ClaudeEvent {
         id: String,
         questions: serde_json::Value,
     },
+    /// The conversation was summarized to free context, by `/compact` or automatically.
+    Compacted {
+        trigger: String,
+        pre_tokens: u64,
+    },
     Result {
         subtype: String,
         duration_ms: f64,
         is_error: bool,
         num_turns: u32,
         session_id: String,
-        model_usage: Option<ModelUsage>,
+        total_cost_usd: f64,
+        /// One entry per model the turn used, subagents' included.
+        model_usage: Vec<ModelUsage>,
     },
     Error {
         message: String,
@@ -61,9 +133,28 @@ pub enum ClaudeEvent {
         model: String,
         permission_mode: String,
         tool_count: u32,
+        tools: Vec<String>,
+        mcp_servers: Vec<McpServerStatus>,
+        /// The options the session was started with.
+        options: SessionOptions,
     },
     Ready,
     MessageStop,
+    /// A bridge event this version doesn't model, passed through whole.
+    Other {
+        event_type: String,
+        data: serde_json::Value,
+    },
+}
+
+/// Where a new chat picks up instead of starting a fresh conversation.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResumeFrom {
+    pub session_id: String,
+    /// Fork into a new session holding the conversation up to this transcript entry,
+    /// leaving the original to carry on separately.
+    pub fork_at: Option<String>,
 }
 
 /// A command sent to the bridge process via stdin.
@@ -74,6 +165,11 @@ enum BridgeCommand {
     Init {
         #[serde(rename = "projectPath")]
         project_path: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        resume: Option<String>,
+        #[serde(rename = "forkAt", skip_serializing_if = "Option::is_none")]
+        fork_at: Option<String>,
+        options: SessionOptions,
     },
     #[serde(rename = "message")]
     Message {
@@ -131,8 +227,11 @@ impl ClaudeManager {
     pub fn create_session(
         &self,
         project_path: &str,
+        resume: Option<ResumeFrom>,
+        options: SessionOptions,
         on_event: Channel<ClaudeEvent>,
     ) -> Result<String, String> {
+        options.validate()?;
         let tab_id = uuid::Uuid::new_v4().to_string();
 
         // Spawn the bridge process
@@ -171,8 +270,15 @@ impl ClaudeManager {
         };
 
         // Send init command
+        let (resume, fork_at) = match resume {
+            Some(from) => (Some(from.session_id), from.fork_at),
+            None => (None, None),
+        };
         session.write_command(&BridgeCommand::Init {
             project_path: project_path.to_string(),
+            resume,
+            fork_at,
+            options,
         })?;
 
         self.sessions
@@ -335,6 +441,52 @@ impl Drop for ClaudeManager {
     }
 }
 
+fn string_field(val: &serde_json::Value, key: &str) -> String {
+    val.get(key)
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string()
+}
+
+fn parent_tool_use_id(val: &serde_json::Value) -> Option<String> {
+    val.get("parent_tool_use_id")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+}
+
+/// Reads a usage count under the SDK's camelCase key or the API's snake_case one.
+fn usage_count(usage: &serde_json::Value, camel: &str, snake: &str) -> u32 {
+    usage
+        .get(camel)
+        .or_else(|| usage.get(snake))
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0) as u32
+}
+
+fn parse_model_usage(model: &str, usage: &serde_json::Value) -> ModelUsage {
+    ModelUsage {
+        model: model.to_string(),
+        input_tokens: usage_count(usage, "inputTokens", "input_tokens"),
+        output_tokens: usage_count(usage, "outputTokens", "output_tokens"),
+        cache_read_input_tokens: usage_count(
+            usage,
+            "cacheReadInputTokens",
+            "cache_read_input_tokens",
+        ),
+        cache_creation_input_tokens: usage_count(
+            usage,
+            "cacheCreationInputTokens",
+            "cache_creation_input_tokens",
+        ),
+        context_window: usage_count(usage, "contextWindow", "context_window"),
+        cost_usd: usage
+            .get("costUSD")
+            .or_else(|| usage.get("cost_usd"))
+            .and_then(|v| v.as_f64())
+            .unwrap_or(0.0),
+    }
+}
+
 /// Parse a JSON line from the bridge stdout into a ClaudeEvent.
 fn parse_bridge_event(val: &serde_json::Value) -> Option<ClaudeEvent> {
     let event_type = val.get("type").and_then(|t| t.as_str())?;
@@ -343,130 +495,114 @@ fn parse_bridge_event(val: &serde_json::Value) -> Option<ClaudeEvent> {
         "ready" => Some(ClaudeEvent::Ready),
 
         "system" => {
-            let session_id = val
-                .get("session_id")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
-            let model = val
-                .get("model")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
             let permission_mode = val
                 .get("permission_mode")
                 .and_then(|v| v.as_str())
                 .unwrap_or("default")
                 .to_string();
-            let tool_count = val
+            let tools: Vec<String> = val
                 .get("tools")
                 .and_then(|v| v.as_array())
-                .map(|a| a.len() as u32)
-                .unwrap_or(0);
+                .map(|a| {
+                    a.iter()
+                        .filter_map(|t| t.as_str().map(|s| s.to_string()))
+                        .collect()
+                })
+                .unwrap_or_default();
+            let mcp_servers = val
+                .get("mcp_servers")
+                .and_then(|v| v.as_array())
+                .map(|a| {
+                    a.iter()
+                        .map(|server| McpServerStatus {
+                            name: string_field(server, "name"),
+                            status: string_field(server, "status"),
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            let options = val
+                .get("options")
+                .cloned()
+                .and_then(|o| serde_json::from_value(o).ok())
+                .unwrap_or_default();
             Some(ClaudeEvent::System {
-                session_id,
-                model,
+                session_id: string_field(val, "session_id"),
+                model: string_field(val, "model"),
                 permission_mode,
-                tool_count,
+                tool_count: tools.len() as u32,
+                tools,
+                mcp_servers,
+                options,
             })
         }
 
         "message_start" => Some(ClaudeEvent::MessageStart),
         "message_stop" => Some(ClaudeEvent::MessageStop),
 
-        "text" => {
-            let text = val
-                .get("text")
-                .and_then(|t| t.as_str())
-                .unwrap_or("")
-                .to_string();
-            Some(ClaudeEvent::Text { text })
-        }
-
-        "thinking" => {
-            let text = val
-                .get("text")
-                .and_then(|t| t.as_str())
-                .unwrap_or("")
-                .to_string();
-            Some(ClaudeEvent::Thinking { text })
-        }
-
-        "tool_use" => {
-            let id = val
-                .get("id")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
-            let name = val
-                .get("name")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
-            let input = val.get("input").cloned().unwrap_or(serde_json::Value::Null);
-            Some(ClaudeEvent::ToolUse { id, name, input })
-        }
+        "text_delta" => Some(ClaudeEvent::TextDelta {
+            text: string_field(val, "text"),
+            parent_tool_use_id: parent_tool_use_id(val),
+        }),
+
+        "thinking_delta" => Some(ClaudeEvent::ThinkingDelta {
+            text: string_field(val, "text"),
+            parent_tool_use_id: parent_tool_use_id(val),
+        }),
+
+        "text" => Some(ClaudeEvent::Text {
+            text: string_field(val, "text"),
+            parent_tool_use_id: parent_tool_use_id(val),
+        }),
+
+        "thinking" => Some(ClaudeEvent::Thinking {
+            text: string_field(val, "text"),
+            parent_tool_use_id: parent_tool_use_id(val),
+        }),
+
+        "tool_use" => Some(ClaudeEvent::ToolUse {
+            id: string_field(val, "id"),
+            name: string_field(val, "name"),
+            input: val.get("input").cloned().unwrap_or(serde_json::Value::Null),
+            parent_tool_use_id: parent_tool_use_id(val),
+        }),
 
         "tool_result" => {
-            let tool_use_id = val
-                .get("tool_use_id")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
-            let content = val
-                .get("content")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
             let is_error = val
                 .get("is_error")
                 .and_then(|v| v.as_bool())
                 .unwrap_or(false);
             Some(ClaudeEvent::ToolResult {
-                tool_use_id,
-                content,
+                tool_use_id: string_field(val, "tool_use_id"),
+                content: string_field(val, "content"),
                 is_error,
+                parent_tool_use_id: parent_tool_use_id(val),
             })
         }
 
-        "permission_request" => {
-            let id = val
-                .get("id")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
-            let tool = val
-                .get("tool")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
-            let input = val.get("input").cloned().unwrap_or(serde_json::Value::Null);
-            let description = val
-                .get("description")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
-            Some(ClaudeEvent::PermissionRequest {
-                id,
-                tool,
-                input,
-                description,
-            })
-        }
+        "permission_request" => Some(ClaudeEvent::PermissionRequest {
+            id: string_field(val, "id"),
+            tool: string_field(val, "tool"),
+            input: val.get("input").cloned().unwrap_or(serde_json::Value::Null),
+            description: string_field(val, "description"),
+        }),
 
         "user_question" => {
-            let id = val
-                .get("id")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
             let questions = val
                 .get("questions")
                 .cloned()
                 .unwrap_or(serde_json::Value::Array(vec![]));
-            Some(ClaudeEvent::UserQuestion { id, questions })
+            Some(ClaudeEvent::UserQuestion {
+                id: string_field(val, "id"),
+                questions,
+            })
         }
 
+        "compacted" => Some(ClaudeEvent::Compacted {
+            trigger: string_field(val, "trigger"),
+            pre_tokens: val.get("pre_tokens").and_then(|v| v.as_u64()).unwrap_or(0),
+        }),
+
         "result" => {
             let subtype = val
                 .get("subtype")
@@ -482,53 +618,27 @@ fn parse_bridge_event(val: &serde_json::Value) -> Option<ClaudeEvent> {
                 .and_then(|v| v.as_bool())
                 .unwrap_or(false);
             let num_turns = val.get("num_turns").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
-            let session_id = val
-                .get("session_id")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
-
-            // Extract first entry from model_usage map
-            let mu_obj = val.get("model_usage").and_then(|mu| mu.as_object());
-            let model_usage =
-                mu_obj
-                    .and_then(|map| map.iter().next())
-                    .map(|(model_name, usage)| ModelUsage {
-                        model: model_name.clone(),
-                        input_tokens: usage
-                            .get("inputTokens")
-                            .or_else(|| usage.get("input_tokens"))
-                            .and_then(|v| v.as_u64())
-                            .unwrap_or(0) as u32,
-                        output_tokens: usage
-                            .get("outputTokens")
-                            .or_else(|| usage.get("output_tokens"))
-                            .and_then(|v| v.as_u64())
-                            .unwrap_or(0) as u32,
-                        cache_read_input_tokens: usage
-                            .get("cacheReadInputTokens")
-                            .or_else(|| usage.get("cache_read_input_tokens"))
-                            .and_then(|v| v.as_u64())
-                            .unwrap_or(0) as u32,
-                        cache_creation_input_tokens: usage
-                            .get("cacheCreationInputTokens")
-                            .or_else(|| usage.get("cache_creation_input_tokens"))
-                            .and_then(|v| v.as_u64())
-                            .unwrap_or(0)
-                            as u32,
-                        context_window: usage
-                            .get("contextWindow")
-                            .or_else(|| usage.get("context_window"))
-                            .and_then(|v| v.as_u64())
-                            .unwrap_or(0) as u32,
-                    });
+            let total_cost_usd = val
+                .get("total_cost_usd")
+                .and_then(|v| v.as_f64())
+                .unwrap_or(0.0);
+            let model_usage = val
+                .get("model_usage")
+                .and_then(|mu| mu.as_object())
+                .map(|map| {
+                    map.iter()
+                        .map(|(model, usage)| parse_model_usage(model, usage))
+                        .collect()
+                })
+                .unwrap_or_default();
 
             Some(ClaudeEvent::Result {
                 subtype,
                 duration_ms,
                 is_error,
                 num_turns,
-                session_id,
+                session_id: string_field(val, "session_id"),
+                total_cost_usd,
                 model_usage,
             })
         }
@@ -542,6 +652,123 @@ fn parse_bridge_event(val: &serde_json::Value) -> Option<ClaudeEvent> {
             Some(ClaudeEvent::Error { message })
         }
 
-        _ => None,
+        other => Some(ClaudeEvent::Other {
+            event_type: other.to_string(),
+            data: val.clone(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn tags_subagent_events_with_their_parent_tool() {
+        let delta = json!({"type": "text_delta", "text": "He", "parent_tool_use_id": "toolu_1"});
+        match parse_bridge_event(&delta) {
+            Some(ClaudeEvent::TextDelta {
+                text,
+                parent_tool_use_id,
+            }) => {
+                assert_eq!(text, "He");
+                assert_eq!(parent_tool_use_id.as_deref(), Some("toolu_1"));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let top_level = json!({"type": "tool_use", "id": "toolu_2", "name": "Read", "input": {}});
+        match parse_bridge_event(&top_level) {
+            Some(ClaudeEvent::ToolUse {
+                parent_tool_use_id, ..
+            }) => assert_eq!(parent_tool_use_id, None),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let unknown = json!({"type": "sdk_message", "message": {"type": "tool_progress"}});
+        assert!(matches!(
+            parse_bridge_event(&unknown),
+            Some(ClaudeEvent::Other { event_type, .. }) if event_type == "sdk_message"
+        ));
+    }
+
+    #[test]
+    fn session_options_go_out_with_init_and_come_back_on_system() {
+        let options = SessionOptions {
+            model: Some("claude-opus".into()),
+            max_turns: Some(8),
+            allowed_tools: vec!["Read".into(), "Grep".into()],
+            ..Default::default()
+        };
+        let init = BridgeCommand::Init {
+            project_path: "/p".into(),
+            resume: None,
+            fork_at: None,
+            options: options.clone(),
+        };
+        assert_eq!(
+            serde_json::to_value(&init).unwrap(),
+            json!({
+                "type": "init",
+                "projectPath": "/p",
+                "options": {"model": "claude-opus", "maxTurns": 8, "allowedTools": ["Read", "Grep"]}
+            })
+        );
+
+        let system = json!({
+            "type": "system",
+            "session_id": "s1",
+            "model": "claude-opus",
+            "tools": ["Read", "Grep"],
+            "mcp_servers": [{"name": "docs", "status": "connected"}],
+            "options": serde_json::to_value(&options).unwrap()
+        });
+        let Some(ClaudeEvent::System {
+            tool_count,
+            mcp_servers,
+            options: reported,
+            ..
+        }) = parse_bridge_event(&system)
+        else {
+            panic!("not a system event");
+        };
+        assert_eq!(tool_count, 2);
+        assert_eq!(mcp_servers[0].status, "connected");
+        assert_eq!(reported, options);
+
+        let zero_turns = SessionOptions {
+            max_turns: Some(0),
+            ..Default::default()
+        };
+        assert!(zero_turns.validate().is_err());
+    }
+
+    #[test]
+    fn reports_usage_for_every_model_in_the_turn() {
+        let result = json!({
+            "type": "result",
+            "subtype": "success",
+            "num_turns": 3,
+            "total_cost_usd": 0.25,
+            "model_usage": {
+                "claude-haiku": {"inputTokens": 10, "outputTokens": 5, "costUSD": 0.05},
+                "claude-sonnet": {"input_tokens": 200, "cache_read_input_tokens": 40, "costUSD": 0.2}
+            }
+        });
+        let Some(ClaudeEvent::Result {
+            model_usage,
+            total_cost_usd,
+            ..
+        }) = parse_bridge_event(&result)
+        else {
+            panic!("not a result");
+        };
+        assert_eq!(total_cost_usd, 0.25);
+        let models: Vec<&str> = model_usage.iter().map(|u| u.model.as_str()).collect();
+        assert_eq!(models, ["claude-haiku", "claude-sonnet"]);
+        assert_eq!(model_usage[1].input_tokens, 200);
+        assert_eq!(model_usage[1].cache_read_input_tokens, 40);
+        assert_eq!(model_usage[0].cost_usd, 0.05);
     }
 }
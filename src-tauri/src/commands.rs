@@ -1,21 +1,32 @@
-use crate::claude_manager::{ClaudeEvent, ClaudeManager};
+use crate::claude_manager::{ClaudeEvent, ClaudeManager, ResumeFrom, SessionOptions};
 use crate::config::{
     self, PiChatSettingsConfig, PinnedFileConfig, ProjectConfig, RemoteConfig, SettingsConfig,
 };
 use crate::conversation;
+use crate::credentials::{self, CredentialsState, Secret};
+use crate::devcontainer;
 use crate::file_watcher::FileWatcherManager;
 use crate::git;
+use crate::git_status_cache::GitStatusCache;
+use crate::git_undo;
 use crate::pi_manager::{PiManager, PiRpcEvent, PiSessionInfo};
 use crate::pty_manager::{AttachStreamResult, PtyManager, PtyOutputEvent, PtySessionInfo};
-use crate::remote::{self, Location, SshTarget};
+use crate::remote::{
+    self, DetachedSession, ForwardStatus, HostDiagnosis, ListeningPort, Location, PortForward,
+    SshTarget, StreamChunk, StreamEnd, Transport,
+};
+use crate::remote_health::{HostHealth, RemoteHealthMonitor};
+use crate::remote_index::RemoteFileIndex;
+use crate::ssh_config;
+use crate::transfer::{self, TransferProgress};
 use crate::whisper_manager::{DownloadProgress, ModelInfo, WhisperEvent, WhisperManager};
 use tauri::ipc::Channel;
-use tauri::State;
+use tauri::{Emitter, State};
 
 #[cfg(windows)]
 use std::os::windows::process::CommandExt;
 
-const HARDCODED_SKIP: &[&str] = &[
+pub(crate) const HARDCODED_SKIP: &[&str] = &[
     ".git",
     "node_modules",
     "target",
@@ -53,12 +64,19 @@ pub struct CreatePtySessionRequest {
     pub rows: u16,
     pub session_type: String,
     pub command: String,
+    /// Run a remote session inside tmux/dtach on the host so it survives disconnects.
+    #[serde(default)]
+    pub persistent: bool,
+    /// Reattach to (or restart under) this detached session instead of naming a new one.
+    #[serde(default)]
+    pub detached_name: Option<String>,
 }
 
 #[derive(serde::Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CreatePtySessionResponse {
     pub session_id: String,
+    pub detached_name: Option<String>,
 }
 
 #[derive(serde::Deserialize, serde::Serialize)]
@@ -105,14 +123,31 @@ pub fn create_pty_session(
     pty_manager: State<'_, PtyManager>,
     request: CreatePtySessionRequest,
 ) -> Result<CreatePtySessionResponse, String> {
+    let detached_name = match request.detached_name {
+        Some(name) => {
+            remote::check_detached_name(&name)?;
+            Some(name)
+        }
+        None if request.persistent => match remote::locate(&request.project_path) {
+            Location::Remote(target) if remote::can_detach(&target, &request.command) => {
+                Some(remote::new_detached_name(&request.session_type))
+            }
+            _ => None,
+        },
+        None => None,
+    };
     let session_id = pty_manager.create_session(
         &request.project_path,
         request.cols,
         request.rows,
         &request.session_type,
         &request.command,
+        detached_name.as_deref(),
     )?;
-    Ok(CreatePtySessionResponse { session_id })
+    Ok(CreatePtySessionResponse {
+        session_id,
+        detached_name,
+    })
 }
 
 #[tauri::command]
@@ -202,8 +237,14 @@ pub fn load_settings(app_handle: tauri::AppHandle) -> Option<SettingsConfig> {
 }
 
 #[tauri::command]
-pub fn save_settings(app_handle: tauri::AppHandle, settings: SettingsConfig) -> Result<(), String> {
-    config::save_settings(&app_handle, &settings)
+pub fn save_settings(
+    app_handle: tauri::AppHandle,
+    file_index: State<'_, RemoteFileIndex>,
+    settings: SettingsConfig,
+) -> Result<(), String> {
+    config::save_settings(&app_handle, &settings)?;
+    file_index.set_enabled(settings.remote_file_index);
+    Ok(())
 }
 
 #[tauri::command]
@@ -233,65 +274,255 @@ pub fn save_workspace_sessions(
 }
 
 #[tauri::command]
-pub async fn get_git_status(project_path: String) -> Result<git::GitStatus, String> {
-    tauri::async_runtime::spawn_blocking(move || git::get_status(&project_path))
+pub async fn get_git_status(
+    git_status_cache: State<'_, GitStatusCache>,
+    project_path: String,
+) -> Result<git::GitStatus, String> {
+    let cache = git_status_cache.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || cache.status(&project_path))
         .await
         .map_err(|e| format!("Task join failed: {}", e))
 }
 
+// watched projects get `git-status-changed` events instead of needing to be polled
+
+#[tauri::command]
+pub fn watch_git_status(
+    git_status_cache: State<'_, GitStatusCache>,
+    project_path: String,
+) -> Result<(), String> {
+    git_status_cache.watch(&project_path)
+}
+
+#[tauri::command]
+pub fn unwatch_git_status(
+    git_status_cache: State<'_, GitStatusCache>,
+    project_path: String,
+) -> Result<(), String> {
+    git_status_cache.unwatch(&project_path)
+}
+
+#[tauri::command]
+pub fn get_remote_health(health_monitor: State<'_, RemoteHealthMonitor>) -> Vec<HostHealth> {
+    health_monitor.status()
+}
+
+#[tauri::command]
+pub fn watch_remote_health(
+    health_monitor: State<'_, RemoteHealthMonitor>,
+    project_path: String,
+) -> Result<(), String> {
+    health_monitor.watch(&project_path)
+}
+
+#[tauri::command]
+pub fn unwatch_remote_health(
+    health_monitor: State<'_, RemoteHealthMonitor>,
+    project_path: String,
+) -> Result<(), String> {
+    health_monitor.unwatch(&project_path)
+}
+
+/// Status of a nested repo or submodule, given by its project-relative path, with the
+/// repos nested inside it.
+#[tauri::command]
+pub async fn get_nested_git_status(
+    project_path: String,
+    repo_path: String,
+) -> Result<git::NestedRepoStatus, String> {
+    tauri::async_runtime::spawn_blocking(move || git::get_nested_status(&project_path, &repo_path))
+        .await
+        .map_err(|e| format!("Task join failed: {}", e))?
+}
+
+#[tauri::command]
+pub async fn list_git_submodules(project_path: String) -> Result<Vec<git::SubmoduleInfo>, String> {
+    tauri::async_runtime::spawn_blocking(move || git::list_submodules(&project_path))
+        .await
+        .map_err(|e| format!("Task join failed: {}", e))?
+}
+
+// `repo_path` scopes an operation to a nested repo or submodule inside the project
+
 #[tauri::command]
 pub async fn get_git_diff(
     project_path: String,
     file_path: String,
     status: String,
+    repo_path: Option<String>,
 ) -> Result<String, String> {
-    tauri::async_runtime::spawn_blocking(move || git::get_diff(&project_path, &file_path, &status))
+    let repo = git::scoped_repo(&project_path, repo_path.as_deref())?;
+    tauri::async_runtime::spawn_blocking(move || git::get_diff(&repo, &file_path, &status))
         .await
         .map_err(|e| format!("Task join failed: {}", e))?
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn git_commit(
+    app_handle: tauri::AppHandle,
+    git_status_cache: State<'_, GitStatusCache>,
     project_path: String,
     files: Vec<String>,
     message: String,
+    repo_path: Option<String>,
+    no_verify: Option<bool>,
+    on_event: Channel<git::CommitEvent>,
 ) -> Result<String, String> {
-    tauri::async_runtime::spawn_blocking(move || git::commit(&project_path, &files, &message))
+    let repo = git::scoped_repo(&project_path, repo_path.as_deref())?;
+    let options = config::commit_options(&app_handle, no_verify.unwrap_or(false));
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        git::commit(&repo, &files, &message, &options, &mut |event| {
+            let _ = on_event.send(event);
+        })
+    })
+    .await
+    .map_err(|e| format!("Task join failed: {}", e))?;
+    git_status_cache.invalidate(&project_path);
+    result
+}
+
+#[tauri::command]
+pub async fn git_revert(
+    git_status_cache: State<'_, GitStatusCache>,
+    project_path: String,
+    files: Vec<git::GitFileEntry>,
+    repo_path: Option<String>,
+) -> Result<(), String> {
+    let repo = git::scoped_repo(&project_path, repo_path.as_deref())?;
+    let result = tauri::async_runtime::spawn_blocking(move || git::revert(&repo, &files))
+        .await
+        .map_err(|e| format!("Task join failed: {}", e))?;
+    git_status_cache.invalidate(&project_path);
+    result
+}
+
+#[tauri::command]
+pub async fn git_reset(
+    git_status_cache: State<'_, GitStatusCache>,
+    project_path: String,
+    target: String,
+    mode: String,
+    repo_path: Option<String>,
+) -> Result<(), String> {
+    let repo = git::scoped_repo(&project_path, repo_path.as_deref())?;
+    let result = tauri::async_runtime::spawn_blocking(move || git::reset(&repo, &target, &mode))
+        .await
+        .map_err(|e| format!("Task join failed: {}", e))?;
+    git_status_cache.invalidate(&project_path);
+    result
+}
+
+#[tauri::command]
+pub async fn git_delete_branch(
+    project_path: String,
+    branch: String,
+    force: bool,
+    repo_path: Option<String>,
+) -> Result<(), String> {
+    let repo = git::scoped_repo(&project_path, repo_path.as_deref())?;
+    tauri::async_runtime::spawn_blocking(move || git::delete_branch(&repo, &branch, force))
         .await
         .map_err(|e| format!("Task join failed: {}", e))?
 }
 
+// revert, reset and branch deletion snapshot first; these walk that history back
+
 #[tauri::command]
-pub async fn git_revert(project_path: String, files: Vec<git::GitFileEntry>) -> Result<(), String> {
-    tauri::async_runtime::spawn_blocking(move || git::revert(&project_path, &files))
+pub async fn get_git_undo_history(
+    project_path: String,
+    repo_path: Option<String>,
+) -> Result<Vec<git_undo::UndoEntry>, String> {
+    let repo = git::scoped_repo(&project_path, repo_path.as_deref())?;
+    tauri::async_runtime::spawn_blocking(move || git_undo::history(&repo))
         .await
         .map_err(|e| format!("Task join failed: {}", e))?
 }
 
+#[tauri::command]
+pub async fn git_undo_last(
+    git_status_cache: State<'_, GitStatusCache>,
+    project_path: String,
+    repo_path: Option<String>,
+) -> Result<git_undo::UndoEntry, String> {
+    let repo = git::scoped_repo(&project_path, repo_path.as_deref())?;
+    let result = tauri::async_runtime::spawn_blocking(move || git_undo::undo_last(&repo))
+        .await
+        .map_err(|e| format!("Task join failed: {}", e))?;
+    git_status_cache.invalidate(&project_path);
+    result
+}
+
 #[tauri::command]
 pub async fn get_git_diff_stats(
     project_path: String,
     files: Vec<git::GitFileEntry>,
+    repo_path: Option<String>,
 ) -> Result<Vec<git::DiffStat>, String> {
-    tauri::async_runtime::spawn_blocking(move || git::get_diff_stats(&project_path, &files))
+    let repo = git::scoped_repo(&project_path, repo_path.as_deref())?;
+    tauri::async_runtime::spawn_blocking(move || git::get_diff_stats(&repo, &files))
         .await
         .map_err(|e| format!("Task join failed: {}", e))?
 }
 
 #[tauri::command]
-pub async fn git_push(project_path: String) -> Result<String, String> {
-    tauri::async_runtime::spawn_blocking(move || git::push(&project_path))
+pub async fn git_push(project_path: String, repo_path: Option<String>) -> Result<String, String> {
+    let repo = git::scoped_repo(&project_path, repo_path.as_deref())?;
+    tauri::async_runtime::spawn_blocking(move || git::push(&repo))
         .await
         .map_err(|e| format!("Task join failed: {}", e))?
 }
 
 #[tauri::command]
 pub async fn generate_commit_message(
+    app_handle: tauri::AppHandle,
     project_path: String,
     files: Vec<git::GitFileEntry>,
 ) -> Result<git::GenerateResult, String> {
+    let token_budget = config::diff_token_budget(&app_handle);
+    tauri::async_runtime::spawn_blocking(move || {
+        git::generate_commit_message(&project_path, &files, token_budget)
+    })
+    .await
+    .map_err(|e| format!("Task join failed: {}", e))?
+}
+
+#[tauri::command]
+pub async fn generate_pr_description(
+    app_handle: tauri::AppHandle,
+    project_path: String,
+    target_branch: String,
+) -> Result<git::GeneratePrResult, String> {
+    let token_budget = config::diff_token_budget(&app_handle);
     tauri::async_runtime::spawn_blocking(move || {
-        git::generate_commit_message(&project_path, &files)
+        git::generate_pr_description(&project_path, &target_branch, token_budget)
+    })
+    .await
+    .map_err(|e| format!("Task join failed: {}", e))?
+}
+
+#[tauri::command]
+pub async fn git_blame(
+    project_path: String,
+    file_path: String,
+    start_line: Option<u32>,
+    end_line: Option<u32>,
+) -> Result<Vec<git::BlameLine>, String> {
+    let range = start_line.zip(end_line);
+    tauri::async_runtime::spawn_blocking(move || git::blame(&project_path, &file_path, range))
+        .await
+        .map_err(|e| format!("Task join failed: {}", e))?
+}
+
+#[tauri::command]
+pub async fn git_line_history(
+    project_path: String,
+    file_path: String,
+    start_line: u32,
+    end_line: u32,
+) -> Result<Vec<git::LineHistoryEntry>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        git::line_history(&project_path, &file_path, start_line, end_line)
     })
     .await
     .map_err(|e| format!("Task join failed: {}", e))?
@@ -415,13 +646,26 @@ pub struct ClaudeMdFile {
     pub content: String,
 }
 
+/// Starts a chat, optionally resuming or forking a stored conversation.
 #[tauri::command]
 pub fn create_claude_session(
     claude_manager: State<'_, ClaudeManager>,
     project_path: String,
+    resume: Option<ResumeFrom>,
+    options: Option<SessionOptions>,
     on_event: Channel<ClaudeEvent>,
 ) -> Result<String, String> {
-    claude_manager.create_session(&project_path, on_event)
+    claude_manager.create_session(&project_path, resume, options.unwrap_or_default(), on_event)
+}
+
+/// Conversations stored for the project that a chat can resume, newest first.
+#[tauri::command]
+pub async fn list_claude_sessions(
+    project_path: String,
+) -> Result<Vec<conversation::SessionSummary>, String> {
+    tauri::async_runtime::spawn_blocking(move || conversation::list_sessions(&project_path))
+        .await
+        .map_err(|e| format!("Task join failed: {}", e))
 }
 
 #[tauri::command]
@@ -538,17 +782,58 @@ pub fn destroy_pi_session(
 
 #[tauri::command]
 pub async fn read_directory(
+    file_index: State<'_, RemoteFileIndex>,
     project_path: String,
     dir_path: Option<String>,
 ) -> Result<Vec<FileTreeEntry>, String> {
+    let file_index = file_index.enabled().then(|| file_index.inner().clone());
     tauri::async_runtime::spawn_blocking(move || match remote::locate(&project_path) {
         Location::Local(path) => read_directory_local(&path, dir_path),
-        Location::Remote(target) => read_directory_remote(&target, dir_path),
+        Location::Remote(target) => {
+            let rel_dir = dir_path.clone().unwrap_or_default();
+            let indexed = match &file_index {
+                Some(index) => index.read_directory(&project_path, &target, &rel_dir)?,
+                None => None,
+            };
+            match indexed {
+                Some(children) => Ok(indexed_entries(&target, &rel_dir, children)),
+                None => read_directory_remote(&target, dir_path),
+            }
+        }
     })
     .await
     .map_err(|e| format!("Task join failed: {}", e))?
 }
 
+/// Drops a remote project's cached file tree so the next listing fetches it again.
+#[tauri::command]
+pub fn refresh_remote_file_index(file_index: State<'_, RemoteFileIndex>, project_path: String) {
+    file_index.invalidate(&project_path);
+}
+
+/// Tree entries for a directory listed out of the remote file index.
+fn indexed_entries(
+    target: &SshTarget,
+    rel_dir: &str,
+    children: Vec<(String, bool)>,
+) -> Vec<FileTreeEntry> {
+    let root = target.path.trim_end_matches('/');
+    let entries = children
+        .into_iter()
+        .filter(|(name, _)| !HARDCODED_SKIP.contains(&name.as_str()))
+        .map(|(name, is_dir)| {
+            let rel_path = if rel_dir.is_empty() {
+                name.clone()
+            } else {
+                format!("{}/{}", rel_dir, name)
+            };
+            let full_path = target.url_for(&format!("{}/{}", root, rel_path));
+            (name, rel_path, full_path, is_dir)
+        })
+        .collect();
+    sort_entries(entries)
+}
+
 /// Lists a remote directory, honouring .gitignore the same way the local walk does.
 fn read_directory_remote(
     target: &SshTarget,
@@ -746,10 +1031,27 @@ fn read_directory_local(
 }
 
 #[tauri::command]
-pub async fn scan_project_files(project_path: String) -> Result<Vec<String>, String> {
+pub async fn scan_project_files(
+    file_index: State<'_, RemoteFileIndex>,
+    project_path: String,
+) -> Result<Vec<String>, String> {
+    let file_index = file_index.enabled().then(|| file_index.inner().clone());
     tauri::async_runtime::spawn_blocking(move || match remote::locate(&project_path) {
         Location::Local(path) => scan_project_files_local(&path),
-        Location::Remote(target) => scan_project_files_remote(&target),
+        Location::Remote(target) => {
+            let indexed = match &file_index {
+                Some(index) => index.files(&project_path, &target)?,
+                None => None,
+            };
+            match indexed {
+                Some(files) => Ok(files
+                    .into_iter()
+                    .filter(|f| !f.split('/').any(|part| HARDCODED_SKIP.contains(&part)))
+                    .take(SCAN_FILE_CAP)
+                    .collect()),
+                None => scan_project_files_remote(&target),
+            }
+        }
     })
     .await
     .map_err(|e| format!("Task join failed: {}", e))?
@@ -857,6 +1159,8 @@ pub struct RemoteSpec {
     pub key_path: Option<String>,
     #[serde(default)]
     pub password: Option<String>,
+    #[serde(default)]
+    pub passphrase: Option<String>,
 }
 
 #[derive(serde::Serialize)]
@@ -883,6 +1187,54 @@ pub fn save_remotes(
     Ok(())
 }
 
+/// Host aliases from `~/.ssh/config`, for the add-project dialog.
+#[tauri::command]
+pub fn list_ssh_hosts() -> Vec<ssh_config::SshHost> {
+    ssh_config::known_hosts()
+}
+
+#[tauri::command]
+pub fn get_remote_credentials_state() -> CredentialsState {
+    credentials::state()
+}
+
+/// Reads saved secrets out of the OS keyring; until then they stay locked.
+#[tauri::command]
+pub async fn unlock_remote_credentials(
+    app_handle: tauri::AppHandle,
+) -> Result<CredentialsState, String> {
+    tauri::async_runtime::spawn_blocking(move || config::unlock_credentials(&app_handle))
+        .await
+        .map_err(|e| format!("Task join failed: {}", e))?
+}
+
+/// Saves a remote's password and/or key passphrase to the OS keyring.
+#[tauri::command]
+pub async fn store_remote_credentials(
+    authority: String,
+    password: Option<String>,
+    passphrase: Option<String>,
+) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Some(password) = password {
+            credentials::store(&authority, Secret::Password, &password)?;
+        }
+        if let Some(passphrase) = passphrase {
+            credentials::store(&authority, Secret::Passphrase, &passphrase)?;
+        }
+        Ok(())
+    })
+    .await
+    .map_err(|e| format!("Task join failed: {}", e))?
+}
+
+#[tauri::command]
+pub async fn forget_remote_credentials(authority: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || credentials::forget(&authority))
+        .await
+        .map_err(|e| format!("Task join failed: {}", e))?
+}
+
 /// Browses directories on a remote host. Doubles as the connection test in the add-project dialog.
 #[tauri::command]
 pub async fn list_remote_dirs(
@@ -890,17 +1242,7 @@ pub async fn list_remote_dirs(
     path: Option<String>,
 ) -> Result<RemoteListing, String> {
     tauri::async_runtime::spawn_blocking(move || {
-        let mut target = remote::make_target(
-            spec.user,
-            spec.host,
-            spec.port,
-            spec.key_path,
-            path.unwrap_or_default(),
-        );
-        if spec.password.is_some() {
-            target.password = spec.password;
-            remote::remember_password(&target.authority, target.password.clone());
-        }
+        let target = spec_target(spec, path.unwrap_or_default());
         // pwd -W is the msys spelling that yields "C:/..." instead of "/c/..."
         let command = format!(
             "{} && (pwd -W 2>/dev/null || pwd -P) && (if [ -d .git ]; then echo 1; else echo 0; fi) && ls -Ap1",
@@ -931,20 +1273,219 @@ pub async fn list_remote_dirs(
     .map_err(|e| format!("Task join failed: {}", e))?
 }
 
+/// The target a connection form describes, remembering any secrets it carries for
+/// this run of the app.
+fn spec_target(spec: RemoteSpec, path: String) -> SshTarget {
+    let mut target = remote::make_target(spec.user, spec.host, spec.port, spec.key_path, path);
+    if spec.password.is_some() {
+        target.password = spec.password;
+        remote::remember_password(&target.authority, target.password.clone());
+    }
+    if spec.passphrase.is_some() {
+        target.passphrase = spec.passphrase;
+        remote::remember_passphrase(&target.authority, target.passphrase.clone());
+    }
+    target
+}
+
+/// Checks a host for the tools remote projects rely on, and what's degraded without them.
 #[tauri::command]
-pub fn save_clipboard_image(
+pub async fn diagnose_remote_host(spec: RemoteSpec) -> Result<HostDiagnosis, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        remote::diagnose(&spec_target(spec, String::new()))
+    })
+    .await
+    .map_err(|e| format!("Task join failed: {}", e))?
+}
+
+/// The "docker://" project url for the running dev container of a local folder.
+#[tauri::command]
+pub async fn resolve_devcontainer(local_path: String) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || devcontainer::resolve(&local_path))
+        .await
+        .map_err(|e| format!("Task join failed: {}", e))?
+}
+
+fn remote_target(path: &str) -> Result<SshTarget, String> {
+    match remote::locate(path) {
+        Location::Remote(target) => Ok(target),
+        Location::Local(_) => Err(format!("Not a remote path: {}", path)),
+    }
+}
+
+/// Copies a local file to an `ssh://` path, verified by checksum.
+#[tauri::command]
+pub async fn upload_remote_file(
+    local_path: String,
+    remote_path: String,
+    on_progress: Channel<TransferProgress>,
+) -> Result<(), String> {
+    let target = remote_target(&remote_path)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let data = std::fs::read(&local_path).map_err(|e| format!("Failed to read file: {}", e))?;
+        transfer::upload(&target, &data, &target.path, &mut |progress| {
+            let _ = on_progress.send(progress);
+        })
+    })
+    .await
+    .map_err(|e| format!("Task join failed: {}", e))?
+}
+
+/// Copies an `ssh://` file to a local path, verified by checksum.
+#[tauri::command]
+pub async fn download_remote_file(
+    remote_path: String,
+    local_path: String,
+    on_progress: Channel<TransferProgress>,
+) -> Result<(), String> {
+    let target = remote_target(&remote_path)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let data = transfer::download(&target, &target.path, &mut |progress| {
+            let _ = on_progress.send(progress);
+        })?;
+        // never leave a half-written file where the real one should be
+        let part = format!("{}.part", local_path);
+        std::fs::write(&part, &data).map_err(|e| format!("Failed to write file: {}", e))?;
+        std::fs::rename(&part, &local_path).map_err(|e| format!("Failed to write file: {}", e))
+    })
+    .await
+    .map_err(|e| format!("Task join failed: {}", e))?
+}
+
+/// Listening ports a remote project's own processes have opened.
+#[tauri::command]
+pub async fn detect_remote_ports(project_path: String) -> Result<Vec<ListeningPort>, String> {
+    let target = remote_target(&project_path)?;
+    // the ports menu polls this while open
+    tauri::async_runtime::spawn_blocking(move || {
+        remote::in_background(|| remote::listening_ports(&target))
+    })
+    .await
+    .map_err(|e| format!("Task join failed: {}", e))?
+}
+
+/// Opens a forward for a remote project; progress arrives as `port-forward-changed`.
+#[tauri::command]
+pub async fn open_port_forward(
     app_handle: tauri::AppHandle,
-    data: Vec<u8>,
-    mime_type: String,
-) -> Result<String, String> {
-    let ext = match mime_type.as_str() {
+    project_path: String,
+    forward: PortForward,
+) -> Result<(), String> {
+    let target = remote_target(&project_path)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        remote::open_forward(&project_path, &target, forward, move |status| {
+            let _ = app_handle.emit("port-forward-changed", status);
+        })
+    })
+    .await
+    .map_err(|e| format!("Task join failed: {}", e))?
+}
+
+#[tauri::command]
+pub async fn close_port_forward(
+    app_handle: tauri::AppHandle,
+    project_path: String,
+    forward: PortForward,
+) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Some(status) = remote::close_forward(&project_path, &forward) {
+            let _ = app_handle.emit("port-forward-changed", status);
+        }
+    })
+    .await
+    .map_err(|e| format!("Task join failed: {}", e))
+}
+
+#[tauri::command]
+pub fn list_port_forwards(project_path: String) -> Vec<ForwardStatus> {
+    remote::list_forwards(&project_path)
+}
+
+/// Sessions the app left running in tmux/dtach on a remote project's host.
+#[tauri::command]
+pub async fn list_detached_sessions(project_path: String) -> Result<Vec<DetachedSession>, String> {
+    let target = remote_target(&project_path)?;
+    tauri::async_runtime::spawn_blocking(move || remote::list_detached_sessions(&target))
+        .await
+        .map_err(|e| format!("Task join failed: {}", e))?
+}
+
+#[tauri::command]
+pub async fn kill_detached_session(project_path: String, name: String) -> Result<(), String> {
+    remote::check_detached_name(&name)?;
+    let target = remote_target(&project_path)?;
+    tauri::async_runtime::spawn_blocking(move || remote::kill_detached_session(&target, &name))
+        .await
+        .map_err(|e| format!("Task join failed: {}", e))?
+}
+
+/// Fails commands still queued for a channel to the project's host.
+#[tauri::command]
+pub fn cancel_remote_commands(project_path: String) -> Result<(), String> {
+    let target = remote_target(&project_path)?;
+    remote::cancel_queued(&target.authority);
+    Ok(())
+}
+
+/// Timeout for `run_remote_command` when the caller doesn't pick one.
+const REMOTE_RUN_TIMEOUT_SECS: u64 = 30 * 60;
+
+/// Runs a shell command in a remote project, streaming its output to `on_output`.
+/// `cancel_remote_run` with the same `run_id` stops it.
+#[tauri::command]
+pub async fn run_remote_command(
+    project_path: String,
+    command: String,
+    run_id: String,
+    timeout_secs: Option<u64>,
+    on_output: Channel<StreamChunk>,
+) -> Result<StreamEnd, String> {
+    let target = remote_target(&project_path)?;
+    let timeout = std::time::Duration::from_secs(timeout_secs.unwrap_or(REMOTE_RUN_TIMEOUT_SECS));
+    // registered up front so a cancel sent right after the invoke finds the run
+    let cancel = remote::begin_run(&run_id);
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let forwarder = std::thread::spawn(move || {
+            for chunk in rx {
+                let _ = on_output.send(chunk);
+            }
+        });
+        let result =
+            remote::run_stream(&target, Some(&target.path), &command, timeout, &tx, &cancel);
+        // every chunk reaches the frontend before the end does
+        drop(tx);
+        let _ = forwarder.join();
+        result
+    })
+    .await;
+    remote::end_run(&run_id);
+    result.map_err(|e| format!("Task join failed: {}", e))?
+}
+
+#[tauri::command]
+pub fn cancel_remote_run(run_id: String) -> bool {
+    remote::cancel_run(&run_id)
+}
+
+fn screenshot_extension(mime_type: &str) -> &'static str {
+    match mime_type {
         "image/png" => "png",
         "image/jpeg" | "image/jpg" => "jpg",
         "image/gif" => "gif",
         "image/webp" => "webp",
         "image/bmp" => "bmp",
         _ => "png",
-    };
+    }
+}
+
+#[tauri::command]
+pub fn save_clipboard_image(
+    app_handle: tauri::AppHandle,
+    data: Vec<u8>,
+    mime_type: String,
+) -> Result<String, String> {
+    let ext = screenshot_extension(&mime_type);
 
     let dir = config::screenshots_dir(&app_handle);
     std::fs::create_dir_all(&dir)
@@ -965,6 +1506,43 @@ pub fn save_clipboard_image(
     Ok(path.to_string_lossy().to_string())
 }
 
+/// Like `save_clipboard_image`, but for a session's project: on an `ssh://` project the
+/// image is uploaded to a temp dir on the host and the remote path is returned. A wsl
+/// distro reads the local file through its windows mount instead.
+#[tauri::command]
+pub async fn save_clipboard_image_for_project(
+    app_handle: tauri::AppHandle,
+    project_path: String,
+    data: Vec<u8>,
+    mime_type: String,
+) -> Result<String, String> {
+    let target = match remote::locate(&project_path) {
+        Location::Local(_) => return save_clipboard_image(app_handle, data, mime_type),
+        Location::Remote(target) if target.transport == Transport::Wsl => {
+            let local = save_clipboard_image(app_handle, data, mime_type)?;
+            return remote::wsl_mount_path(&local)
+                .ok_or_else(|| format!("No wsl path for {}", local));
+        }
+        Location::Remote(target) => target,
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        let dir = transfer::remote_screenshots_dir(&target)?;
+        let now = chrono::Local::now();
+        // the remote dir can't be cheaply checked for collisions, so always add a suffix
+        let name = format!(
+            "{}_{}.{}",
+            now.format("screenshot_%Y-%m-%d_%H%M%S"),
+            &uuid::Uuid::new_v4().simple().to_string()[..8],
+            screenshot_extension(&mime_type)
+        );
+        let path = format!("{}/{}", dir, name);
+        transfer::upload(&target, &data, &path, &mut |_| {})?;
+        Ok(path)
+    })
+    .await
+    .map_err(|e| format!("Task join failed: {}", e))?
+}
+
 #[tauri::command]
 pub fn exit_app(app_handle: tauri::AppHandle) {
     app_handle.exit(0);
@@ -972,30 +1550,30 @@ pub fn exit_app(app_handle: tauri::AppHandle) {
 
 // --- File watcher commands ---
 
-// remote files have nothing to watch — the notify backend is local only
+// remote watches start an ssh process, so both sides run off the main thread
 
 #[tauri::command]
-pub fn watch_file(
+pub async fn watch_file(
     file_watcher: State<'_, FileWatcherManager>,
     tab_id: String,
     file_path: String,
 ) -> Result<(), String> {
-    if remote::is_remote(&file_path) {
-        return Ok(());
-    }
-    file_watcher.watch_file(&tab_id, &file_path)
+    let fw = file_watcher.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || fw.watch_file(&tab_id, &file_path))
+        .await
+        .map_err(|e| format!("Task join failed: {}", e))?
 }
 
 #[tauri::command]
-pub fn unwatch_file(
+pub async fn unwatch_file(
     file_watcher: State<'_, FileWatcherManager>,
     tab_id: String,
     file_path: String,
 ) -> Result<(), String> {
-    if remote::is_remote(&file_path) {
-        return Ok(());
-    }
-    file_watcher.unwatch_file(&tab_id, &file_path)
+    let fw = file_watcher.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || fw.unwatch_file(&tab_id, &file_path))
+        .await
+        .map_err(|e| format!("Task join failed: {}", e))?
 }
 
 // --- Whisper STT commands ---
@@ -15,6 +15,9 @@ pub struct ProjectConfig {
     pub theme: String,
     #[serde(default)]
     pub pinned: bool,
+    /// Remote sessions run inside tmux/dtach on the host so they survive disconnects.
+    #[serde(default)]
+    pub persistent_sessions: bool,
 }
 
 pub fn config_dir(app_handle: &tauri::AppHandle) -> PathBuf {
@@ -73,6 +76,15 @@ pub struct RemoteConfig {
     pub authority: String,
     #[serde(default)]
     pub key_path: Option<String>,
+    /// Forwards offered for every project on this host.
+    #[serde(default)]
+    pub forwards: Vec<crate::remote::PortForward>,
+    /// Extra ssh `-o` options, e.g. "ForwardAgent=yes".
+    #[serde(default)]
+    pub ssh_options: Vec<String>,
+    /// Command channels kept open to the host; unset means the default.
+    #[serde(default)]
+    pub channels: Option<usize>,
 }
 
 fn remotes_path(app_handle: &tauri::AppHandle) -> PathBuf {
@@ -106,6 +118,9 @@ pub fn sync_remotes(app_handle: &tauri::AppHandle) {
                 crate::remote::RemoteInfo {
                     key_path: r.key_path,
                     password: None,
+                    passphrase: None,
+                    ssh_options: r.ssh_options,
+                    channels: r.channels,
                 },
             )
         })
@@ -113,6 +128,21 @@ pub fn sync_remotes(app_handle: &tauri::AppHandle) {
     crate::remote::set_remotes(entries);
 }
 
+/// Reads saved secrets for every known remote out of the OS keyring.
+pub fn unlock_credentials(
+    app_handle: &tauri::AppHandle,
+) -> Result<crate::credentials::CredentialsState, String> {
+    let authorities: Vec<String> = load_remotes(app_handle)
+        .into_iter()
+        .map(|r| r.authority)
+        .collect();
+    crate::credentials::unlock(&authorities)
+}
+
+pub fn unlock_credentials_on_start(app_handle: &tauri::AppHandle) -> bool {
+    load_settings(app_handle).is_some_and(|s| s.unlock_credentials_on_start)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PinnedFileConfig {
     pub path: String,
@@ -288,6 +318,22 @@ pub struct SettingsConfig {
     pub default_session_type: String,
     #[serde(default)]
     pub session_types: Vec<SessionTypeConfigRust>,
+    #[serde(default = "default_diff_token_budget")]
+    pub diff_token_budget: usize,
+    #[serde(default)]
+    pub sign_commits: bool,
+    #[serde(default)]
+    pub signing_key: Option<String>,
+    /// Read saved remote credentials from the OS keyring at launch instead of on request.
+    #[serde(default)]
+    pub unlock_credentials_on_start: bool,
+    /// Fetch a remote project's whole file tree at once instead of a directory at a time.
+    #[serde(default = "default_remote_file_index")]
+    pub remote_file_index: bool,
+}
+
+fn default_remote_file_index() -> bool {
+    true
 }
 
 fn default_notes_panel_width() -> f64 {
@@ -298,6 +344,31 @@ fn default_session_type() -> String {
     "claude".to_string()
 }
 
+fn default_diff_token_budget() -> usize {
+    crate::git::DEFAULT_DIFF_TOKEN_BUDGET
+}
+
+/// Token budget for diffs fed into generated commit and PR messages.
+pub fn diff_token_budget(app_handle: &tauri::AppHandle) -> usize {
+    load_settings(app_handle)
+        .map(|s| s.diff_token_budget)
+        .unwrap_or_else(default_diff_token_budget)
+}
+
+pub fn remote_file_index(app_handle: &tauri::AppHandle) -> bool {
+    load_settings(app_handle).is_none_or(|s| s.remote_file_index)
+}
+
+/// Signing choice for commits made from the app; hooks are skipped per commit, not here.
+pub fn commit_options(app_handle: &tauri::AppHandle, no_verify: bool) -> crate::git::CommitOptions {
+    let settings = load_settings(app_handle);
+    crate::git::CommitOptions {
+        no_verify,
+        sign: settings.as_ref().is_some_and(|s| s.sign_commits),
+        signing_key: settings.and_then(|s| s.signing_key),
+    }
+}
+
 fn settings_path(app_handle: &tauri::AppHandle) -> PathBuf {
     config_dir(app_handle).join("settings.json")
 }
@@ -1,7 +1,8 @@
 use serde::Serialize;
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
 use std::time::UNIX_EPOCH;
 
 #[derive(Serialize)]
@@ -15,11 +16,23 @@ pub struct ConversationResponse {
 #[serde(rename_all = "camelCase")]
 pub struct ConversationMessage {
     pub uuid: String,
+    /// The transcript entry's own uuid, which a fork starts from.
+    pub entry_uuid: String,
     pub role: String,
     pub text: String,
     pub timestamp: String,
 }
 
+/// A stored conversation that a new chat can resume or fork.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSummary {
+    pub session_id: String,
+    /// The summary Claude Code wrote after compacting, else the opening prompt.
+    pub title: String,
+    pub last_modified: f64,
+}
+
 /// Encode a project directory path the way Claude Code does:
 /// replace every non-alphanumeric character with `-`.
 pub(crate) fn encode_project_dir(path: &str) -> String {
@@ -28,13 +41,17 @@ pub(crate) fn encode_project_dir(path: &str) -> String {
         .collect()
 }
 
+fn project_dir(project_path: &str) -> Option<PathBuf> {
+    let home = dirs::home_dir()?;
+    let encoded = encode_project_dir(project_path);
+    Some(home.join(".claude").join("projects").join(encoded))
+}
+
 /// Find the JSONL conversation file for a project.
 /// If session_id is provided, build the deterministic path.
 /// Otherwise, find the most recently modified `.jsonl` in the directory.
 fn find_jsonl_path(project_path: &str, session_id: Option<&str>) -> Option<PathBuf> {
-    let home = dirs::home_dir()?;
-    let encoded = encode_project_dir(project_path);
-    let dir = home.join(".claude").join("projects").join(&encoded);
+    let dir = project_dir(project_path)?;
 
     if let Some(sid) = session_id {
         let path = dir.join(format!("{}.jsonl", sid));
@@ -132,6 +149,12 @@ pub fn read_conversation(
             None => continue,
         };
 
+        let entry_uuid = val
+            .get("uuid")
+            .and_then(|u| u.as_str())
+            .unwrap_or(&uuid)
+            .to_string();
+
         // Extract timestamp
         let timestamp = val
             .get("timestamp")
@@ -174,6 +197,7 @@ pub fn read_conversation(
             uuid.clone(),
             ConversationMessage {
                 uuid,
+                entry_uuid,
                 role: role.to_string(),
                 text,
                 timestamp,
@@ -191,3 +215,110 @@ pub fn read_conversation(
         last_modified,
     })
 }
+
+/// Text of a transcript line that opens a conversation: a user prompt typed by a person,
+/// not a tool result or a subagent's.
+fn opening_prompt(val: &serde_json::Value) -> Option<&str> {
+    if val.get("type").and_then(|t| t.as_str()) != Some("user")
+        || val.get("isSidechain").and_then(|v| v.as_bool()) == Some(true)
+        || val.get("isMeta").and_then(|v| v.as_bool()) == Some(true)
+    {
+        return None;
+    }
+    let content = val.get("message")?.get("content")?;
+    let text = content.as_str().or_else(|| {
+        content.as_array()?.iter().find_map(|item| {
+            if item.get("type").and_then(|t| t.as_str()) == Some("text") {
+                item.get("text").and_then(|t| t.as_str())
+            } else {
+                None
+            }
+        })
+    })?;
+    Some(text).filter(|t| !t.trim().is_empty())
+}
+
+/// Reads a transcript only as far as its title: summaries come first in the file, and
+/// the opening prompt is the fallback.
+fn summarize(path: &Path) -> Option<SessionSummary> {
+    let session_id = path.file_stem()?.to_str()?.to_string();
+    let mtime = fs::metadata(path).ok()?.modified().ok()?;
+    let last_modified = mtime.duration_since(UNIX_EPOCH).ok()?.as_secs_f64() * 1000.0;
+
+    let file = fs::File::open(path).ok()?;
+    let mut summary: Option<String> = None;
+    for line in BufReader::new(file).lines() {
+        let Ok(line) = line else { break };
+        let Ok(val) = serde_json::from_str::<serde_json::Value>(&line) else {
+            continue;
+        };
+        if val.get("type").and_then(|t| t.as_str()) == Some("summary") {
+            summary = val
+                .get("summary")
+                .and_then(|s| s.as_str())
+                .map(|s| s.to_string());
+            continue;
+        }
+        if let Some(prompt) = opening_prompt(&val) {
+            let title = summary.unwrap_or_else(|| prompt.lines().next().unwrap_or("").to_string());
+            return Some(SessionSummary {
+                session_id,
+                title,
+                last_modified,
+            });
+        }
+    }
+    // nothing a person typed: a subagent's transcript or an empty session
+    None
+}
+
+/// Conversations stored for a project, newest first.
+pub fn list_sessions(project_path: &str) -> Vec<SessionSummary> {
+    let Some(entries) = project_dir(project_path).and_then(|dir| fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+    let mut sessions: Vec<SessionSummary> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("jsonl"))
+        .filter_map(|path| summarize(&path))
+        .collect();
+    sessions.sort_by(|a, b| b.last_modified.total_cmp(&a.last_modified));
+    sessions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn titles_a_session_by_summary_or_opening_prompt() {
+        let dir = std::env::temp_dir().join(format!("cc-sessions-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let plain = dir.join("plain.jsonl");
+        let lines = [
+            r#"{"type":"user","isMeta":true,"message":{"content":"<command-name>"}}"#,
+            r#"{"type":"user","message":{"content":[{"type":"text","text":"fix the build\nit fails"}]}}"#,
+        ];
+        fs::write(&plain, lines.join("\n")).unwrap();
+        let summary = summarize(&plain).unwrap();
+        assert_eq!(summary.session_id, "plain");
+        assert_eq!(summary.title, "fix the build");
+
+        let compacted = dir.join("compacted.jsonl");
+        let lines = [
+            r#"{"type":"summary","summary":"Build fix"}"#,
+            r#"{"type":"user","message":{"content":"fix the build"}}"#,
+        ];
+        fs::write(&compacted, lines.join("\n")).unwrap();
+        assert_eq!(summarize(&compacted).unwrap().title, "Build fix");
+
+        let subagent = dir.join("agent-1.jsonl");
+        let line = r#"{"type":"user","isSidechain":true,"message":{"content":"search"}}"#;
+        fs::write(&subagent, line).unwrap();
+        assert!(summarize(&subagent).is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
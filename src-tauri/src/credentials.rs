@@ -0,0 +1,105 @@
+// remote passwords and key passphrases kept in the OS keyring (Secret Service, Keychain,
+// Credential Manager) so they survive restarts. Stored secrets stay locked — unread —
+// until the user unlocks them, since reading may pop a keyring prompt of its own.
+use crate::remote;
+use serde::Serialize;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+const SERVICE: &str = "circuitclaude";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Secret {
+    Password,
+    Passphrase,
+}
+
+impl Secret {
+    const ALL: [Secret; 2] = [Secret::Password, Secret::Passphrase];
+
+    fn account(self, authority: &str) -> String {
+        match self {
+            Secret::Password => format!("password:{}", authority),
+            Secret::Passphrase => format!("passphrase:{}", authority),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialsState {
+    pub unlocked: bool,
+    /// Authorities with anything in the keyring; only known once unlocked.
+    pub stored: Vec<String>,
+}
+
+fn lock_state() -> MutexGuard<'static, CredentialsState> {
+    static STATE: OnceLock<Mutex<CredentialsState>> = OnceLock::new();
+    match STATE.get_or_init(Default::default).lock() {
+        Ok(state) => state,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+pub fn state() -> CredentialsState {
+    lock_state().clone()
+}
+
+fn entry(authority: &str, secret: Secret) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(SERVICE, &secret.account(authority))
+        .map_err(|e| format!("Keyring unavailable: {}", e))
+}
+
+fn read(authority: &str, secret: Secret) -> Result<Option<String>, String> {
+    match entry(authority, secret)?.get_password() {
+        Ok(value) => Ok(Some(value)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Failed to read keyring: {}", e)),
+    }
+}
+
+/// Saves a secret for an authority and makes it usable right away.
+pub fn store(authority: &str, secret: Secret, value: &str) -> Result<(), String> {
+    entry(authority, secret)?
+        .set_password(value)
+        .map_err(|e| format!("Failed to write keyring: {}", e))?;
+    match secret {
+        Secret::Password => remote::remember_password(authority, Some(value.to_string())),
+        Secret::Passphrase => remote::remember_passphrase(authority, Some(value.to_string())),
+    }
+    let mut state = lock_state();
+    if !state.stored.iter().any(|a| a == authority) {
+        state.stored.push(authority.to_string());
+    }
+    Ok(())
+}
+
+/// Removes an authority's secrets from the keyring and from memory.
+pub fn forget(authority: &str) -> Result<(), String> {
+    remote::forget_secrets(authority);
+    for secret in Secret::ALL {
+        match entry(authority, secret)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => {}
+            Err(e) => return Err(format!("Failed to delete from keyring: {}", e)),
+        }
+    }
+    lock_state().stored.retain(|a| a != authority);
+    Ok(())
+}
+
+/// Loads every stored secret for `authorities` into the remote registry.
+pub fn unlock(authorities: &[String]) -> Result<CredentialsState, String> {
+    let mut stored = Vec::new();
+    for authority in authorities {
+        let password = read(authority, Secret::Password)?;
+        let passphrase = read(authority, Secret::Passphrase)?;
+        if password.is_some() || passphrase.is_some() {
+            stored.push(authority.clone());
+        }
+        remote::remember_password(authority, password);
+        remote::remember_passphrase(authority, passphrase);
+    }
+    let mut state = lock_state();
+    state.unlocked = true;
+    state.stored = stored;
+    Ok(state.clone())
+}
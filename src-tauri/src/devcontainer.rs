@@ -0,0 +1,123 @@
+// dev containers: a local folder with a running container from the devcontainer CLI (or
+// an editor built on it) opens as the matching "docker://" project
+use crate::remote::{self, DOCKER_SCHEME};
+use serde_json::Value;
+
+/// Where the devcontainer CLI mounts the folder when the config doesn't say otherwise.
+const DEFAULT_WORKSPACE_ROOT: &str = "/workspaces";
+
+/// Lowercase, forward slashes, no drive colon or leading slash, so a windows folder
+/// compares equal to the path Docker Desktop mounts it from.
+fn normalize(path: &str) -> String {
+    path.replace('\\', "/")
+        .replace(':', "")
+        .trim_matches('/')
+        .to_lowercase()
+}
+
+/// The "docker://" url for `local_folder` out of `docker inspect` output, if one of the
+/// containers was started for it.
+fn parse_inspect(inspect: &str, local_folder: &str) -> Option<String> {
+    let folder = normalize(local_folder);
+    let containers: Vec<Value> = serde_json::from_str(inspect).ok()?;
+    let container = containers.iter().find(|c| {
+        c["Config"]["Labels"]["devcontainer.local_folder"]
+            .as_str()
+            .is_some_and(|label| normalize(label) == folder)
+    })?;
+    let name = container["Name"].as_str()?.trim_start_matches('/');
+
+    let workspace = container["Mounts"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|m| {
+            let source = m["Source"].as_str().map(normalize).unwrap_or_default();
+            source == folder || source.ends_with(&format!("/{}", folder))
+        })
+        .and_then(|m| m["Destination"].as_str())
+        .map(str::to_string)
+        .unwrap_or_else(|| {
+            let base = folder.rsplit('/').next().unwrap_or_default();
+            format!("{}/{}", DEFAULT_WORKSPACE_ROOT, base)
+        });
+
+    // later entries in the merged metadata override earlier ones
+    let metadata: Vec<Value> = container["Config"]["Labels"]["devcontainer.metadata"]
+        .as_str()
+        .and_then(|m| serde_json::from_str(m).ok())
+        .unwrap_or_default();
+    let user = ["remoteUser", "containerUser"].iter().find_map(|key| {
+        metadata
+            .iter()
+            .rev()
+            .find_map(|entry| entry[*key].as_str().map(str::to_string))
+    });
+
+    Some(match user {
+        Some(user) => format!("{}{}@{}{}", DOCKER_SCHEME, user, name, workspace),
+        None => format!("{}{}{}", DOCKER_SCHEME, name, workspace),
+    })
+}
+
+/// The project url of the running dev container for `local_folder`.
+pub fn resolve(local_folder: &str) -> Result<String, String> {
+    let ids = remote::run_local(
+        ".",
+        "docker",
+        &["ps", "-q", "--filter", "label=devcontainer.local_folder"],
+        &[],
+    )?;
+    if !ids.ok {
+        return Err(format!("docker ps failed: {}", ids.err_text()));
+    }
+    let ids: Vec<&str> = ids.stdout.split_whitespace().collect();
+    let not_running = || format!("No running dev container for {}", local_folder);
+    if ids.is_empty() {
+        return Err(not_running());
+    }
+
+    let mut args = vec!["inspect"];
+    args.extend(&ids);
+    let inspect = remote::run_local(".", "docker", &args, &[])?;
+    if !inspect.ok {
+        return Err(format!("docker inspect failed: {}", inspect.err_text()));
+    }
+    parse_inspect(&inspect.stdout, local_folder).ok_or_else(not_running)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_container_started_for_a_folder() {
+        let inspect = r#"[
+            {
+                "Name": "/other",
+                "Config": {"Labels": {"devcontainer.local_folder": "/home/me/else"}},
+                "Mounts": []
+            },
+            {
+                "Name": "/eager_turing",
+                "Config": {"Labels": {
+                    "devcontainer.local_folder": "c:\\Users\\me\\app",
+                    "devcontainer.metadata": "[{\"containerUser\":\"root\"},{\"remoteUser\":\"node\"}]"
+                }},
+                "Mounts": [
+                    {"Source": "/var/lib/docker/volumes/x", "Destination": "/vscode"},
+                    {"Source": "/run/desktop/mnt/host/c/Users/me/app", "Destination": "/src/app"}
+                ]
+            }
+        ]"#;
+        assert_eq!(
+            parse_inspect(inspect, "C:/Users/me/app").as_deref(),
+            Some("docker://node@eager_turing/src/app")
+        );
+        assert_eq!(
+            parse_inspect(inspect, "/home/me/else").as_deref(),
+            Some("docker://other/workspaces/else")
+        );
+        assert_eq!(parse_inspect(inspect, "/home/me/missing"), None);
+    }
+}
@@ -0,0 +1,322 @@
+// fits multi-file diffs into a model prompt without silently dropping whole files
+use serde::Serialize;
+
+/// Rough chars-per-token for code and diffs; close enough to budget a prompt.
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Files whose diff carries no signal worth prompt space — only their stat line is kept.
+const COLLAPSED_NAMES: &[&str] = &[
+    "Cargo.lock",
+    "package-lock.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "bun.lockb",
+    "poetry.lock",
+    "Pipfile.lock",
+    "composer.lock",
+    "Gemfile.lock",
+    "go.sum",
+    "flake.lock",
+];
+
+const COLLAPSED_SUFFIXES: &[&str] = &[".min.js", ".min.css", ".map", ".snap", ".lock"];
+
+const COLLAPSED_DIRS: &[&str] = &["dist", "build", "generated", "vendor", "node_modules"];
+
+fn estimate_tokens(text: &str) -> usize {
+    text.len().div_ceil(CHARS_PER_TOKEN)
+}
+
+/// One file's slice of a diff.
+pub struct FileDiff {
+    pub path: String,
+    pub text: String,
+    pub insertions: u32,
+    pub deletions: u32,
+}
+
+impl FileDiff {
+    pub fn new(path: String, text: String) -> FileDiff {
+        let mut insertions = 0;
+        let mut deletions = 0;
+        for line in text.lines() {
+            if line.starts_with('+') && !line.starts_with("+++") {
+                insertions += 1;
+            } else if line.starts_with('-') && !line.starts_with("---") {
+                deletions += 1;
+            }
+        }
+        FileDiff {
+            path,
+            text,
+            insertions,
+            deletions,
+        }
+    }
+
+    fn stat_line(&self) -> String {
+        format!("{} (+{} -{})", self.path, self.insertions, self.deletions)
+    }
+
+    fn is_collapsed(&self) -> bool {
+        let name = self.path.rsplit('/').next().unwrap_or(&self.path);
+        COLLAPSED_NAMES.contains(&name)
+            || COLLAPSED_SUFFIXES.iter().any(|s| name.ends_with(s))
+            || self
+                .path
+                .split('/')
+                .rev()
+                .skip(1)
+                .any(|dir| COLLAPSED_DIRS.contains(&dir))
+            || self.text.contains("\nBinary files ")
+            || self.text.starts_with("Binary files ")
+    }
+
+    /// File headers and `@@` hunk lines only — where the change is, without the body.
+    fn hunk_outline(&self) -> String {
+        let mut out = String::new();
+        for line in self.text.lines() {
+            if line.starts_with("@@")
+                || line.starts_with("diff --git ")
+                || line.starts_with("--- ")
+                || line.starts_with("+++ ")
+            {
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+        out
+    }
+
+    /// Higher is more worth keeping in full. Source changes beat docs and config, and within
+    /// a kind smaller focused diffs beat sprawling ones, which get outlined first.
+    fn significance(&self) -> i64 {
+        let changed = (self.insertions + self.deletions) as i64;
+        let weight = match self.path.rsplit('.').next().unwrap_or("") {
+            "md" | "txt" | "rst" => 1,
+            "json" | "yaml" | "yml" | "toml" | "ini" | "csv" | "svg" => 1,
+            _ => 3,
+        };
+        let is_test = is_test_path(&self.path);
+        let weight = if is_test { weight - 1 } else { weight };
+        weight * 10_000 - changed.min(9_999)
+    }
+}
+
+/// Test files by their directory ("tests/", "__tests__/") or name ("x_test.go",
+/// "x.spec.ts", "test_x.py"), so "latest.rs" or "attestation/" don't count.
+fn is_test_path(path: &str) -> bool {
+    let mut segments = path.split('/');
+    let name = segments.next_back().unwrap_or("");
+    let in_test_dir =
+        segments.any(|dir| matches!(dir, "test" | "tests" | "spec" | "specs" | "__tests__"));
+    in_test_dir
+        || name.starts_with("test_")
+        || ["_test.", ".test.", "_spec.", ".spec."]
+            .iter()
+            .any(|marker| name.contains(marker))
+}
+
+/// Splits a multi-file `git diff` into per-file sections at each `diff --git` header.
+pub fn split_file_diffs(diff: &str) -> Vec<FileDiff> {
+    let mut sections: Vec<(String, String)> = Vec::new();
+    for line in diff.split_inclusive('\n') {
+        if let Some(rest) = line.strip_prefix("diff --git ") {
+            // "a/src/x.rs b/src/x.rs" — the b side is the post-change path
+            let path = rest
+                .trim_end()
+                .rsplit_once(" b/")
+                .map(|(_, b)| b.to_string())
+                .unwrap_or_else(|| rest.trim_end().to_string());
+            sections.push((path, String::new()));
+        }
+        if let Some((_, text)) = sections.last_mut() {
+            text.push_str(line);
+        }
+    }
+    sections
+        .into_iter()
+        .map(|(path, text)| FileDiff::new(path, text))
+        .collect()
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum Detail {
+    /// Only the `@@` hunk headers made it into the prompt.
+    Hunks,
+    /// Only the stat line made it into the prompt.
+    Stat,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SummarisedFile {
+    pub path: String,
+    pub detail: Detail,
+}
+
+pub struct CondensedDiff {
+    pub text: String,
+    /// Files that went in as less than their full diff.
+    pub summarised: Vec<SummarisedFile>,
+}
+
+/// Fits the diffs into `token_budget`. Every file keeps a stat line up front; then, most
+/// significant first, each file goes in whole if it fits, else as a hunk outline, else
+/// stat-only. Lockfiles and generated files are always stat-only.
+pub fn condense(files: Vec<FileDiff>, token_budget: usize) -> CondensedDiff {
+    let total: usize = files.iter().map(|f| estimate_tokens(&f.text)).sum();
+    if total <= token_budget && !files.iter().any(|f| f.is_collapsed()) {
+        return CondensedDiff {
+            text: files.iter().map(|f| f.text.as_str()).collect(),
+            summarised: Vec::new(),
+        };
+    }
+
+    let mut header = String::from("Changed files:\n");
+    for f in &files {
+        header.push_str("- ");
+        header.push_str(&f.stat_line());
+        header.push('\n');
+    }
+    header.push('\n');
+    let mut used = estimate_tokens(&header);
+
+    let mut ranked: Vec<&FileDiff> = files.iter().collect();
+    ranked.sort_by_key(|f| std::cmp::Reverse(f.significance()));
+
+    let mut body = String::new();
+    let mut summarised: Vec<SummarisedFile> = Vec::new();
+    for f in ranked {
+        if f.is_collapsed() {
+            summarised.push(SummarisedFile {
+                path: f.path.clone(),
+                detail: Detail::Stat,
+            });
+            continue;
+        }
+        let full = estimate_tokens(&f.text);
+        if used + full <= token_budget {
+            body.push_str(&f.text);
+            used += full;
+            continue;
+        }
+        let outline = f.hunk_outline();
+        let outline_tokens = estimate_tokens(&outline);
+        if !outline.is_empty() && used + outline_tokens <= token_budget {
+            body.push_str(&outline);
+            used += outline_tokens;
+            summarised.push(SummarisedFile {
+                path: f.path.clone(),
+                detail: Detail::Hunks,
+            });
+        } else {
+            summarised.push(SummarisedFile {
+                path: f.path.clone(),
+                detail: Detail::Stat,
+            });
+        }
+    }
+
+    if !summarised.is_empty() {
+        body.push_str(&format!(
+            "\n... ({} files shortened to fit; see the list above)\n",
+            summarised.len()
+        ));
+    }
+
+    CondensedDiff {
+        text: header + &body,
+        summarised,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file_diff(path: &str, added: usize) -> FileDiff {
+        let mut text = format!(
+            "diff --git a/{p} b/{p}\n--- a/{p}\n+++ b/{p}\n@@ -1,0 +1,{n} @@ fn main()\n",
+            p = path,
+            n = added
+        );
+        for i in 0..added {
+            text.push_str(&format!("+line {}\n", i));
+        }
+        FileDiff::new(path.to_string(), text)
+    }
+
+    #[test]
+    fn splits_a_combined_diff_per_file() {
+        let combined = format!(
+            "{}{}",
+            file_diff("src/a.rs", 2).text,
+            file_diff("docs/b.md", 3).text
+        );
+        let files = split_file_diffs(&combined);
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, "src/a.rs");
+        assert_eq!(files[0].insertions, 2);
+        assert_eq!(files[1].path, "docs/b.md");
+        assert_eq!(files[1].insertions, 3);
+    }
+
+    #[test]
+    fn small_diffs_pass_through_untouched() {
+        let f = file_diff("src/a.rs", 3);
+        let expected = f.text.clone();
+        let out = condense(vec![f], 10_000);
+        assert_eq!(out.text, expected);
+        assert!(out.summarised.is_empty());
+    }
+
+    #[test]
+    fn lockfiles_collapse_to_a_stat_line() {
+        let out = condense(
+            vec![file_diff("src/a.rs", 3), file_diff("Cargo.lock", 50)],
+            10_000,
+        );
+        assert!(out.text.contains("Cargo.lock (+50 -0)"));
+        assert!(!out.text.contains("diff --git a/Cargo.lock"));
+        assert_eq!(out.summarised.len(), 1);
+        assert_eq!(out.summarised[0].path, "Cargo.lock");
+        assert_eq!(out.summarised[0].detail, Detail::Stat);
+    }
+
+    #[test]
+    fn over_budget_files_keep_their_hunk_headers() {
+        let out = condense(
+            vec![file_diff("src/small.rs", 2), file_diff("src/huge.rs", 2_000)],
+            200,
+        );
+        assert!(out.text.contains("+line 1"));
+        assert!(out.text.contains("diff --git a/src/huge.rs"));
+        assert!(out.text.contains("@@ -1,0 +1,2000 @@"));
+        assert!(!out.text.contains("+line 1999"));
+        assert_eq!(out.summarised.len(), 1);
+        assert_eq!(out.summarised[0].detail, Detail::Hunks);
+        assert!(estimate_tokens(&out.text) <= 200);
+    }
+
+    #[test]
+    fn recognises_test_files_by_directory_or_name() {
+        for path in ["tests/api.rs", "src/__tests__/a.tsx", "x_test.go", "a.spec.ts"] {
+            assert!(is_test_path(path), "{}", path);
+        }
+        for path in ["src/latest.rs", "attestation/verify.rs", "src/contest.rs"] {
+            assert!(!is_test_path(path), "{}", path);
+        }
+    }
+
+    #[test]
+    fn source_outranks_docs_for_the_budget() {
+        let out = condense(
+            vec![file_diff("README.md", 40), file_diff("src/lib.rs", 40)],
+            180,
+        );
+        let summarised: Vec<&str> = out.summarised.iter().map(|s| s.path.as_str()).collect();
+        assert_eq!(summarised, vec!["README.md"]);
+    }
+}
@@ -1,14 +1,26 @@
+use crate::remote::{self, Location};
+use crate::remote_watch::{self, RemoteWatch};
 use notify_debouncer_mini::notify;
 use notify_debouncer_mini::{new_debouncer, DebouncedEventKind};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tauri::Emitter;
 
+// held only to keep the watch alive
+enum Watch {
+    Local {
+        _debouncer: notify_debouncer_mini::Debouncer<notify::RecommendedWatcher>,
+    },
+    Remote {
+        _watch: RemoteWatch,
+    },
+}
+
 struct WatchEntry {
     tab_ids: Vec<String>,
-    _debouncer: notify_debouncer_mini::Debouncer<notify::RecommendedWatcher>,
+    _watch: Watch,
 }
 
 #[derive(Clone, serde::Serialize)]
@@ -17,6 +29,7 @@ struct FileChangedPayload {
     file_path: String,
 }
 
+#[derive(Clone)]
 pub struct FileWatcherManager {
     app: tauri::AppHandle,
     watches: Arc<Mutex<HashMap<String, WatchEntry>>>,
@@ -55,6 +68,52 @@ impl FileWatcherManager {
             return Ok(());
         }
 
+        let watch = match remote::locate(file_path) {
+            Location::Local(_) => Watch::Local {
+                _debouncer: self.watch_local(file_path)?,
+            },
+            Location::Remote(target) => {
+                // the ssh process can take seconds to come up; don't hold the map meanwhile
+                drop(watches);
+                let app_handle = self.app.clone();
+                let watched_path = file_path.to_string();
+                let remote_watch = remote_watch::watch(target, move || {
+                    let _ = app_handle.emit(
+                        "file-changed",
+                        FileChangedPayload {
+                            file_path: watched_path.clone(),
+                        },
+                    );
+                })?;
+                watches = self.watches.lock().map_err(|e| e.to_string())?;
+                if let Some(entry) = watches.get_mut(&key) {
+                    // raced with another tab opening the same file
+                    if !entry.tab_ids.contains(&tab_id.to_string()) {
+                        entry.tab_ids.push(tab_id.to_string());
+                    }
+                    return Ok(());
+                }
+                Watch::Remote {
+                    _watch: remote_watch,
+                }
+            }
+        };
+
+        watches.insert(
+            key,
+            WatchEntry {
+                tab_ids: vec![tab_id.to_string()],
+                _watch: watch,
+            },
+        );
+
+        Ok(())
+    }
+
+    fn watch_local(
+        &self,
+        file_path: &str,
+    ) -> Result<notify_debouncer_mini::Debouncer<notify::RecommendedWatcher>, String> {
         // Watch the parent directory (more reliable on Windows than watching a single file)
         let target = PathBuf::from(file_path);
         let parent = target
@@ -105,15 +164,7 @@ impl FileWatcherManager {
             .watch(parent, notify::RecursiveMode::NonRecursive)
             .map_err(|e| format!("Failed to watch directory: {}", e))?;
 
-        watches.insert(
-            key,
-            WatchEntry {
-                tab_ids: vec![tab_id.to_string()],
-                _debouncer: debouncer,
-            },
-        );
-
-        Ok(())
+        Ok(debouncer)
     }
 
     pub fn unwatch_file(&self, tab_id: &str, file_path: &str) -> Result<(), String> {
@@ -137,6 +188,36 @@ impl FileWatcherManager {
     }
 }
 
+/// Recursively watches a directory tree, handing each debounced batch of changed paths
+/// to `on_change`. The watch lasts as long as the returned debouncer.
+pub(crate) fn watch_tree(
+    root: &Path,
+    on_change: impl Fn(Vec<PathBuf>) + Send + 'static,
+) -> Result<notify_debouncer_mini::Debouncer<notify::RecommendedWatcher>, String> {
+    let mut debouncer = new_debouncer(
+        Duration::from_millis(500),
+        move |results: Result<Vec<notify_debouncer_mini::DebouncedEvent>, notify::Error>| {
+            let Ok(events) = results else { return };
+            let paths: Vec<PathBuf> = events
+                .into_iter()
+                .filter(|e| e.kind == DebouncedEventKind::Any)
+                .map(|e| e.path)
+                .collect();
+            if !paths.is_empty() {
+                on_change(paths);
+            }
+        },
+    )
+    .map_err(|e| format!("Failed to create file watcher: {}", e))?;
+
+    debouncer
+        .watcher()
+        .watch(root, notify::RecursiveMode::Recursive)
+        .map_err(|e| format!("Failed to watch directory: {}", e))?;
+
+    Ok(debouncer)
+}
+
 // Required for Tauri's State<> to work across threads
 unsafe impl Send for FileWatcherManager {}
 unsafe impl Sync for FileWatcherManager {}
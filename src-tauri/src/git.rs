@@ -1,20 +1,36 @@
+use crate::diff_condense::{self, FileDiff, SummarisedFile};
+use crate::git_undo::{self, Snapshot};
 use crate::remote::{self, CmdOutput, Location};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::io::Write;
 use std::os::windows::process::CommandExt;
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
+use std::time::Duration;
 
 const CREATE_NO_WINDOW: u32 = 0x08000000;
 
 /// Runs git in the project, locally or over ssh depending on the project path.
-fn run_git(project_path: &str, args: &[&str]) -> Result<CmdOutput, String> {
+pub(crate) fn run_git(project_path: &str, args: &[&str]) -> Result<CmdOutput, String> {
+    run_git_env(project_path, &[], args)
+}
+
+pub(crate) fn run_git_env(
+    project_path: &str,
+    envs: &[(&str, &str)],
+    args: &[&str],
+) -> Result<CmdOutput, String> {
     match remote::locate(project_path) {
-        Location::Local(path) => remote::run_local(&path, "git", args),
+        Location::Local(path) => remote::run_local(&path, "git", args, envs),
         Location::Remote(target) => {
-            remote::run(&target, Some(&target.path), &remote::shell_cmd("git", args))
+            let mut command = String::new();
+            for (name, value) in envs {
+                command.push_str(&format!("{}={} ", name, remote::q(value)));
+            }
+            command.push_str(&remote::shell_cmd("git", args));
+            remote::run(&target, Some(&target.path), &command)
         }
     }
 }
@@ -72,14 +88,14 @@ pub(crate) fn find_claude_exe() -> Result<PathBuf, String> {
     Err("Could not find claude CLI. Ensure it is installed and in PATH.".to_string())
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct GitFileEntry {
     pub path: String,
     pub status: String,
 }
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct GitStatus {
     pub is_repo: bool,
@@ -99,7 +115,12 @@ pub fn get_status(project_path: &str) -> GitStatus {
         }
     };
 
-    let mut files = match run_git(project_path, &["status", "--porcelain=v1", "-uall"]) {
+    // no optional locks: status must not rewrite the index, or the status watcher sees its
+    // own refresh as a change
+    let mut files = match run_git(
+        project_path,
+        &["--no-optional-locks", "status", "--porcelain=v1", "-uall"],
+    ) {
         Ok(output) if output.ok => parse_porcelain(&output.stdout),
         _ => Vec::new(),
     };
@@ -157,6 +178,114 @@ fn mark_nested_repos(project_path: &str, files: &mut [GitFileEntry]) {
     }
 }
 
+/// Resolves the repo a git operation targets: the project itself, or a nested repo or
+/// submodule inside it given by its project-relative path. Paths that would leave the
+/// project are refused.
+pub fn scoped_repo(project_path: &str, repo: Option<&str>) -> Result<String, String> {
+    let Some(rel) = repo.filter(|r| !r.is_empty()) else {
+        return Ok(project_path.to_string());
+    };
+    let absolute = rel.starts_with('/') || rel.starts_with('\\') || remote::is_windows_path(rel);
+    if absolute || rel.split(['/', '\\']).any(|segment| segment == "..") {
+        return Err(format!("Repo path must stay inside the project: {}", rel));
+    }
+    match rel.trim_end_matches('/') {
+        "" => Ok(project_path.to_string()),
+        rel => Ok(remote::join_path(project_path, rel)),
+    }
+}
+
+/// How deep `get_nested_status` follows repos inside repos.
+const MAX_NESTED_DEPTH: usize = 4;
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NestedRepoStatus {
+    /// Project-relative path of the nested repo.
+    pub path: String,
+    pub status: GitStatus,
+    /// Repos nested inside this one, their paths also relative to the project.
+    pub nested: Vec<NestedRepoStatus>,
+}
+
+/// Status of a nested repo, and of the repos nested inside it in turn.
+pub fn get_nested_status(project_path: &str, repo: &str) -> Result<NestedRepoStatus, String> {
+    nested_status(project_path, repo.trim_end_matches('/'), MAX_NESTED_DEPTH)
+}
+
+fn nested_status(project_path: &str, rel: &str, depth: usize) -> Result<NestedRepoStatus, String> {
+    let status = get_status(&scoped_repo(project_path, Some(rel))?);
+    let mut nested = Vec::new();
+    if depth > 0 {
+        for f in status.files.iter().filter(|f| f.status == "S") {
+            let child = format!("{}/{}", rel, f.path.trim_end_matches('/'));
+            nested.push(nested_status(project_path, &child, depth - 1)?);
+        }
+    }
+    Ok(NestedRepoStatus {
+        path: rel.to_string(),
+        status,
+        nested,
+    })
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmoduleInfo {
+    pub path: String,
+    /// The commit checked out in the submodule (or recorded in the index if uninitialized).
+    pub commit: String,
+    /// `git describe` of the commit, when git could produce one.
+    pub describe: Option<String>,
+    /// "clean" | "outOfSync" | "uninitialized" | "conflict"
+    pub state: String,
+    /// Uncommitted changes inside the submodule's own worktree.
+    pub dirty: bool,
+}
+
+/// Submodules (recursively) with their checked-out commit and worktree state.
+pub fn list_submodules(project_path: &str) -> Result<Vec<SubmoduleInfo>, String> {
+    let output = run_git(project_path, &["submodule", "status", "--recursive"])?;
+    if !output.ok {
+        return Err(format!(
+            "git submodule status failed: {}",
+            output.err_text()
+        ));
+    }
+    let mut submodules = parse_submodule_status(&output.stdout);
+    if submodules.iter().all(|s| s.state == "uninitialized") {
+        return Ok(submodules);
+    }
+
+    // one foreach instead of a status call per submodule — it matters over ssh
+    let dirty_output = run_git(
+        project_path,
+        &[
+            "submodule",
+            "foreach",
+            "--quiet",
+            "--recursive",
+            "test -z \"$(git status --porcelain)\" || printf '%s\\n' \"$displaypath\"",
+        ],
+    )?;
+    if !dirty_output.ok {
+        return Err(format!(
+            "git submodule foreach failed: {}",
+            dirty_output.err_text()
+        ));
+    }
+    let dirty: HashSet<&str> = dirty_output
+        .stdout
+        .lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty())
+        .collect();
+    for submodule in submodules.iter_mut() {
+        submodule.dirty = dirty.contains(submodule.path.as_str());
+    }
+    Ok(submodules)
+}
+
 pub fn get_diff(project_path: &str, file_path: &str, status: &str) -> Result<String, String> {
     if status == "?" {
         // Untracked file: read contents and format as synthetic diff
@@ -182,7 +311,41 @@ pub fn get_diff(project_path: &str, file_path: &str, status: &str) -> Result<Str
     Ok(output.stdout)
 }
 
-pub fn commit(project_path: &str, files: &[String], message: &str) -> Result<String, String> {
+/// Streamed to the commit dialog while `git commit` runs, mostly hook output.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", content = "data")]
+pub enum CommitEvent {
+    /// A hook started; the output that follows is its.
+    Hook {
+        name: String,
+    },
+    Output {
+        line: String,
+    },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CommitOptions {
+    /// Skip the pre-commit and commit-msg hooks.
+    pub no_verify: bool,
+    pub sign: bool,
+    /// Key for `-S`; git's configured default when unset.
+    pub signing_key: Option<String>,
+}
+
+/// Hooks that run before the commit is written, and so can stop it, in git's order.
+const BLOCKING_HOOKS: &[&str] = &["pre-commit", "prepare-commit-msg", "commit-msg"];
+
+/// Where the message is kept for the hooks to read (and edit) before committing it.
+const EDITMSG_FILE: &str = "CIRCUITCLAUDE_EDITMSG";
+
+pub fn commit(
+    project_path: &str,
+    files: &[String],
+    message: &str,
+    options: &CommitOptions,
+    on_event: &mut dyn FnMut(CommitEvent),
+) -> Result<String, String> {
     // Stage selected files
     let mut add_args = vec!["add", "--"];
     let file_refs: Vec<&str> = files.iter().map(|s| s.as_str()).collect();
@@ -193,15 +356,214 @@ pub fn commit(project_path: &str, files: &[String], message: &str) -> Result<Str
         return Err(format!("git add failed: {}", add_output.err_text()));
     }
 
-    let commit_output = run_git(project_path, &["commit", "-m", message])?;
-    if !commit_output.ok {
-        return Err(format!("git commit failed: {}", commit_output.err_text()));
+    let sign_arg = match &options.signing_key {
+        Some(key) if !key.trim().is_empty() => format!("-S{}", key.trim()),
+        _ => "-S".to_string(),
+    };
+    let mut sign = Vec::new();
+    if options.sign {
+        sign.push(sign_arg.as_str());
+    }
+
+    let output = if !options.no_verify && supports_hook_run(project_path) {
+        commit_running_hooks(project_path, message, &sign, on_event)?
+    } else {
+        // --no-verify, or a git too old to run hooks on its own: git runs whatever
+        // hooks apply and its output streams unattributed
+        let mut args = vec!["commit", "-m", message];
+        if options.no_verify {
+            args.push("--no-verify");
+        }
+        args.extend(&sign);
+        git_lines(project_path, &args, &mut |line| {
+            on_event(CommitEvent::Output { line })
+        })?
+    };
+
+    if !output.ok {
+        return Err(format!("git commit failed: {}", output.err_text()));
+    }
+    Ok(output.stdout.trim().to_string())
+}
+
+/// `git hook run` arrived in git 2.36; a hook name that doesn't exist is fine with
+/// `--ignore-missing`, so only an older git fails this.
+fn supports_hook_run(project_path: &str) -> bool {
+    run_git(
+        project_path,
+        &["hook", "run", "--ignore-missing", "circuitclaude-probe"],
+    )
+    .is_ok_and(|output| output.ok)
+}
+
+/// Runs the blocking hooks one at a time with `git hook run`, so each one's output is
+/// known to be its own, then commits with them switched off.
+fn commit_running_hooks(
+    project_path: &str,
+    message: &str,
+    sign: &[&str],
+    on_event: &mut dyn FnMut(CommitEvent),
+) -> Result<CmdOutput, String> {
+    let git_dir = run_git(project_path, &["rev-parse", "--absolute-git-dir"])?;
+    if !git_dir.ok {
+        return Err(format!("git rev-parse failed: {}", git_dir.err_text()));
+    }
+    let git_dir = git_dir.stdout.trim().to_string();
+    let message_file = format!("{}/{}", git_dir, EDITMSG_FILE);
+    write_git_file(project_path, &message_file, message)?;
+
+    for hook in BLOCKING_HOOKS {
+        let hook_args: &[&str] = match *hook {
+            "prepare-commit-msg" => &[&message_file, "message"],
+            "commit-msg" => &[&message_file],
+            _ => &[],
+        };
+        let (output, hook_output) = run_hook(project_path, hook, hook_args, on_event)?;
+        if !output.ok {
+            let detail = hook_output.trim();
+            return Err(if detail.is_empty() {
+                format!("{} hook failed", hook)
+            } else {
+                format!("{} hook failed:\n{}", hook, detail)
+            });
+        }
+    }
+
+    // --no-verify still runs prepare-commit-msg, which already had its turn; pointing
+    // the hooks dir somewhere empty keeps it from editing the message twice
+    let no_hooks = format!("core.hooksPath={}/circuitclaude-no-hooks", git_dir);
+    let mut args = vec![
+        "-c",
+        &no_hooks,
+        "commit",
+        "--no-verify",
+        "-F",
+        &message_file,
+    ];
+    args.extend(sign);
+    let output = git_lines(project_path, &args, &mut |line| {
+        on_event(CommitEvent::Output { line })
+    })?;
+    if output.ok {
+        // the commit is made; like git, don't let post-commit undo that
+        let _ = run_hook(project_path, "post-commit", &[], on_event);
     }
+    Ok(output)
+}
 
-    Ok(commit_output.stdout.trim().to_string())
+/// Runs `hook` if the repo has one, announcing it with its first line of output.
+/// Returns git's result and everything the hook printed.
+fn run_hook(
+    project_path: &str,
+    hook: &str,
+    hook_args: &[&str],
+    on_event: &mut dyn FnMut(CommitEvent),
+) -> Result<(CmdOutput, String), String> {
+    let mut args = vec!["hook", "run", "--ignore-missing", hook, "--"];
+    args.extend(hook_args);
+    let mut announced = false;
+    let mut hook_output = String::new();
+    let output = git_lines(project_path, &args, &mut |line| {
+        if !announced {
+            announced = true;
+            on_event(CommitEvent::Hook {
+                name: hook.to_string(),
+            });
+        }
+        hook_output.push_str(&line);
+        hook_output.push('\n');
+        on_event(CommitEvent::Output { line });
+    })?;
+    if !output.ok && !announced {
+        on_event(CommitEvent::Hook {
+            name: hook.to_string(),
+        });
+    }
+    Ok((output, hook_output))
 }
 
+/// Runs git, handing each line of its output (stdout and stderr as they interleave) to
+/// `on_line`.
+fn git_lines(
+    project_path: &str,
+    args: &[&str],
+    on_line: &mut dyn FnMut(String),
+) -> Result<CmdOutput, String> {
+    let mut lines = LineSplitter::default();
+    let mut on_output = |chunk: &[u8]| lines.feed(chunk, on_line);
+    let output = match remote::locate(project_path) {
+        Location::Local(path) => {
+            remote::run_local_streaming(&path, "git", args, &[], &mut on_output)?
+        }
+        Location::Remote(target) => remote::run_streaming(
+            &target,
+            Some(&target.path),
+            &remote::shell_cmd("git", args),
+            &mut on_output,
+        )?,
+    };
+    lines.finish(on_line);
+    Ok(output)
+}
+
+fn write_git_file(project_path: &str, path: &str, content: &str) -> Result<(), String> {
+    match remote::locate(project_path) {
+        Location::Local(_) => {
+            fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path, e))
+        }
+        Location::Remote(target) => remote::write_text(&target, path, content),
+    }
+}
+
+/// Splits streamed output into lines, holding a partial line until the rest arrives.
+#[derive(Default)]
+struct LineSplitter {
+    pending: Vec<u8>,
+}
+
+impl LineSplitter {
+    fn feed(&mut self, chunk: &[u8], on_line: &mut dyn FnMut(String)) {
+        self.pending.extend_from_slice(chunk);
+        while let Some(end) = self.pending.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            on_line(Self::text(&line));
+        }
+    }
+
+    fn finish(&mut self, on_line: &mut dyn FnMut(String)) {
+        if !self.pending.is_empty() {
+            on_line(Self::text(&std::mem::take(&mut self.pending)));
+        }
+    }
+
+    fn text(line: &[u8]) -> String {
+        String::from_utf8_lossy(line)
+            .trim_end_matches(['\n', '\r'])
+            .to_string()
+    }
+}
+
+/// Discards changes to the given files. Their current content is snapshotted first so
+/// `git_undo::undo_last` can bring it back.
 pub fn revert(project_path: &str, files: &[GitFileEntry]) -> Result<(), String> {
+    if files.is_empty() {
+        return Ok(());
+    }
+    let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+    git_undo::record(
+        project_path,
+        &Snapshot {
+            op: "revert",
+            summary: match paths.as_slice() {
+                [only] => format!("revert {}", only),
+                _ => format!("revert {} files", paths.len()),
+            },
+            paths: &paths,
+            branch: None,
+            mode: None,
+        },
+    )?;
+
     let mut untracked: Vec<&str> = Vec::new();
     let mut added: Vec<&str> = Vec::new();
     let mut tracked: Vec<&str> = Vec::new();
@@ -255,6 +617,70 @@ pub struct DiffStat {
     pub deletions: u32,
 }
 
+/// `git reset --<mode> <target>`, snapshotting HEAD (and for `hard`, every uncommitted
+/// change to tracked files) first.
+pub fn reset(project_path: &str, target: &str, mode: &str) -> Result<(), String> {
+    if !matches!(mode, "soft" | "mixed" | "hard") {
+        return Err(format!("Unknown reset mode '{}'", mode));
+    }
+    let dirty = if mode == "hard" {
+        let output = run_git(project_path, &["diff", "--name-only", "-z", "HEAD"])?;
+        if !output.ok {
+            return Err(format!("git diff failed: {}", output.err_text()));
+        }
+        output.stdout
+    } else {
+        String::new()
+    };
+    let paths: Vec<&str> = dirty.split('\0').filter(|p| !p.is_empty()).collect();
+    git_undo::record(
+        project_path,
+        &Snapshot {
+            op: "reset",
+            summary: format!("reset --{} {}", mode, target),
+            paths: &paths,
+            branch: None,
+            mode: Some(mode),
+        },
+    )?;
+
+    let flag = format!("--{}", mode);
+    let output = run_git(project_path, &["reset", &flag, target, "--"])?;
+    if !output.ok {
+        git_undo::drop_latest(project_path).ok();
+        return Err(format!("git reset failed: {}", output.err_text()));
+    }
+    Ok(())
+}
+
+/// Deletes a local branch, remembering its tip so the deletion can be undone.
+pub fn delete_branch(project_path: &str, name: &str, force: bool) -> Result<(), String> {
+    let reference = format!("refs/heads/{}", name);
+    let tip = run_git(project_path, &["rev-parse", "-q", "--verify", &reference])?;
+    if !tip.ok {
+        return Err(format!("No branch named '{}'", name));
+    }
+    let tip = tip.stdout.trim().to_string();
+    git_undo::record(
+        project_path,
+        &Snapshot {
+            op: "deleteBranch",
+            summary: format!("delete branch {}", name),
+            paths: &[],
+            branch: Some((name, &tip)),
+            mode: None,
+        },
+    )?;
+
+    let flag = if force { "-D" } else { "-d" };
+    let output = run_git(project_path, &["branch", flag, "--", name])?;
+    if !output.ok {
+        git_undo::drop_latest(project_path).ok();
+        return Err(format!("git branch failed: {}", output.err_text()));
+    }
+    Ok(())
+}
+
 pub fn get_diff_stats(project_path: &str, files: &[GitFileEntry]) -> Result<Vec<DiffStat>, String> {
     let mut tracked_paths: Vec<&str> = Vec::new();
     let mut untracked: Vec<&str> = Vec::new();
@@ -335,8 +761,16 @@ fn count_lines(project_path: &str, paths: &[&str]) -> Vec<DiffStat> {
     }
 }
 
+/// Long enough for a large push over a slow link.
+const PUSH_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
 pub fn push(project_path: &str) -> Result<String, String> {
-    let output = run_git(project_path, &["push"])?;
+    let output = match remote::locate(project_path) {
+        Location::Remote(target) => {
+            remote::run_with_timeout(&target, Some(&target.path), "git push", PUSH_TIMEOUT)?
+        }
+        Location::Local(_) => run_git(project_path, &["push"])?,
+    };
     if !output.ok {
         return Err(format!("git push failed: {}", output.err_text()));
     }
@@ -347,7 +781,8 @@ pub fn push(project_path: &str) -> Result<String, String> {
     Ok(if stdout.is_empty() { stderr } else { stdout })
 }
 
-const MAX_DIFF_CHARS: usize = 100_000;
+/// Prompt budget for generated messages when settings don't say otherwise.
+pub const DEFAULT_DIFF_TOKEN_BUDGET: usize = 25_000;
 
 #[derive(Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -355,32 +790,29 @@ pub struct GenerateResult {
     pub prompt: String,
     pub message: String,
     pub model: String,
+    /// Files that went into the prompt as less than their full diff.
+    pub summarised_files: Vec<SummarisedFile>,
 }
 
 pub fn generate_commit_message(
     project_path: &str,
     files: &[GitFileEntry],
+    token_budget: usize,
 ) -> Result<GenerateResult, String> {
-    // Collect combined diffs from all selected files
-    let mut combined_diff = String::new();
-    for f in files {
-        match get_diff(project_path, &f.path, &f.status) {
-            Ok(diff) => {
-                combined_diff.push_str(&diff);
-                combined_diff.push('\n');
-                if combined_diff.len() > MAX_DIFF_CHARS {
-                    combined_diff.truncate(MAX_DIFF_CHARS);
-                    combined_diff.push_str("\n... (truncated)\n");
-                    break;
-                }
-            }
-            Err(_) => continue,
-        }
-    }
+    // Collect per-file diffs from all selected files
+    let diffs: Vec<FileDiff> = files
+        .iter()
+        .filter_map(|f| {
+            let diff = get_diff(project_path, &f.path, &f.status).ok()?;
+            Some(FileDiff::new(f.path.clone(), diff + "\n"))
+        })
+        .collect();
 
-    if combined_diff.trim().is_empty() {
+    if diffs.iter().all(|d| d.text.trim().is_empty()) {
         return Err("No diff content to generate a message from".to_string());
     }
+    let condensed = diff_condense::condense(diffs, token_budget);
+    let combined_diff = condensed.text;
 
     let prompt = format!(
         "Generate a git commit message for this diff. \
@@ -393,7 +825,18 @@ pub fn generate_commit_message(
     );
 
     let model = "claude-haiku-4-5-20251001";
+    let message = run_claude_prompt(project_path, &prompt, model)?;
+
+    Ok(GenerateResult {
+        prompt,
+        message,
+        model: model.to_string(),
+        summarised_files: condensed.summarised,
+    })
+}
 
+/// Pipes a prompt through `claude -p` and returns the trimmed reply.
+fn run_claude_prompt(project_path: &str, prompt: &str, model: &str) -> Result<String, String> {
     let claude_path = find_claude_exe()?;
 
     // the CLI runs on this machine even for remote projects, so fall back to home
@@ -440,14 +883,291 @@ pub fn generate_commit_message(
     if message.is_empty() {
         return Err("Claude CLI returned an empty response".to_string());
     }
+    Ok(message)
+}
 
-    Ok(GenerateResult {
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GeneratePrResult {
+    pub prompt: String,
+    pub title: String,
+    pub body: String,
+    pub model: String,
+    /// The merge base the diff was taken from.
+    pub base: String,
+    pub commit_count: usize,
+    pub summarised_files: Vec<SummarisedFile>,
+}
+
+/// Drafts a pull request title and body from everything on the current branch since it
+/// diverged from `target_branch`.
+pub fn generate_pr_description(
+    project_path: &str,
+    target_branch: &str,
+    token_budget: usize,
+) -> Result<GeneratePrResult, String> {
+    // a leading dash would reach git as an option rather than a revision
+    if target_branch.is_empty() || target_branch.starts_with('-') {
+        return Err(format!("Invalid target branch: {}", target_branch));
+    }
+    let base_output = run_git(project_path, &["merge-base", "HEAD", target_branch])?;
+    if !base_output.ok {
+        return Err(format!("git merge-base failed: {}", base_output.err_text()));
+    }
+    let base = base_output.stdout.trim().to_string();
+
+    let range = format!("{}..HEAD", base);
+    let log_output = run_git(
+        project_path,
+        &["log", "--no-merges", "--format=- %s", &range],
+    )?;
+    if !log_output.ok {
+        return Err(format!("git log failed: {}", log_output.err_text()));
+    }
+    let commits: Vec<&str> = log_output
+        .stdout
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .collect();
+
+    let diff_output = run_git(project_path, &["diff", &base, "HEAD"])?;
+    if !diff_output.ok {
+        return Err(format!("git diff failed: {}", diff_output.err_text()));
+    }
+    if commits.is_empty() && diff_output.stdout.trim().is_empty() {
+        return Err(format!("No changes relative to {}", target_branch));
+    }
+    let condensed = diff_condense::condense(
+        diff_condense::split_file_diffs(&diff_output.stdout),
+        token_budget,
+    );
+
+    let prompt = format!(
+        "Write a pull request description for merging this branch into {}. \
+         Rules: output ONLY the description, no quotes, no preamble. \
+         First line is the PR title: imperative mood, under 72 chars. \
+         Then a blank line, then the body in markdown: one or two plain sentences saying \
+         what changes and why, followed by a few \"-\" bullets covering the notable changes. \
+         Skip trivial stuff like whitespace, imports, or minor rewording.\n\n\
+         Commits:\n{}\n\nDiff:\n{}",
+        target_branch,
+        commits.join("\n"),
+        condensed.text
+    );
+
+    let model = "claude-haiku-4-5-20251001";
+    let reply = run_claude_prompt(project_path, &prompt, model)?;
+
+    let (title, body) = match reply.split_once('\n') {
+        Some((title, body)) => (title.trim().to_string(), body.trim().to_string()),
+        None => (reply.trim().to_string(), String::new()),
+    };
+
+    Ok(GeneratePrResult {
         prompt,
-        message,
+        title,
+        body,
         model: model.to_string(),
+        base,
+        commit_count: commits.len(),
+        summarised_files: condensed.summarised,
     })
 }
 
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BlameLine {
+    pub line: u32,
+    pub commit: String,
+    pub author: String,
+    pub author_mail: String,
+    /// Unix seconds.
+    pub timestamp: i64,
+    pub summary: String,
+    /// The line is not committed yet.
+    pub uncommitted: bool,
+    pub content: String,
+}
+
+/// Per-line authorship for a file, optionally limited to a 1-based inclusive line range.
+pub fn blame(
+    project_path: &str,
+    file_path: &str,
+    range: Option<(u32, u32)>,
+) -> Result<Vec<BlameLine>, String> {
+    let range_arg = range.map(|(start, end)| format!("{},{}", start, end));
+    let mut args: Vec<&str> = vec!["blame", "--porcelain"];
+    if let Some(range_arg) = &range_arg {
+        args.push("-L");
+        args.push(range_arg);
+    }
+    args.push("--");
+    args.push(file_path);
+
+    let output = run_git(project_path, &args)?;
+    if !output.ok {
+        return Err(format!("git blame failed: {}", output.err_text()));
+    }
+    Ok(parse_blame_porcelain(&output.stdout))
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LineHistoryEntry {
+    pub commit: String,
+    pub author: String,
+    pub author_mail: String,
+    pub timestamp: i64,
+    pub summary: String,
+    /// The change this commit made to the tracked range.
+    pub diff: String,
+}
+
+/// Every commit that touched a line range, newest first, via `git log -L`.
+pub fn line_history(
+    project_path: &str,
+    file_path: &str,
+    start: u32,
+    end: u32,
+) -> Result<Vec<LineHistoryEntry>, String> {
+    let range_arg = format!("{},{}:{}", start, end, file_path);
+    let output = run_git(
+        project_path,
+        &[
+            "log",
+            "--no-color",
+            "--format=%x1e%H%x1f%an%x1f%ae%x1f%at%x1f%s",
+            "-L",
+            &range_arg,
+        ],
+    )?;
+    if !output.ok {
+        return Err(format!("git log -L failed: {}", output.err_text()));
+    }
+    Ok(parse_line_history(&output.stdout))
+}
+
+/// `--porcelain` repeats a commit's author block only the first time the commit appears,
+/// so headers are remembered per sha.
+fn parse_blame_porcelain(output: &str) -> Vec<BlameLine> {
+    #[derive(Clone, Default)]
+    struct CommitInfo {
+        author: String,
+        author_mail: String,
+        timestamp: i64,
+        summary: String,
+    }
+
+    let mut known: HashMap<String, CommitInfo> = HashMap::new();
+    let mut lines: Vec<BlameLine> = Vec::new();
+    let mut current: Option<(String, u32)> = None;
+
+    for raw in output.lines() {
+        if let Some(content) = raw.strip_prefix('\t') {
+            let Some((commit, line)) = current.take() else {
+                continue;
+            };
+            let info = known.get(&commit).cloned().unwrap_or_default();
+            lines.push(BlameLine {
+                line,
+                uncommitted: commit.chars().all(|c| c == '0'),
+                commit,
+                author: info.author,
+                author_mail: info.author_mail,
+                timestamp: info.timestamp,
+                summary: info.summary,
+                content: content.to_string(),
+            });
+            continue;
+        }
+
+        let Some((key, value)) = raw.split_once(' ') else {
+            continue;
+        };
+        let Some((commit, _)) = &current else {
+            // "<sha> <orig_line> <final_line> [<group_size>]"
+            let final_line = value.split(' ').nth(1).and_then(|n| n.parse::<u32>().ok());
+            if let Some(final_line) = final_line {
+                // sha-1 repos name commits in 40 hex digits, sha-256 ones in 64
+                let is_sha = matches!(key.len(), 40 | 64);
+                if is_sha && key.chars().all(|c| c.is_ascii_hexdigit()) {
+                    known.entry(key.to_string()).or_default();
+                    current = Some((key.to_string(), final_line));
+                }
+            }
+            continue;
+        };
+        let Some(info) = known.get_mut(commit) else {
+            continue;
+        };
+        match key {
+            "author" => info.author = value.to_string(),
+            "author-mail" => {
+                info.author_mail = value.trim_matches(|c| c == '<' || c == '>').to_string()
+            }
+            "author-time" => info.timestamp = value.parse().unwrap_or(0),
+            "summary" => info.summary = value.to_string(),
+            _ => {}
+        }
+    }
+
+    lines
+}
+
+fn parse_line_history(output: &str) -> Vec<LineHistoryEntry> {
+    output
+        .split('\x1e')
+        .filter(|record| !record.trim().is_empty())
+        .filter_map(|record| {
+            let (header, diff) = record.split_once('\n').unwrap_or((record, ""));
+            let mut fields = header.split('\x1f');
+            Some(LineHistoryEntry {
+                commit: fields.next()?.to_string(),
+                author: fields.next()?.to_string(),
+                author_mail: fields.next()?.to_string(),
+                timestamp: fields.next()?.parse().unwrap_or(0),
+                summary: fields.next().unwrap_or("").to_string(),
+                diff: diff.trim_matches('\n').to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Parses `git submodule status`: "<state><sha> <path>[ (<describe>)]" per line, where the
+/// state column is a space, `+`, `-` or `U`.
+fn parse_submodule_status(output: &str) -> Vec<SubmoduleInfo> {
+    let mut submodules = Vec::new();
+    for line in output.lines() {
+        if line.len() < 42 {
+            continue;
+        }
+        let state = match line.as_bytes()[0] {
+            b'+' => "outOfSync",
+            b'-' => "uninitialized",
+            b'U' => "conflict",
+            _ => "clean",
+        };
+        let rest = &line[1..];
+        let Some((commit, rest)) = rest.split_once(' ') else {
+            continue;
+        };
+        let (path, describe) = match rest.rsplit_once(" (") {
+            Some((path, describe)) if describe.ends_with(')') => {
+                (path, Some(describe.trim_end_matches(')').to_string()))
+            }
+            _ => (rest, None),
+        };
+        submodules.push(SubmoduleInfo {
+            path: path.to_string(),
+            commit: commit.to_string(),
+            describe,
+            state: state.to_string(),
+            dirty: false,
+        });
+    }
+    submodules
+}
+
 fn parse_numstat(output: &str) -> Vec<DiffStat> {
     let mut stats = Vec::new();
     for line in output.lines() {
@@ -517,3 +1237,181 @@ fn parse_porcelain(output: &str) -> Vec<GitFileEntry> {
 
     files
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_streamed_output_into_lines() {
+        let mut lines = Vec::new();
+        let mut splitter = LineSplitter::default();
+        splitter.feed(b"checking st", &mut |l| lines.push(l));
+        splitter.feed(b"yle\r\nbad\n", &mut |l| lines.push(l));
+        splitter.feed(b"tail", &mut |l| lines.push(l));
+        splitter.finish(&mut |l| lines.push(l));
+        assert_eq!(lines, ["checking style", "bad", "tail"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn runs_each_hook_on_its_own_and_names_the_one_that_failed() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("cc-hooks-{}", uuid::Uuid::new_v4().simple()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.to_string_lossy().to_string();
+        let git = |args: &[&str]| run_git(&path, args).map(|o| o.ok).unwrap_or(false);
+        if !git(&["init", "-q"]) || !supports_hook_run(&path) {
+            let _ = std::fs::remove_dir_all(&dir);
+            return;
+        }
+        git(&["config", "user.name", "Test"]);
+        git(&["config", "user.email", "test@example.com"]);
+        git(&["config", "commit.gpgsign", "false"]);
+        let hook = |name: &str, body: &str| {
+            let file = dir.join(".git/hooks").join(name);
+            std::fs::write(&file, format!("#!/bin/sh\n{}\n", body)).unwrap();
+            std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o755)).unwrap();
+        };
+        // a hook capturing git's stderr sees no trace noise, and git calls inside a hook
+        // don't lose track of which hook is running
+        hook(
+            "pre-commit",
+            r#"err=$(git rev-parse --git-dir 2>&1 >/dev/null); echo "lint ${#err}""#,
+        );
+        hook("prepare-commit-msg", r#"printf '\nprepared\n' >> "$1""#);
+        hook(
+            "commit-msg",
+            r#"git status >/dev/null; grep -q '^feat' "$1" || { echo "needs a type"; exit 1; }"#,
+        );
+        std::fs::write(dir.join("a.txt"), "a").unwrap();
+        let files = vec!["a.txt".to_string()];
+        let options = CommitOptions::default();
+
+        let mut events = Vec::new();
+        let err = commit(&path, &files, "oops", &options, &mut |e| events.push(e)).unwrap_err();
+        assert_eq!(err, "commit-msg hook failed:\nneeds a type");
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, CommitEvent::Output { line } if line == "lint 0")));
+        assert!(
+            matches!(events.last(), Some(CommitEvent::Output { line }) if line == "needs a type")
+        );
+
+        commit(&path, &files, "feat: a", &options, &mut |_| {}).unwrap();
+        let body = run_git(&path, &["log", "-1", "--format=%B"])
+            .unwrap()
+            .stdout;
+        assert_eq!(body.matches("prepared").count(), 1);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn parses_blame_porcelain_with_repeated_commits() {
+        let sha = "a".repeat(40);
+        let zero = "0".repeat(40);
+        let output = format!(
+            "{sha} 1 1 2\n\
+             author Ada\n\
+             author-mail <ada@example.com>\n\
+             author-time 1700000000\n\
+             author-tz +0000\n\
+             summary Add parser\n\
+             filename src/lib.rs\n\
+             \tfn main() {{\n\
+             {sha} 2 2\n\
+             \t}}\n\
+             {zero} 3 3 1\n\
+             author Not Committed Yet\n\
+             author-mail <not.committed.yet>\n\
+             author-time 1700000100\n\
+             summary Version of src/lib.rs from src/lib.rs\n\
+             filename src/lib.rs\n\
+             \t// wip\n",
+            sha = sha,
+            zero = zero
+        );
+
+        let lines = parse_blame_porcelain(&output);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].line, 1);
+        assert_eq!(lines[0].author, "Ada");
+        assert_eq!(lines[0].author_mail, "ada@example.com");
+        assert_eq!(lines[0].timestamp, 1_700_000_000);
+        assert_eq!(lines[0].content, "fn main() {");
+        // the second line of the group carries no header block of its own
+        assert_eq!(lines[1].line, 2);
+        assert_eq!(lines[1].author, "Ada");
+        assert_eq!(lines[1].summary, "Add parser");
+        assert!(!lines[1].uncommitted);
+        assert!(lines[2].uncommitted);
+        assert_eq!(lines[2].content, "// wip");
+    }
+
+    #[test]
+    fn parses_blame_porcelain_from_sha256_repos() {
+        let sha = "b".repeat(64);
+        let output = format!("{} 1 1 1\nauthor Ada\nsummary Init\n\tx\n", sha);
+        let lines = parse_blame_porcelain(&output);
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].commit, sha);
+        assert_eq!(lines[0].author, "Ada");
+    }
+
+    #[test]
+    fn parses_submodule_status_states() {
+        let sha = "b".repeat(40);
+        let output = format!(
+            " {sha} libs/core (v1.2.0)\n+{sha} libs/ui (heads/main)\n-{sha} vendor/old\nU{sha} libs/conflicted\n",
+            sha = sha
+        );
+        let subs = parse_submodule_status(&output);
+        assert_eq!(subs.len(), 4);
+        assert_eq!(subs[0].path, "libs/core");
+        assert_eq!(subs[0].state, "clean");
+        assert_eq!(subs[0].describe.as_deref(), Some("v1.2.0"));
+        assert_eq!(subs[0].commit, sha);
+        assert_eq!(subs[1].state, "outOfSync");
+        assert_eq!(subs[2].path, "vendor/old");
+        assert_eq!(subs[2].state, "uninitialized");
+        assert_eq!(subs[2].describe, None);
+        assert_eq!(subs[3].state, "conflict");
+    }
+
+    #[test]
+    fn scopes_operations_to_nested_repos() {
+        let app = "ssh://box/srv/app";
+        assert_eq!(scoped_repo(app, None).unwrap(), app);
+        assert_eq!(scoped_repo(app, Some("")).unwrap(), app);
+        assert_eq!(
+            scoped_repo(app, Some("libs/core/")).unwrap(),
+            "ssh://box/srv/app/libs/core"
+        );
+        for escape in [
+            "../other",
+            "libs/../../etc",
+            "/etc",
+            "C:/Windows",
+            "libs\\..\\..",
+        ] {
+            assert!(scoped_repo(app, Some(escape)).is_err(), "{}", escape);
+        }
+    }
+
+    #[test]
+    fn parses_line_history_records() {
+        let output = "\x1eabc\x1fAda\x1fada@example.com\x1f1700000000\x1fTweak loop\n\n\
+                      diff --git a/x.rs b/x.rs\n@@ -1 +1 @@\n-a\n+b\n\n\
+                      \x1edef\x1fBot\x1fbot@example.com\x1f1690000000\x1fAdd loop\n\n\
+                      diff --git a/x.rs b/x.rs\n@@ -0,0 +1 @@\n+a\n";
+        let entries = parse_line_history(output);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].commit, "abc");
+        assert_eq!(entries[0].summary, "Tweak loop");
+        assert!(entries[0].diff.starts_with("diff --git"));
+        assert!(entries[0].diff.ends_with("+b"));
+        assert_eq!(entries[1].author, "Bot");
+        assert_eq!(entries[1].timestamp, 1_690_000_000);
+    }
+}
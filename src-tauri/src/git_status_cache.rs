@@ -0,0 +1,360 @@
+// per-project git status, kept fresh by watching the repo instead of being polled
+use crate::commands::HARDCODED_SKIP;
+use crate::file_watcher;
+use crate::git::{self, GitStatus};
+use crate::remote::{self, Location, SshTarget};
+use crate::remote_index::RemoteFileIndex;
+use notify_debouncer_mini::{notify, Debouncer};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::Emitter;
+
+/// How often a remote repo's `.git/index` and `HEAD` are stat'ed.
+const REMOTE_POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Working-tree edits on a remote don't touch the index, so remote status is also
+/// recomputed this often regardless.
+const REMOTE_FULL_REFRESH: Duration = Duration::from_secs(30);
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GitStatusChangedPayload {
+    project_path: String,
+    status: GitStatus,
+}
+
+// held only to keep the watch alive; dropping it stops the watcher or poller
+enum Invalidator {
+    Local {
+        _debouncer: Debouncer<notify::RecommendedWatcher>,
+    },
+    Remote {
+        _stop: StopFlag,
+    },
+}
+
+struct StopFlag(Arc<AtomicBool>);
+
+impl Drop for StopFlag {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+struct ProjectEntry {
+    /// Last computed status, kept after invalidation so refreshes can tell if it moved.
+    status: Option<GitStatus>,
+    stale: bool,
+    watchers: usize,
+    _invalidator: Invalidator,
+}
+
+#[derive(Clone)]
+pub struct GitStatusCache {
+    app: tauri::AppHandle,
+    projects: Arc<Mutex<HashMap<String, ProjectEntry>>>,
+    /// Remote file trees, kept current from the same status refreshes.
+    file_index: RemoteFileIndex,
+}
+
+impl GitStatusCache {
+    pub fn new(app: tauri::AppHandle, file_index: RemoteFileIndex) -> Self {
+        Self {
+            app,
+            projects: Arc::new(Mutex::new(HashMap::new())),
+            file_index,
+        }
+    }
+
+    /// Cached status for a watched project; unwatched projects are always computed fresh
+    /// since nothing would invalidate them.
+    pub fn status(&self, project_path: &str) -> GitStatus {
+        if let Ok(projects) = self.projects.lock() {
+            if let Some(entry) = projects.get(project_path).filter(|p| !p.stale) {
+                if let Some(status) = &entry.status {
+                    return status.clone();
+                }
+            }
+        }
+        let status = git::get_status(project_path);
+        if let Ok(mut projects) = self.projects.lock() {
+            if let Some(entry) = projects.get_mut(project_path) {
+                entry.status = Some(status.clone());
+                entry.stale = false;
+            }
+        }
+        status
+    }
+
+    /// Drops the cached status so the next read recomputes it.
+    pub fn invalidate(&self, project_path: &str) {
+        if let Ok(mut projects) = self.projects.lock() {
+            if let Some(entry) = projects.get_mut(project_path) {
+                entry.stale = true;
+            }
+        }
+    }
+
+    /// Recomputes a watched project's status and emits `git-status-changed` if it moved.
+    fn refresh(&self, project_path: &str) {
+        let status = git::get_status(project_path);
+        let changed = {
+            let Ok(mut projects) = self.projects.lock() else {
+                return;
+            };
+            let Some(entry) = projects.get_mut(project_path) else {
+                return;
+            };
+            let changed = entry.status.as_ref() != Some(&status);
+            if changed {
+                self.file_index
+                    .apply_status(project_path, entry.status.as_ref(), &status);
+            }
+            entry.status = Some(status.clone());
+            entry.stale = false;
+            changed
+        };
+        if changed {
+            let _ = self.app.emit(
+                "git-status-changed",
+                GitStatusChangedPayload {
+                    project_path: project_path.to_string(),
+                    status,
+                },
+            );
+        }
+    }
+
+    pub fn watch(&self, project_path: &str) -> Result<(), String> {
+        {
+            let mut projects = self.projects.lock().map_err(|e| e.to_string())?;
+            if let Some(entry) = projects.get_mut(project_path) {
+                entry.watchers += 1;
+                return Ok(());
+            }
+        }
+
+        let invalidator = match remote::locate(project_path) {
+            Location::Local(path) => Invalidator::Local {
+                _debouncer: self.watch_local(project_path, &path)?,
+            },
+            Location::Remote(target) => Invalidator::Remote {
+                _stop: self.poll_remote(project_path, target),
+            },
+        };
+
+        let mut projects = self.projects.lock().map_err(|e| e.to_string())?;
+        match projects.get_mut(project_path) {
+            // raced with another watch() while the watcher was being set up
+            Some(entry) => entry.watchers += 1,
+            None => {
+                projects.insert(
+                    project_path.to_string(),
+                    ProjectEntry {
+                        status: None,
+                        stale: true,
+                        watchers: 1,
+                        _invalidator: invalidator,
+                    },
+                );
+            }
+        }
+        Ok(())
+    }
+
+    pub fn unwatch(&self, project_path: &str) -> Result<(), String> {
+        let removed = {
+            let mut projects = self.projects.lock().map_err(|e| e.to_string())?;
+            match projects.get_mut(project_path) {
+                Some(entry) if entry.watchers > 1 => {
+                    entry.watchers -= 1;
+                    None
+                }
+                Some(_) => projects.remove(project_path),
+                None => None,
+            }
+        };
+        // the watcher is dropped outside the lock — its thread may be waiting on it
+        drop(removed);
+        Ok(())
+    }
+
+    pub fn cleanup(&self) {
+        let drained: Vec<ProjectEntry> = match self.projects.lock() {
+            Ok(mut projects) => projects.drain().map(|(_, entry)| entry).collect(),
+            Err(_) => return,
+        };
+        drop(drained);
+    }
+
+    fn watch_local(
+        &self,
+        project_path: &str,
+        path: &str,
+    ) -> Result<Debouncer<notify::RecommendedWatcher>, String> {
+        let cache = self.clone();
+        let key = project_path.to_string();
+        let root = PathBuf::from(path);
+        file_watcher::watch_tree(Path::new(path), move |paths| {
+            if changes_status(&root, &paths) {
+                cache.invalidate(&key);
+                cache.refresh(&key);
+            }
+        })
+    }
+
+    fn poll_remote(&self, project_path: &str, target: SshTarget) -> StopFlag {
+        let stop = Arc::new(AtomicBool::new(false));
+        let cache = self.clone();
+        let key = project_path.to_string();
+        let thread_stop = stop.clone();
+        // nobody is waiting on a poll, so it queues behind interactive commands
+        std::thread::spawn(move || {
+            remote::in_background(|| {
+                let mut fingerprint = String::new();
+                let mut last_refresh: Option<Instant> = None;
+                while !thread_stop.load(Ordering::Relaxed) {
+                    let current = index_fingerprint(&target).unwrap_or_default();
+                    let due = last_refresh.is_none_or(|t| t.elapsed() >= REMOTE_FULL_REFRESH);
+                    if current != fingerprint {
+                        // commits, checkouts and pulls change tracked files without
+                        // leaving a trace in status
+                        cache.file_index.invalidate(&key);
+                    }
+                    if current != fingerprint || due {
+                        fingerprint = current;
+                        last_refresh = Some(Instant::now());
+                        cache.refresh(&key);
+                    }
+                    std::thread::sleep(REMOTE_POLL_INTERVAL);
+                }
+            })
+        });
+        StopFlag(stop)
+    }
+}
+
+/// Object writes, reflogs and lock files churn on every git command without changing
+/// what `status` reports.
+fn affects_status(path: &Path) -> bool {
+    let text = path.to_string_lossy().replace('\\', "/");
+    let Some((_, inside)) = text.split_once("/.git/") else {
+        return !text.ends_with("/.git");
+    };
+    !(inside.starts_with("objects/")
+        || inside.starts_with("logs/")
+        || inside.ends_with(".lock")
+        || inside == "FETCH_HEAD")
+}
+
+/// Whether a batch of changed paths can move `git status`: git's own state (see
+/// `affects_status`), or a worktree file that is neither skipped nor gitignored. Build
+/// output and dependency installs would otherwise re-run status on every write.
+fn changes_status(root: &Path, paths: &[PathBuf]) -> bool {
+    let mut worktree: Vec<String> = Vec::new();
+    for path in paths {
+        let rel = path.strip_prefix(root).unwrap_or(path);
+        let rel = rel.to_string_lossy().replace('\\', "/");
+        if rel == ".git" || rel.starts_with(".git/") {
+            if affects_status(path) {
+                return true;
+            }
+        } else if !rel.split('/').any(|part| HARDCODED_SKIP.contains(&part)) {
+            worktree.push(rel);
+        }
+    }
+    !worktree.is_empty() && count_ignored(root, &worktree) < worktree.len()
+}
+
+/// How many of the project-relative paths git ignores, in one `check-ignore` call.
+/// Tracked files are never reported, so edits to them always count. Paths are fed from
+/// another thread: git answers as it reads, and a big batch would otherwise fill the
+/// output pipe while we are still writing.
+fn count_ignored(root: &Path, rels: &[String]) -> usize {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let mut cmd = Command::new("git");
+    cmd.args(["check-ignore", "--stdin", "-z"])
+        .current_dir(root)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+    let Ok(mut child) = cmd.spawn() else {
+        return 0;
+    };
+    let writer = child.stdin.take().map(|mut stdin| {
+        let mut input = rels.join("\0");
+        input.push('\0');
+        std::thread::spawn(move || {
+            let _ = stdin.write_all(input.as_bytes());
+        })
+    });
+    // exit code 1 just means nothing matched
+    let output = child.wait_with_output();
+    if let Some(writer) = writer {
+        let _ = writer.join();
+    }
+    match output {
+        Ok(output) => output
+            .stdout
+            .split(|b| *b == 0)
+            .filter(|path| !path.is_empty())
+            .count(),
+        Err(_) => 0,
+    }
+}
+
+/// mtimes and sizes of the remote repo's index and HEAD; changes whenever anything is
+/// staged, committed, checked out or reset.
+fn index_fingerprint(target: &SshTarget) -> Result<String, String> {
+    let command = "d=$(git rev-parse --git-dir 2>/dev/null) && \
+                   stat -c '%Y %s' \"$d/index\" \"$d/HEAD\" 2>/dev/null";
+    remote::run(target, Some(&target.path), command).map(|output| output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ignored_and_skipped_paths_leave_status_alone() {
+        let dir = std::env::temp_dir().join(format!("cc-status-{}", uuid::Uuid::new_v4().simple()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let init = remote::run_local(&dir.to_string_lossy(), "git", &["init", "-q"], &[]);
+        if !init.map(|o| o.ok).unwrap_or(false) {
+            return;
+        }
+        std::fs::write(dir.join(".gitignore"), "*.log\ncoverage/\n").unwrap();
+        let changed = |rels: &[&str]| {
+            let paths: Vec<PathBuf> = rels.iter().map(|r| dir.join(r)).collect();
+            changes_status(&dir, &paths)
+        };
+
+        assert!(!changed(&[
+            "debug.log",
+            "node_modules/x/index.js",
+            ".git/objects/ab/cd"
+        ]));
+        assert!(changed(&["debug.log", "src/main.rs"]));
+        assert!(changed(&[".git/index"]));
+
+        // more answers than a pipe holds, with a name git would otherwise quote
+        let mut batch: Vec<String> = (0..5000)
+            .map(|i| format!("coverage/lcov-report/src/file_{}.ts.html", i))
+            .collect();
+        batch.push("coverage/caf\u{e9} \"x\".html".to_string());
+        let paths: Vec<PathBuf> = batch.iter().map(|r| dir.join(r)).collect();
+        assert!(!changes_status(&dir, &paths));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,445 @@
+// snapshots taken before destructive git operations, kept on a reflog so they can be undone
+use crate::git::{run_git, run_git_env};
+use serde::Serialize;
+
+/// Each snapshot is a commit this ref pointed at; its reflog is the undo history, newest
+/// first, the same way `refs/stash` works.
+const UNDO_REF: &str = "refs/circuitclaude/undo";
+
+/// Scratch index for building snapshot trees without touching the real one. Rewritten
+/// wholesale by every snapshot, so it's left in place.
+const SCRATCH_INDEX: &str = "circuitclaude-undo-index";
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoEntry {
+    /// Snapshot commit holding the affected content.
+    pub commit: String,
+    /// "revert" | "reset" | "deleteBranch"
+    pub op: String,
+    pub summary: String,
+    pub timestamp: i64,
+    /// HEAD when the snapshot was taken.
+    pub head: Option<String>,
+    /// Worktree paths the operation overwrote or removed.
+    pub paths: Vec<String>,
+    /// Deleted branch name and tip, or the reset mode.
+    pub branch: Option<String>,
+    pub tip: Option<String>,
+    pub mode: Option<String>,
+    /// Commit holding the index when the snapshot was taken; absent if it had conflicts.
+    pub index: Option<String>,
+}
+
+/// What a snapshot is for; `record` turns it into the snapshot commit's message.
+pub struct Snapshot<'a> {
+    pub op: &'a str,
+    pub summary: String,
+    pub paths: &'a [&'a str],
+    pub branch: Option<(&'a str, &'a str)>,
+    pub mode: Option<&'a str>,
+}
+
+fn head_commit(project_path: &str) -> Option<String> {
+    run_git(project_path, &["rev-parse", "-q", "--verify", "HEAD"])
+        .ok()
+        .filter(|o| o.ok)
+        .map(|o| o.stdout.trim().to_string())
+}
+
+/// Saves the current content of `snapshot.paths` (tracked or not, present or deleted) as a
+/// commit on the undo ref. Operations must not proceed if this fails.
+pub fn record(project_path: &str, snapshot: &Snapshot) -> Result<String, String> {
+    let head = head_commit(project_path);
+    // snapshots are internal, so they shouldn't depend on the user having an identity set
+    let identity = [
+        ("GIT_AUTHOR_NAME", "CircuitClaude"),
+        ("GIT_AUTHOR_EMAIL", "undo@circuitclaude"),
+        ("GIT_COMMITTER_NAME", "CircuitClaude"),
+        ("GIT_COMMITTER_EMAIL", "undo@circuitclaude"),
+    ];
+
+    // staged work isn't in any commit, so the index gets one of its own, the way a stash
+    // keeps it; an index with conflicts can't be written and goes without
+    let index = match run_git(project_path, &["write-tree"])? {
+        tree if tree.ok => {
+            let args = ["commit-tree", tree.stdout.trim(), "-m", "index"];
+            let commit = run_git_env(project_path, &identity, &args)?;
+            if !commit.ok {
+                return Err(format!("Failed to snapshot: {}", commit.err_text()));
+            }
+            Some(commit.stdout.trim().to_string())
+        }
+        _ => None,
+    };
+
+    let index_path = run_git(project_path, &["rev-parse", "--git-path", SCRATCH_INDEX])?;
+    if !index_path.ok {
+        return Err(format!("Failed to snapshot: {}", index_path.err_text()));
+    }
+    let index_path = index_path.stdout.trim().to_string();
+    let env = [("GIT_INDEX_FILE", index_path.as_str())];
+
+    let base = head.as_deref().unwrap_or("--empty");
+    let read = run_git_env(project_path, &env, &["read-tree", base])?;
+    if !read.ok {
+        return Err(format!("Failed to snapshot: {}", read.err_text()));
+    }
+    if !snapshot.paths.is_empty() {
+        let mut args = vec!["add", "-A", "--"];
+        args.extend(snapshot.paths);
+        let add = run_git_env(project_path, &env, &args)?;
+        if !add.ok {
+            return Err(format!("Failed to snapshot: {}", add.err_text()));
+        }
+    }
+    let tree = run_git_env(project_path, &env, &["write-tree"])?;
+    if !tree.ok {
+        return Err(format!("Failed to snapshot: {}", tree.err_text()));
+    }
+    let tree = tree.stdout.trim().to_string();
+
+    let message = snapshot_message(snapshot, head.as_deref(), index.as_deref());
+    let mut args = vec!["commit-tree", tree.as_str(), "-m", message.as_str()];
+    // parents keep what the operation drops reachable: the old HEAD, a deleted branch
+    // tip, the index
+    if let Some(head) = &head {
+        args.extend(["-p", head.as_str()]);
+    }
+    if let Some((_, tip)) = snapshot.branch {
+        args.extend(["-p", tip]);
+    }
+    if let Some(index) = &index {
+        args.extend(["-p", index.as_str()]);
+    }
+    let commit = run_git_env(project_path, &identity, &args)?;
+    if !commit.ok {
+        return Err(format!("Failed to snapshot: {}", commit.err_text()));
+    }
+    let commit = commit.stdout.trim().to_string();
+
+    let update = run_git(
+        project_path,
+        &[
+            "update-ref",
+            "--create-reflog",
+            "-m",
+            &snapshot.summary,
+            UNDO_REF,
+            &commit,
+        ],
+    )?;
+    if !update.ok {
+        return Err(format!("Failed to snapshot: {}", update.err_text()));
+    }
+    Ok(commit)
+}
+
+fn snapshot_message(snapshot: &Snapshot, head: Option<&str>, index: Option<&str>) -> String {
+    let mut message = format!("{}\n\nop: {}\n", snapshot.summary, snapshot.op);
+    if let Some(head) = head {
+        message.push_str(&format!("head: {}\n", head));
+    }
+    if let Some(index) = index {
+        message.push_str(&format!("index: {}\n", index));
+    }
+    if let Some((name, tip)) = snapshot.branch {
+        message.push_str(&format!("branch: {}\ntip: {}\n", name, tip));
+    }
+    if let Some(mode) = snapshot.mode {
+        message.push_str(&format!("mode: {}\n", mode));
+    }
+    for path in snapshot.paths {
+        message.push_str(&format!("path: {}\n", path));
+    }
+    message
+}
+
+/// Undo history, newest first.
+pub fn history(project_path: &str) -> Result<Vec<UndoEntry>, String> {
+    let exists = run_git(project_path, &["rev-parse", "-q", "--verify", UNDO_REF])?;
+    if !exists.ok {
+        return Ok(Vec::new());
+    }
+    let output = run_git(
+        project_path,
+        &["log", "-g", "--format=%x1e%H%x1f%ct%x1f%B", UNDO_REF],
+    )?;
+    if !output.ok {
+        return Err(format!(
+            "Failed to read undo history: {}",
+            output.err_text()
+        ));
+    }
+    Ok(parse_history(&output.stdout))
+}
+
+fn parse_history(output: &str) -> Vec<UndoEntry> {
+    let mut entries = Vec::new();
+    for record in output.split('\x1e').filter(|r| !r.trim().is_empty()) {
+        let mut fields = record.splitn(3, '\x1f');
+        let commit = fields.next().unwrap_or("").trim().to_string();
+        let timestamp = fields.next().unwrap_or("").trim().parse().unwrap_or(0);
+        let body = fields.next().unwrap_or("");
+
+        let mut entry = UndoEntry {
+            commit,
+            op: String::new(),
+            summary: body.lines().next().unwrap_or("").to_string(),
+            timestamp,
+            head: None,
+            paths: Vec::new(),
+            branch: None,
+            tip: None,
+            mode: None,
+            index: None,
+        };
+        for line in body.lines().skip(1) {
+            let Some((key, value)) = line.split_once(": ") else {
+                continue;
+            };
+            let value = value.to_string();
+            match key {
+                "op" => entry.op = value,
+                "head" => entry.head = Some(value),
+                "branch" => entry.branch = Some(value),
+                "tip" => entry.tip = Some(value),
+                "mode" => entry.mode = Some(value),
+                "index" => entry.index = Some(value),
+                "path" => entry.paths.push(value),
+                _ => {}
+            }
+        }
+        if !entry.op.is_empty() {
+            entries.push(entry);
+        }
+    }
+    entries
+}
+
+/// Restores what the most recent destructive operation removed and drops it from history.
+pub fn undo_last(project_path: &str) -> Result<UndoEntry, String> {
+    let entry = history(project_path)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| "Nothing to undo".to_string())?;
+
+    match entry.op.as_str() {
+        "revert" => restore_paths(project_path, &entry)?,
+        "reset" => {
+            let head = entry
+                .head
+                .as_deref()
+                .ok_or_else(|| "Snapshot has no HEAD to return to".to_string())?;
+            // HEAD goes back, then the index as it was, staged work included; older
+            // snapshots without one get HEAD's tree
+            let mode = match (&entry.index, entry.mode.as_deref()) {
+                (Some(_), _) => "--soft",
+                (None, Some("hard")) => "--hard",
+                (None, Some("mixed")) => "--mixed",
+                (None, _) => "--soft",
+            };
+            let output = run_git(project_path, &["reset", mode, head])?;
+            if !output.ok {
+                return Err(format!("git reset failed: {}", output.err_text()));
+            }
+            if let Some(index) = &entry.index {
+                let output = run_git(project_path, &["read-tree", index])?;
+                if !output.ok {
+                    return Err(format!("git read-tree failed: {}", output.err_text()));
+                }
+            }
+            if entry.mode.as_deref() == Some("hard") {
+                restore_paths(project_path, &entry)?;
+            }
+        }
+        "deleteBranch" => {
+            let (Some(name), Some(tip)) = (entry.branch.as_deref(), entry.tip.as_deref()) else {
+                return Err("Snapshot is missing the deleted branch".to_string());
+            };
+            let output = run_git(project_path, &["branch", name, tip])?;
+            if !output.ok {
+                return Err(format!("git branch failed: {}", output.err_text()));
+            }
+        }
+        other => return Err(format!("Don't know how to undo '{}'", other)),
+    }
+
+    drop_latest(project_path)?;
+    Ok(entry)
+}
+
+/// Puts the snapshotted worktree content back, deleting paths that didn't exist then.
+/// The index is left alone.
+fn restore_paths(project_path: &str, entry: &UndoEntry) -> Result<(), String> {
+    if entry.paths.is_empty() {
+        return Ok(());
+    }
+    let source = format!("--source={}", entry.commit);
+    let mut args = vec!["restore", source.as_str(), "--worktree", "--"];
+    args.extend(entry.paths.iter().map(|p| p.as_str()));
+    let output = run_git(project_path, &args)?;
+    if !output.ok {
+        return Err(format!("git restore failed: {}", output.err_text()));
+    }
+    Ok(())
+}
+
+/// Removes the newest snapshot, for after it's been undone or its operation failed
+/// without changing anything.
+pub fn drop_latest(project_path: &str) -> Result<(), String> {
+    let count = run_git(
+        project_path,
+        &["rev-list", "--walk-reflogs", "--count", UNDO_REF],
+    )?;
+    let last = count.ok && count.stdout.trim() == "1";
+    // deleting the only reflog entry would leave the ref behind, as `stash drop` knows
+    let output = if last {
+        run_git(project_path, &["update-ref", "-d", UNDO_REF])?
+    } else {
+        let newest = format!("{}@{{0}}", UNDO_REF);
+        run_git(
+            project_path,
+            &["reflog", "delete", "--updateref", "--rewrite", &newest],
+        )?
+    };
+    if !output.ok {
+        return Err(format!("Failed to drop undo entry: {}", output.err_text()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs git in `repo` with a throwaway identity, reporting whether it succeeded.
+    fn git_ok(repo: &str, args: &[&str]) -> bool {
+        let identity = [
+            ("GIT_AUTHOR_NAME", "t"),
+            ("GIT_AUTHOR_EMAIL", "t@t"),
+            ("GIT_COMMITTER_NAME", "t"),
+            ("GIT_COMMITTER_EMAIL", "t@t"),
+        ];
+        run_git_env(repo, &identity, args)
+            .map(|o| o.ok)
+            .unwrap_or(false)
+    }
+
+    #[test]
+    fn parses_snapshot_messages_back_into_entries() {
+        let snapshot = Snapshot {
+            op: "deleteBranch",
+            summary: "delete branch topic".to_string(),
+            paths: &["a.txt", "dir/b c.txt"],
+            branch: Some(("topic", "bbb")),
+            mode: None,
+        };
+        let body = snapshot_message(&snapshot, Some("aaa"), Some("ttt"));
+        let log = format!("\x1eccc\x1f1700000000\x1f{}\n", body);
+
+        let entries = parse_history(&log);
+        assert_eq!(entries.len(), 1);
+        let e = &entries[0];
+        assert_eq!(e.commit, "ccc");
+        assert_eq!(e.op, "deleteBranch");
+        assert_eq!(e.summary, "delete branch topic");
+        assert_eq!(e.timestamp, 1_700_000_000);
+        assert_eq!(e.head.as_deref(), Some("aaa"));
+        assert_eq!(e.index.as_deref(), Some("ttt"));
+        assert_eq!(e.branch.as_deref(), Some("topic"));
+        assert_eq!(e.tip.as_deref(), Some("bbb"));
+        assert_eq!(e.paths, vec!["a.txt", "dir/b c.txt"]);
+    }
+
+    #[test]
+    fn undoes_a_revert_of_modified_deleted_and_untracked_files() {
+        let dir = std::env::temp_dir().join(format!("cc-undo-{}", uuid::Uuid::new_v4().simple()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let repo = dir.to_string_lossy().to_string();
+        let git = |args: &[&str]| git_ok(&repo, args);
+        if !git(&["init", "-q"]) {
+            return;
+        }
+        std::fs::write(dir.join("keep.txt"), "one\n").unwrap();
+        std::fs::write(dir.join("gone.txt"), "one\n").unwrap();
+        assert!(git(&["add", "."]));
+        assert!(git(&["commit", "-qm", "init"]));
+
+        std::fs::write(dir.join("keep.txt"), "agent work\n").unwrap();
+        std::fs::remove_file(dir.join("gone.txt")).unwrap();
+        std::fs::write(dir.join("new.txt"), "fresh\n").unwrap();
+        let status = crate::git::get_status(&repo);
+        crate::git::revert(&repo, &status.files).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.join("keep.txt")).unwrap(),
+            "one\n"
+        );
+        assert!(dir.join("gone.txt").exists());
+        assert!(!dir.join("new.txt").exists());
+        assert_eq!(history(&repo).unwrap().len(), 1);
+
+        let undone = undo_last(&repo).unwrap();
+        assert_eq!(undone.op, "revert");
+        assert_eq!(
+            std::fs::read_to_string(dir.join("keep.txt")).unwrap(),
+            "agent work\n"
+        );
+        assert!(!dir.join("gone.txt").exists());
+        assert_eq!(
+            std::fs::read_to_string(dir.join("new.txt")).unwrap(),
+            "fresh\n"
+        );
+        assert!(history(&repo).unwrap().is_empty());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn undoes_resets_back_to_the_index_they_found() {
+        let dir = std::env::temp_dir().join(format!("cc-undo-{}", uuid::Uuid::new_v4().simple()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let repo = dir.to_string_lossy().to_string();
+        let git = |args: &[&str]| git_ok(&repo, args);
+        if !git(&["init", "-q"]) {
+            return;
+        }
+        for content in ["one\n", "two\n"] {
+            std::fs::write(dir.join("a.txt"), content).unwrap();
+            assert!(git(&["add", "."]));
+            assert!(git(&["commit", "-qm", content.trim()]));
+        }
+
+        for mode in ["soft", "mixed"] {
+            crate::git::reset(&repo, "HEAD~1", mode).unwrap();
+            assert!(!crate::git::get_status(&repo).files.is_empty());
+
+            let undone = undo_last(&repo).unwrap();
+            assert_eq!(undone.mode.as_deref(), Some(mode));
+            assert!(crate::git::get_status(&repo).files.is_empty(), "{}", mode);
+        }
+
+        // partly staged: one change in the index, another on top of it in the worktree
+        std::fs::write(dir.join("a.txt"), "staged\n").unwrap();
+        assert!(git(&["add", "a.txt"]));
+        std::fs::write(dir.join("a.txt"), "unstaged\n").unwrap();
+        let staged = |repo: &str| run_git(repo, &["show", ":a.txt"]).unwrap().stdout;
+        for mode in ["soft", "mixed", "hard"] {
+            crate::git::reset(&repo, "HEAD~1", mode).unwrap();
+            undo_last(&repo).unwrap();
+            assert_eq!(staged(&repo), "staged\n", "{}", mode);
+            assert_eq!(
+                std::fs::read_to_string(dir.join("a.txt")).unwrap(),
+                "unstaged\n",
+                "{}",
+                mode
+            );
+            let head = run_git(&repo, &["log", "-1", "--format=%s"])
+                .unwrap()
+                .stdout;
+            assert_eq!(head.trim(), "two", "{}", mode);
+        }
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -4,11 +4,21 @@ mod claude_manager;
 mod commands;
 mod config;
 mod conversation;
+mod credentials;
+mod devcontainer;
+mod diff_condense;
 mod file_watcher;
 mod git;
+mod git_status_cache;
+mod git_undo;
 mod pi_manager;
 mod pty_manager;
 mod remote;
+mod remote_health;
+mod remote_index;
+mod remote_watch;
+mod ssh_config;
+mod transfer;
 mod whisper_manager;
 
 #[cfg(not(debug_assertions))]
@@ -74,8 +84,25 @@ pub fn run() {
             std::fs::create_dir_all(&models_dir).ok();
             app.manage(whisper_manager::WhisperManager::new(models_dir));
             app.manage(file_watcher::FileWatcherManager::new(app.handle().clone()));
+            let file_index =
+                remote_index::RemoteFileIndex::new(config::remote_file_index(&app.handle()));
+            app.manage(file_index.clone());
+            app.manage(git_status_cache::GitStatusCache::new(
+                app.handle().clone(),
+                file_index,
+            ));
+            app.manage(remote_health::RemoteHealthMonitor::new(app.handle().clone()));
 
             config::sync_remotes(&app.handle());
+            if config::unlock_credentials_on_start(&app.handle()) {
+                // the keyring may prompt to unlock itself, so keep it off the setup path
+                let handle = app.handle().clone();
+                std::thread::spawn(move || {
+                    if let Err(e) = config::unlock_credentials(&handle) {
+                        eprintln!("Failed to unlock saved credentials: {}", e);
+                    }
+                });
+            }
 
             Ok(())
         })
@@ -91,6 +118,7 @@ pub fn run() {
             commands::get_pty_session_info,
             commands::load_tracked_pi_session,
             commands::create_claude_session,
+            commands::list_claude_sessions,
             commands::send_claude_message,
             commands::respond_to_permission,
             commands::respond_to_question,
@@ -114,15 +142,34 @@ pub fn run() {
             commands::load_remotes,
             commands::save_remotes,
             commands::list_remote_dirs,
+            commands::list_ssh_hosts,
+            commands::get_remote_credentials_state,
+            commands::unlock_remote_credentials,
+            commands::store_remote_credentials,
+            commands::forget_remote_credentials,
             commands::load_note,
             commands::save_note,
             commands::get_git_status,
+            commands::watch_git_status,
+            commands::unwatch_git_status,
+            commands::get_nested_git_status,
+            commands::get_remote_health,
+            commands::watch_remote_health,
+            commands::unwatch_remote_health,
+            commands::list_git_submodules,
             commands::get_git_diff,
             commands::git_commit,
             commands::git_revert,
+            commands::git_reset,
+            commands::git_delete_branch,
+            commands::get_git_undo_history,
+            commands::git_undo_last,
             commands::get_git_diff_stats,
             commands::git_push,
             commands::generate_commit_message,
+            commands::generate_pr_description,
+            commands::git_blame,
+            commands::git_line_history,
             commands::read_conversation,
             commands::get_conversation_mtime,
             commands::read_claude_md,
@@ -132,6 +179,21 @@ pub fn run() {
             commands::read_file,
             commands::write_file,
             commands::save_clipboard_image,
+            commands::save_clipboard_image_for_project,
+            commands::upload_remote_file,
+            commands::download_remote_file,
+            commands::detect_remote_ports,
+            commands::open_port_forward,
+            commands::close_port_forward,
+            commands::list_port_forwards,
+            commands::list_detached_sessions,
+            commands::kill_detached_session,
+            commands::cancel_remote_commands,
+            commands::run_remote_command,
+            commands::cancel_remote_run,
+            commands::diagnose_remote_host,
+            commands::resolve_devcontainer,
+            commands::refresh_remote_file_index,
             commands::exit_app,
             commands::whisper_start_session,
             commands::whisper_push_audio,
@@ -160,6 +222,12 @@ pub fn run() {
                 whisper_manager.cancel_all();
                 let file_watcher = app.state::<file_watcher::FileWatcherManager>();
                 file_watcher.cleanup();
+                let git_status_cache = app.state::<git_status_cache::GitStatusCache>();
+                git_status_cache.cleanup();
+                let file_index = app.state::<remote_index::RemoteFileIndex>();
+                file_index.cleanup();
+                let health_monitor = app.state::<remote_health::RemoteHealthMonitor>();
+                health_monitor.cleanup();
                 remote::shutdown();
                 config::cleanup_old_screenshots(&app.app_handle());
             }
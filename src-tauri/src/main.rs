@@ -3,8 +3,15 @@
 
 fn main() {
     if std::env::var_os("CIRCUITCLAUDE_ASKPASS").is_some() {
-        if let Ok(password) = std::env::var("CIRCUITCLAUDE_SSH_PASSWORD") {
-            println!("{}", password);
+        // ssh passes its prompt as the only argument
+        let prompt = std::env::args().nth(1).unwrap_or_default().to_lowercase();
+        let secret = if prompt.contains("passphrase") {
+            "CIRCUITCLAUDE_SSH_PASSPHRASE"
+        } else {
+            "CIRCUITCLAUDE_SSH_PASSWORD"
+        };
+        if let Ok(value) = std::env::var(secret) {
+            println!("{}", value);
         }
         return;
     }
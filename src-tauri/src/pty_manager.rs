@@ -4,7 +4,7 @@ use serde::Serialize;
 use std::collections::{HashMap, VecDeque};
 use std::io::{Read, Write};
 use std::sync::{Arc, Mutex};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tauri::ipc::Channel;
 
 pub type SessionId = String;
@@ -13,6 +13,13 @@ pub type SubscriberId = String;
 const MAX_REPLAY_BYTES: usize = 1024 * 1024;
 const MAX_REPLAY_CHUNKS: usize = 2048;
 
+/// ssh's exit code when the connection itself failed, as opposed to the remote command.
+const SSH_CONNECTION_LOST: u32 = 255;
+
+/// How long a dropped remote session keeps trying to reattach before it counts as exited.
+const REATTACH_WINDOW: Duration = Duration::from_secs(300);
+const REATTACH_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub enum SessionState {
@@ -72,6 +79,21 @@ struct PtySession {
     writer: Mutex<Box<dyn Write + Send>>,
     child: Mutex<Box<dyn portable_pty::Child + Send>>,
     meta: Mutex<PtySessionMeta>,
+    /// For remote sessions running inside a detached session on the host (tmux, dtach),
+    /// where to attach again after the connection drops.
+    reattach: Option<Reattach>,
+}
+
+struct Reattach {
+    project_path: String,
+    name: String,
+}
+
+struct Spawned {
+    master: Box<dyn MasterPty + Send>,
+    writer: Box<dyn Write + Send>,
+    reader: Box<dyn Read + Send>,
+    child: Box<dyn portable_pty::Child + Send>,
 }
 
 pub struct PtyManager {
@@ -92,37 +114,34 @@ impl PtyManager {
         rows: u16,
         session_type: &str,
         command: &str,
+        detached_name: Option<&str>,
     ) -> Result<SessionId, String> {
-        let cmd = Self::build_command(project_path, command)?;
-
-        let pty_system = native_pty_system();
-        let pair = pty_system
-            .openpty(PtySize {
-                rows,
-                cols,
-                pixel_width: 0,
-                pixel_height: 0,
-            })
-            .map_err(|e| format!("Failed to open PTY: {}", e))?;
-
-        let child = pair
-            .slave
-            .spawn_command(cmd)
-            .map_err(|e| format!("Failed to spawn process: {}", e))?;
-        drop(pair.slave);
-
-        let reader = pair
-            .master
-            .try_clone_reader()
-            .map_err(|e| format!("Failed to clone reader: {}", e))?;
-        let writer = pair
-            .master
-            .take_writer()
-            .map_err(|e| format!("Failed to take writer: {}", e))?;
+        let size = PtySize {
+            rows,
+            cols,
+            pixel_width: 0,
+            pixel_height: 0,
+        };
+        let Spawned {
+            master,
+            writer,
+            reader,
+            child,
+        } = Self::spawn(
+            Self::build_command(project_path, command, detached_name)?,
+            size,
+        )?;
+
+        let reattach = detached_name
+            .filter(|_| remote::is_remote(project_path))
+            .map(|name| Reattach {
+                project_path: project_path.to_string(),
+                name: name.to_string(),
+            });
 
         let session_id = uuid::Uuid::new_v4().to_string();
         let session = Arc::new(PtySession {
-            master: Mutex::new(pair.master),
+            master: Mutex::new(master),
             writer: Mutex::new(writer),
             child: Mutex::new(child),
             meta: Mutex::new(PtySessionMeta {
@@ -135,6 +154,7 @@ impl PtyManager {
                 started_at_ms: now_ms(),
                 last_exit_code: None,
             }),
+            reattach,
         });
 
         self.sessions
@@ -262,6 +282,10 @@ impl PtyManager {
             let reason = reason.to_string();
             std::thread::spawn(move || {
                 Self::close_session_handle(&session, &reason);
+                // closing the tab ends the work; only quitting the app leaves it running
+                if reason == "closed_by_client" {
+                    Self::end_detached(&session);
+                }
             });
         }
         Ok(())
@@ -309,7 +333,39 @@ impl PtyManager {
             .ok_or_else(|| format!("Session not found: {}", session_id))
     }
 
-    fn build_command(project_path: &str, command: &str) -> Result<CommandBuilder, String> {
+    fn spawn(cmd: CommandBuilder, size: PtySize) -> Result<Spawned, String> {
+        let pty_system = native_pty_system();
+        let pair = pty_system
+            .openpty(size)
+            .map_err(|e| format!("Failed to open PTY: {}", e))?;
+
+        let child = pair
+            .slave
+            .spawn_command(cmd)
+            .map_err(|e| format!("Failed to spawn process: {}", e))?;
+        drop(pair.slave);
+
+        let reader = pair
+            .master
+            .try_clone_reader()
+            .map_err(|e| format!("Failed to clone reader: {}", e))?;
+        let writer = pair
+            .master
+            .take_writer()
+            .map_err(|e| format!("Failed to take writer: {}", e))?;
+        Ok(Spawned {
+            master: pair.master,
+            writer,
+            reader,
+            child,
+        })
+    }
+
+    fn build_command(
+        project_path: &str,
+        command: &str,
+        detached_name: Option<&str>,
+    ) -> Result<CommandBuilder, String> {
         match remote::locate(project_path) {
             remote::Location::Local(path) => {
                 let mut cmd = CommandBuilder::new("cmd.exe");
@@ -318,53 +374,61 @@ impl PtyManager {
                 Ok(cmd)
             }
             remote::Location::Remote(target) => {
-                let mut cmd = CommandBuilder::new(remote::find_ssh_exe()?);
-                for arg in remote::ssh_args(&target, true) {
-                    cmd.arg(arg);
-                }
-                cmd.arg(remote::login_script(&target, command));
-                for (name, value) in remote::ssh_env(&target)? {
-                    cmd.env(name, value);
-                }
-                // ssh forwards TERM to the remote pty, and the app process has none
-                cmd.env("TERM", "xterm-256color");
-                Ok(cmd)
+                remote::prepare_session(&target);
+                let script = match detached_name {
+                    Some(name) => remote::detached_login_script(&target, command, name),
+                    None => remote::login_script(&target, command),
+                };
+                Self::ssh_command(&target, &script)
             }
         }
     }
 
+    fn ssh_command(target: &remote::SshTarget, script: &str) -> Result<CommandBuilder, String> {
+        let (program, args) = remote::shell_argv(target, true, script)?;
+        let mut cmd = CommandBuilder::new(program);
+        for arg in args {
+            cmd.arg(arg);
+        }
+        for (name, value) in remote::ssh_env(target)? {
+            cmd.env(name, value);
+        }
+        // ssh forwards TERM to the remote pty, and the app process has none
+        cmd.env("TERM", "xterm-256color");
+        Ok(cmd)
+    }
+
     fn reader_loop(
         session_id: SessionId,
         mut reader: Box<dyn Read + Send>,
         session: Arc<PtySession>,
     ) {
         let mut buf = [0u8; 4096];
-        loop {
-            match reader.read(&mut buf) {
-                Ok(0) => break,
-                Ok(n) => Self::record_data(&session, buf[..n].to_vec()),
-                Err(err) => {
-                    Self::broadcast(
-                        &session,
-                        PtyOutputEvent::Error {
-                            message: format!("PTY read failed for {}: {}", session_id, err),
-                        },
-                    );
-                    break;
+        let exit_code = loop {
+            loop {
+                match reader.read(&mut buf) {
+                    Ok(0) => break,
+                    Ok(n) => Self::record_data(&session, buf[..n].to_vec()),
+                    Err(err) => {
+                        Self::broadcast(
+                            &session,
+                            PtyOutputEvent::Error {
+                                message: format!("PTY read failed for {}: {}", session_id, err),
+                            },
+                        );
+                        break;
+                    }
                 }
             }
-        }
 
-        let exit_code = {
-            let mut child = match session.child.lock() {
-                Ok(child) => child,
-                Err(poisoned) => poisoned.into_inner(),
-            };
-            child
-                .try_wait()
-                .ok()
-                .flatten()
-                .map(|status| status.exit_code())
+            let exit_code = Self::wait_child(&session);
+            if exit_code != Some(SSH_CONNECTION_LOST) {
+                break exit_code;
+            }
+            match Self::reattach(&session) {
+                Some(next) => reader = next,
+                None => break exit_code,
+            }
         };
 
         let should_broadcast = {
@@ -386,6 +450,109 @@ impl PtyManager {
         }
     }
 
+    fn end_detached(session: &Arc<PtySession>) {
+        let Some(reattach) = &session.reattach else {
+            return;
+        };
+        if let remote::Location::Remote(target) = remote::locate(&reattach.project_path) {
+            let _ = remote::kill_detached_session(&target, &reattach.name);
+        }
+    }
+
+    fn wait_child(session: &Arc<PtySession>) -> Option<u32> {
+        let mut child = match session.child.lock() {
+            Ok(child) => child,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        // the pty hits EOF a moment before the process is reapable
+        let deadline = Instant::now() + Duration::from_secs(2);
+        loop {
+            match child.try_wait() {
+                Ok(Some(status)) => return Some(status.exit_code()),
+                Ok(None) if Instant::now() < deadline => {
+                    std::thread::sleep(Duration::from_millis(50))
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    fn is_running(session: &Arc<PtySession>) -> bool {
+        match session.meta.lock() {
+            Ok(meta) => meta.state == SessionState::Running,
+            Err(poisoned) => poisoned.into_inner().state == SessionState::Running,
+        }
+    }
+
+    /// After a dropped connection, waits for the host to answer again and attaches to the
+    /// still-running remote session in a fresh pty, keeping the session id and output
+    /// stream. Returns the new reader, or `None` if the session can't or shouldn't resume.
+    fn reattach(session: &Arc<PtySession>) -> Option<Box<dyn Read + Send>> {
+        let reattach = session.reattach.as_ref()?;
+        let remote::Location::Remote(target) = remote::locate(&reattach.project_path) else {
+            return None;
+        };
+        if !Self::is_running(session) {
+            return None;
+        }
+        Self::record_data(
+            session,
+            b"\r\n\x1b[2m[connection lost, reconnecting...]\x1b[0m\r\n".to_vec(),
+        );
+
+        let size = match session.master.lock() {
+            Ok(master) => master.get_size().ok(),
+            Err(_) => None,
+        }
+        .unwrap_or_default();
+        let started = Instant::now();
+        let mut backoff = Duration::from_secs(1);
+        while started.elapsed() < REATTACH_WINDOW {
+            std::thread::sleep(backoff);
+            backoff = (backoff * 2).min(REATTACH_MAX_BACKOFF);
+            if !Self::is_running(session) {
+                return None;
+            }
+            // probe over the command channel first; a pty ssh that can't connect just
+            // prints its error into the terminal
+            if remote::ping(&target, 5).is_err() {
+                continue;
+            }
+            let script = remote::attach_script(&reattach.name);
+            let Ok(spawned) =
+                Self::ssh_command(&target, &script).and_then(|cmd| Self::spawn(cmd, size))
+            else {
+                continue;
+            };
+
+            // swap under the meta lock so a concurrent close either sees the old child
+            // or the new one, never neither
+            let meta = match session.meta.lock() {
+                Ok(meta) => meta,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+            let mut child = spawned.child;
+            if meta.state != SessionState::Running {
+                let _ = child.kill();
+                return None;
+            }
+            if let Ok(mut slot) = session.child.lock() {
+                *slot = child;
+            }
+            if let Ok(mut slot) = session.master.lock() {
+                *slot = spawned.master;
+            }
+            if let Ok(mut slot) = session.writer.lock() {
+                *slot = spawned.writer;
+            }
+            drop(meta);
+            Self::record_data(session, b"\x1b[2m[reconnected]\x1b[0m\r\n".to_vec());
+            return Some(spawned.reader);
+        }
+        Self::record_data(session, b"\x1b[2m[could not reconnect]\x1b[0m\r\n".to_vec());
+        None
+    }
+
     fn record_data(session: &Arc<PtySession>, bytes: Vec<u8>) {
         let event = {
             let mut meta = match session.meta.lock() {
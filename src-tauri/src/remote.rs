@@ -1,9 +1,14 @@
-// remote projects: paths shaped "ssh://user@host:port/abs/path" run over the OpenSSH client
+// remote projects: paths shaped "ssh://user@host:port/abs/path" run over the OpenSSH client,
+// "docker://user@container/abs/path" ones through `docker exec`, and
+// "wsl://distro/abs/path" ones through `wsl.exe`
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
 use std::collections::HashMap;
 use std::io::{Read, Write};
 use std::process::{Child, ChildStdin, Command, Stdio};
-use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
-use std::sync::{Arc, Mutex, OnceLock};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, OnceLock};
 use std::time::{Duration, Instant};
 
 #[cfg(windows)]
@@ -12,9 +17,14 @@ use std::os::windows::process::CommandExt;
 const CREATE_NO_WINDOW: u32 = 0x08000000;
 
 pub const SCHEME: &str = "ssh://";
+pub const DOCKER_SCHEME: &str = "docker://";
+pub const WSL_SCHEME: &str = "wsl://";
 const RUN_TIMEOUT_SECS: u64 = 180;
 const CONNECT_TIMEOUT_SECS: u64 = 30;
 
+/// Receives command output as it arrives.
+pub type OutputSink<'a> = &'a mut dyn FnMut(&[u8]);
+
 pub struct CmdOutput {
     pub ok: bool,
     pub stdout: String,
@@ -38,6 +48,12 @@ impl CmdOutput {
 pub struct RemoteInfo {
     pub key_path: Option<String>,
     pub password: Option<String>,
+    /// Unlocks an encrypted `key_path` key.
+    pub passphrase: Option<String>,
+    /// Extra `-o` options, e.g. "ForwardAgent=yes".
+    pub ssh_options: Vec<String>,
+    /// Command channels kept open to the host.
+    pub channels: Option<usize>,
 }
 
 fn registry() -> &'static Mutex<HashMap<String, RemoteInfo>> {
@@ -50,17 +66,22 @@ pub fn set_remotes(entries: Vec<(String, RemoteInfo)>) {
         Ok(reg) => reg,
         Err(poisoned) => poisoned.into_inner(),
     };
-    let passwords: HashMap<String, String> = reg
+    // secrets never come from the config file, so carry over whatever was entered
+    let secrets: HashMap<String, (Option<String>, Option<String>)> = reg
         .iter()
-        .filter_map(|(authority, info)| {
-            info.password
-                .clone()
-                .map(|password| (authority.clone(), password))
+        .map(|(authority, info)| {
+            (
+                authority.clone(),
+                (info.password.clone(), info.passphrase.clone()),
+            )
         })
         .collect();
     reg.clear();
     for (authority, mut info) in entries {
-        info.password = passwords.get(&authority).cloned();
+        if let Some((password, passphrase)) = secrets.get(&authority) {
+            info.password = password.clone();
+            info.passphrase = passphrase.clone();
+        }
         reg.insert(authority, info);
     }
 }
@@ -74,6 +95,27 @@ pub fn remember_password(authority: &str, password: Option<String>) {
     reg.entry(authority.to_string()).or_default().password = Some(password);
 }
 
+pub fn remember_passphrase(authority: &str, passphrase: Option<String>) {
+    let Some(passphrase) = passphrase else { return };
+    let mut reg = match registry().lock() {
+        Ok(reg) => reg,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    reg.entry(authority.to_string()).or_default().passphrase = Some(passphrase);
+}
+
+/// Drops the in-memory password and passphrase for an authority.
+pub fn forget_secrets(authority: &str) {
+    let mut reg = match registry().lock() {
+        Ok(reg) => reg,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    if let Some(info) = reg.get_mut(authority) {
+        info.password = None;
+        info.passphrase = None;
+    }
+}
+
 fn lookup(authority: &str) -> RemoteInfo {
     match registry().lock() {
         Ok(reg) => reg.get(authority).cloned().unwrap_or_default(),
@@ -96,8 +138,47 @@ pub struct SshTarget {
     pub path: String,
     pub key_path: Option<String>,
     pub password: Option<String>,
+    pub passphrase: Option<String>,
+    pub ssh_options: Vec<String>,
     /// The path lives on a windows filesystem, so sessions run under cmd.exe.
     pub windows: bool,
+    pub transport: Transport,
+}
+
+/// How a target's shell is reached. For docker `host` names the container, for wsl the
+/// distribution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transport {
+    Ssh,
+    Docker,
+    Wsl,
+}
+
+impl Transport {
+    fn scheme(self) -> &'static str {
+        match self {
+            Transport::Ssh => SCHEME,
+            Transport::Docker => DOCKER_SCHEME,
+            Transport::Wsl => WSL_SCHEME,
+        }
+    }
+
+    /// Carried on authorities, so a container or distro never shares a channel pool or a
+    /// health monitor with an ssh host of the same name.
+    fn authority_prefix(self) -> &'static str {
+        match self {
+            Transport::Ssh => "",
+            Transport::Docker => "docker:",
+            Transport::Wsl => "wsl:",
+        }
+    }
+
+    fn of_authority(authority: &str) -> Transport {
+        [Transport::Docker, Transport::Wsl]
+            .into_iter()
+            .find(|t| authority.starts_with(t.authority_prefix()))
+            .unwrap_or(Transport::Ssh)
+    }
 }
 
 impl SshTarget {
@@ -111,17 +192,24 @@ impl SshTarget {
     /// Rebuilds a url for another absolute path on the same host.
     pub fn url_for(&self, path: &str) -> String {
         let separator = if path.starts_with('/') { "" } else { "/" };
-        format!("{}{}{}{}", SCHEME, self.authority, separator, path)
+        let authority = &self.authority[self.transport.authority_prefix().len()..];
+        let scheme = self.transport.scheme();
+        format!("{}{}{}{}", scheme, authority, separator, path)
     }
 }
 
 pub enum Location {
     Local(String),
+    /// A shell reached over ssh, inside a container or in a wsl distro, per its
+    /// `transport`. All speak through the same command channel, so callers needn't tell
+    /// them apart.
     Remote(SshTarget),
 }
 
 pub fn is_remote(path: &str) -> bool {
-    path.starts_with(SCHEME)
+    [SCHEME, DOCKER_SCHEME, WSL_SCHEME]
+        .iter()
+        .any(|scheme| path.starts_with(scheme))
 }
 
 /// True for a windows-style path like "C:/Projects/app".
@@ -130,14 +218,18 @@ pub fn is_windows_path(path: &str) -> bool {
     bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
 }
 
-/// Splits "ssh://user@host:22/abs/path" into ("user@host:22", "/abs/path").
+/// Splits "ssh://user@host:22/abs/path" into ("user@host:22", "/abs/path"), and
+/// "docker://app/work" into ("docker:app", "/work").
 /// Windows paths keep their drive letter: "ssh://host/C:/app" yields "C:/app".
 pub fn split_url(url: &str) -> Option<(String, String)> {
-    let rest = url.strip_prefix(SCHEME)?;
+    let (prefix, rest) = [Transport::Ssh, Transport::Docker, Transport::Wsl]
+        .into_iter()
+        .find_map(|t| Some((t.authority_prefix(), url.strip_prefix(t.scheme())?)))?;
     let (authority, path) = match rest.find('/') {
         Some(slash) => (rest[..slash].to_string(), rest[slash..].to_string()),
         None => (rest.to_string(), "/".to_string()),
     };
+    let authority = format!("{}{}", prefix, authority);
     let path = match path.strip_prefix('/') {
         Some(stripped) if is_windows_path(stripped) => stripped.to_string(),
         _ => path,
@@ -148,14 +240,18 @@ pub fn split_url(url: &str) -> Option<(String, String)> {
 pub fn target_from_url(url: &str) -> Result<SshTarget, String> {
     let (authority, path) = split_url(url).ok_or_else(|| format!("Not a remote path: {}", url))?;
     let info = lookup(&authority);
+    let transport = Transport::of_authority(&authority);
 
-    let (user, host_port) = match authority.split_once('@') {
+    let user_host = &authority[transport.authority_prefix().len()..];
+    let (user, host_port) = match user_host.split_once('@') {
         Some((user, rest)) => (Some(user.to_string()), rest.to_string()),
-        None => (None, authority.clone()),
+        None => (None, user_host.to_string()),
     };
     let (host, port) = match host_port.split_once(':') {
-        Some((host, port)) => (host.to_string(), port.parse::<u16>().ok()),
-        None => (host_port, None),
+        Some((host, port)) if transport == Transport::Ssh => {
+            (host.to_string(), port.parse::<u16>().ok())
+        }
+        _ => (host_port, None),
     };
     if host.is_empty() {
         return Err(format!("Remote path has no host: {}", url));
@@ -163,6 +259,7 @@ pub fn target_from_url(url: &str) -> Result<SshTarget, String> {
 
     Ok(SshTarget {
         windows: is_windows_path(&path),
+        transport,
         authority,
         user,
         host,
@@ -170,6 +267,8 @@ pub fn target_from_url(url: &str) -> Result<SshTarget, String> {
         path,
         key_path: info.key_path,
         password: info.password,
+        passphrase: info.passphrase,
+        ssh_options: info.ssh_options,
     })
 }
 
@@ -191,16 +290,19 @@ pub fn make_target(
         authority.push(':');
         authority.push_str(&port.to_string());
     }
-    let password = lookup(&authority).password;
+    let info = lookup(&authority);
     SshTarget {
         windows: is_windows_path(&path),
+        transport: Transport::Ssh,
         authority,
         user,
         host,
         port,
         path,
         key_path,
-        password,
+        password: info.password,
+        passphrase: info.passphrase,
+        ssh_options: info.ssh_options,
     }
 }
 
@@ -259,6 +361,10 @@ pub fn find_ssh_exe() -> Result<String, String> {
     static CACHED: OnceLock<Option<String>> = OnceLock::new();
     CACHED
         .get_or_init(|| {
+            if cfg!(not(windows)) {
+                // every unix ships OpenSSH on PATH
+                return Some("ssh".to_string());
+            }
             // prefer the Win32 client — it maps console resizes onto the remote pty,
             // which the msys build shipped with Git does not
             let win32 = r"C:\Windows\System32\OpenSSH\ssh.exe";
@@ -288,8 +394,16 @@ pub fn find_ssh_exe() -> Result<String, String> {
         .ok_or_else(|| "Could not find ssh.exe. Install the Windows OpenSSH client.".to_string())
 }
 
+/// Host aliases from `~/.ssh/config` work as the host: ssh applies their HostName, User,
+/// IdentityFile and ProxyJump itself, so nothing here overrides those unless asked to.
 pub fn ssh_args(target: &SshTarget, tty: bool) -> Vec<String> {
-    let mut args: Vec<String> = vec![
+    let mut args: Vec<String> = Vec::new();
+    // ssh keeps the first value it sees, so per-remote options beat the defaults below
+    for option in &target.ssh_options {
+        args.push("-o".into());
+        args.push(option.clone());
+    }
+    args.extend([
         "-o".into(),
         "StrictHostKeyChecking=accept-new".into(),
         "-o".into(),
@@ -298,11 +412,12 @@ pub fn ssh_args(target: &SshTarget, tty: bool) -> Vec<String> {
         "ConnectTimeout=10".into(),
         "-o".into(),
         "LogLevel=ERROR".into(),
-    ];
+    ]);
     if tty {
         args.push("-t".into());
     } else {
-        if target.password.is_none() {
+        // batch mode would also stop ssh asking askpass for a known secret
+        if target.password.is_none() && target.passphrase.is_none() {
             args.push("-o".into());
             args.push("BatchMode=yes".into());
         }
@@ -326,24 +441,280 @@ pub fn ssh_args(target: &SshTarget, tty: bool) -> Vec<String> {
         args.push("-o".into());
         args.push("IdentitiesOnly=yes".into());
     }
+    args.extend(control_args(target));
     args.push(target.user_host());
     args
 }
 
+/// Program and arguments that hand `script` to a shell on the target: ssh passes it to
+/// the remote login shell, `docker exec` and `wsl.exe` to `sh -c`.
+pub fn shell_argv(
+    target: &SshTarget,
+    tty: bool,
+    script: &str,
+) -> Result<(String, Vec<String>), String> {
+    let (program, mut args) = match target.transport {
+        Transport::Ssh => {
+            let mut args = ssh_args(target, tty);
+            args.push(script.to_string());
+            return Ok((find_ssh_exe()?, args));
+        }
+        Transport::Docker => {
+            let mut args: Vec<String> = vec!["exec".into(), "-i".into()];
+            if tty {
+                // docker exec sets a bare TERM=xterm otherwise
+                args.extend(["-t".into(), "-e".into(), "TERM=xterm-256color".into()]);
+            }
+            if let Some(user) = &target.user {
+                args.push("-u".into());
+                args.push(user.clone());
+            }
+            args.push(target.host.clone());
+            ("docker".to_string(), args)
+        }
+        Transport::Wsl => wsl_runner(target),
+    };
+    args.extend(["sh".into(), "-c".into(), script.into()]);
+    Ok((program, args))
+}
+
+#[cfg(test)]
+thread_local! {
+    /// Stands in for `wsl.exe` on this thread, so tests exercise wsl targets on the local
+    /// shell without a windows host.
+    static WSL_RUNNER: Cell<Option<&'static str>> = const { Cell::new(None) };
+}
+
+/// The command that runs a program inside the target's distro.
+fn wsl_runner(target: &SshTarget) -> (String, Vec<String>) {
+    #[cfg(test)]
+    if let Some(program) = WSL_RUNNER.with(Cell::get) {
+        return (program.to_string(), Vec::new());
+    }
+    let mut args = vec!["-d".to_string(), target.host.clone()];
+    if let Some(user) = &target.user {
+        args.push("-u".into());
+        args.push(user.clone());
+    }
+    args.push("-e".into());
+    ("wsl.exe".to_string(), args)
+}
+
+/// A windows path as a wsl distro sees it under the default automount root:
+/// "C:\Users\me\shot.png" becomes "/mnt/c/Users/me/shot.png".
+pub fn wsl_mount_path(windows_path: &str) -> Option<String> {
+    if !is_windows_path(windows_path) {
+        return None;
+    }
+    let drive = windows_path[..1].to_lowercase();
+    let rest = windows_path[2..].replace('\\', "/");
+    Some(format!("/mnt/{}/{}", drive, rest.trim_start_matches('/')))
+}
+
+// --- connection multiplexing ---
+//
+// OpenSSH on unix clients can share one authenticated connection between processes over a
+// control socket, so PTY sessions and reconnects skip the handshake the command channel
+// already paid for. Windows OpenSSH can't, which is why the command channel exists at all.
+
+/// How long an idle master outlives its last client.
+const CONTROL_PERSIST_SECS: u64 = 600;
+
+/// How long a host whose master failed connects directly before sharing is tried again.
+const MUX_RETRY_AFTER: Duration = Duration::from_secs(600);
+
+/// Authorities we've handed a control socket to, so `shutdown` can close their masters,
+/// with when multiplexing last failed for the host if it connects directly for now.
+type MuxedHosts = HashMap<String, (SshTarget, Option<Instant>)>;
+
+fn muxed() -> &'static Mutex<MuxedHosts> {
+    static MUXED: OnceLock<Mutex<MuxedHosts>> = OnceLock::new();
+    MUXED.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn mux_allowed(failed_at: &Option<Instant>) -> bool {
+    failed_at.is_none_or(|at| at.elapsed() >= MUX_RETRY_AFTER)
+}
+
+fn control_dir() -> Option<&'static std::path::PathBuf> {
+    static DIR: OnceLock<Option<std::path::PathBuf>> = OnceLock::new();
+    DIR.get_or_init(|| {
+        if cfg!(windows) {
+            return None;
+        }
+        let dir = dirs::runtime_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("circuitclaude-ssh");
+        std::fs::create_dir_all(&dir).ok()?;
+        // ssh refuses sockets other users could reach
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700)).ok()?;
+        }
+        Some(dir)
+    })
+    .as_ref()
+}
+
+/// Socket path for an authority. Hashed because unix socket paths are capped near 100
+/// bytes, and stable across runs so a master left from a previous run is reused.
+fn control_path(target: &SshTarget) -> Option<std::path::PathBuf> {
+    if target.transport != Transport::Ssh {
+        return None;
+    }
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in target.authority.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    Some(control_dir()?.join(format!("{:016x}", hash)))
+}
+
+fn control_args(target: &SshTarget) -> Vec<String> {
+    let Some(path) = control_path(target) else {
+        return Vec::new();
+    };
+    {
+        let mut muxed = match muxed().lock() {
+            Ok(muxed) => muxed,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let entry = muxed
+            .entry(target.authority.clone())
+            .or_insert_with(|| (target.clone(), None));
+        if !mux_allowed(&entry.1) {
+            return Vec::new();
+        }
+    }
+    vec![
+        "-o".into(),
+        "ControlMaster=auto".into(),
+        "-o".into(),
+        format!("ControlPath={}", path.to_string_lossy()),
+        "-o".into(),
+        format!("ControlPersist={}", CONTROL_PERSIST_SECS),
+    ]
+}
+
+/// Runs `ssh -O <op>` against the authority's master.
+fn control_command(target: &SshTarget, op: &str) -> bool {
+    let (Some(path), Ok(ssh)) = (control_path(target), find_ssh_exe()) else {
+        return false;
+    };
+    let mut cmd = Command::new(ssh);
+    cmd.arg("-O")
+        .arg(op)
+        .arg("-o")
+        .arg(format!("ControlPath={}", path.to_string_lossy()));
+    if let Some(port) = target.port {
+        cmd.arg("-p").arg(port.to_string());
+    }
+    cmd.arg(target.user_host())
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    cmd.status().map(|s| s.success()).unwrap_or(false)
+}
+
+/// Clears a control socket whose master has died, so the next ssh starts a fresh master
+/// instead of failing to reach the old one.
+fn clear_dead_master(target: &SshTarget) {
+    let Some(path) = control_path(target) else {
+        return;
+    };
+    if path.exists() && !control_command(target, "check") {
+        let _ = std::fs::remove_file(&path);
+    }
+}
+
+/// Stops sharing a connection for a host whose master failed; its ssh processes connect
+/// directly until `MUX_RETRY_AFTER` has passed.
+fn disable_multiplexing(target: &SshTarget) {
+    let mut muxed = match muxed().lock() {
+        Ok(muxed) => muxed,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    if let Some(entry) = muxed.get_mut(&target.authority) {
+        entry.1 = Some(Instant::now());
+    }
+}
+
+fn is_multiplexed(target: &SshTarget) -> bool {
+    control_path(target).is_some()
+        && match muxed().lock() {
+            Ok(muxed) => muxed
+                .get(&target.authority)
+                .is_none_or(|e| mux_allowed(&e.1)),
+            Err(_) => false,
+        }
+}
+
+/// ssh's complaints about the control socket itself, as opposed to the host.
+const CONTROL_ERRORS: &[&str] = &[
+    "controlpath",
+    "controlsocket",
+    "control socket",
+    "mux_client",
+    "muxclient",
+    "unix_listener",
+];
+
+/// Whether a failed connect is down to the master or its socket rather than the host:
+/// auth and network failures would fail the same way without one.
+fn master_failed(target: &SshTarget, err: &str) -> bool {
+    let err = err.to_lowercase();
+    CONTROL_ERRORS.iter().any(|marker| err.contains(marker))
+        || control_path(target).is_some_and(|p| p.exists() && !control_command(target, "check"))
+}
+
+/// Checks the master before a new PTY session leans on it.
+pub fn prepare_session(target: &SshTarget) {
+    if is_multiplexed(target) {
+        clear_dead_master(target);
+    }
+}
+
+fn close_masters() {
+    let targets: Vec<SshTarget> = match muxed().lock() {
+        Ok(mut muxed) => muxed
+            .drain()
+            .filter(|(_, (_, failed_at))| failed_at.is_none())
+            .map(|(_, (target, _))| target)
+            .collect(),
+        Err(_) => return,
+    };
+    for target in targets {
+        control_command(&target, "exit");
+    }
+}
+
+/// Points ssh's askpass at this executable, which answers password and passphrase
+/// prompts from the env (see main.rs).
 pub fn ssh_env(target: &SshTarget) -> Result<Vec<(String, String)>, String> {
-    let Some(password) = &target.password else {
+    if target.transport == Transport::Wsl {
+        // wsl.exe writes its own errors (no such distro) as utf-16 otherwise
+        return Ok(vec![("WSL_UTF8".into(), "1".into())]);
+    }
+    if target.password.is_none() && target.passphrase.is_none() {
         return Ok(Vec::new());
-    };
+    }
     let askpass = std::env::current_exe()
         .map_err(|e| format!("Failed to locate ssh askpass helper: {}", e))?
         .to_string_lossy()
         .to_string();
-    Ok(vec![
+    let mut env = vec![
         ("SSH_ASKPASS".into(), askpass),
         ("SSH_ASKPASS_REQUIRE".into(), "force".into()),
         ("CIRCUITCLAUDE_ASKPASS".into(), "1".into()),
-        ("CIRCUITCLAUDE_SSH_PASSWORD".into(), password.clone()),
-    ])
+    ];
+    if let Some(password) = &target.password {
+        env.push(("CIRCUITCLAUDE_SSH_PASSWORD".into(), password.clone()));
+    }
+    if let Some(passphrase) = &target.passphrase {
+        env.push(("CIRCUITCLAUDE_SSH_PASSPHRASE".into(), passphrase.clone()));
+    }
+    Ok(env)
 }
 
 fn is_windows_shell(command: &str) -> bool {
@@ -363,17 +734,36 @@ pub fn login_script(target: &SshTarget, command: &str) -> String {
     if target.windows {
         format!("cd /d \"{}\" && {}", target.path.replace('/', "\\"), command)
     } else if is_windows_shell(command) {
-        format!("{} && exec bash -l", cd_to(&target.path))
+        format!("{} && exec {} -l", cd_to(&target.path), login_shell(target))
     } else {
-        format!("{} && exec bash -lc {}", cd_to(&target.path), q(command))
+        format!(
+            "{} && exec {} -lc {}",
+            cd_to(&target.path),
+            login_shell(target),
+            q(command)
+        )
+    }
+}
+
+/// The shell sessions start in. Containers and wsl distros may lack bash (see
+/// `LINUX_SHELL_LAUNCHERS`), so there it is looked up when the session starts.
+fn login_shell(target: &SshTarget) -> &'static str {
+    match target.transport {
+        Transport::Ssh => "bash",
+        Transport::Docker | Transport::Wsl => "\"$(command -v bash || echo sh)\"",
     }
 }
 
 // --- local execution ---
 
-pub fn run_local(cwd: &str, program: &str, args: &[&str]) -> Result<CmdOutput, String> {
+pub fn run_local(
+    cwd: &str,
+    program: &str,
+    args: &[&str],
+    envs: &[(&str, &str)],
+) -> Result<CmdOutput, String> {
     let mut cmd = Command::new(program);
-    cmd.args(args).current_dir(cwd);
+    cmd.args(args).current_dir(cwd).envs(envs.iter().copied());
     #[cfg(windows)]
     cmd.creation_flags(CREATE_NO_WINDOW);
     let output = cmd
@@ -386,11 +776,77 @@ pub fn run_local(cwd: &str, program: &str, args: &[&str]) -> Result<CmdOutput, S
     })
 }
 
+/// Like `run_local`, but hands stdout and stderr to `on_output` as they arrive.
+pub fn run_local_streaming(
+    cwd: &str,
+    program: &str,
+    args: &[&str],
+    envs: &[(&str, &str)],
+    on_output: OutputSink<'_>,
+) -> Result<CmdOutput, String> {
+    let mut cmd = Command::new(program);
+    cmd.args(args)
+        .current_dir(cwd)
+        .envs(envs.iter().copied())
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(windows)]
+    cmd.creation_flags(CREATE_NO_WINDOW);
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+
+    let (tx, rx) = channel();
+    pipe_tagged(child.stdout.take(), false, tx.clone());
+    pipe_tagged(child.stderr.take(), true, tx);
+
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    for (is_err, chunk) in rx {
+        on_output(&chunk);
+        if is_err {
+            stderr.extend_from_slice(&chunk);
+        } else {
+            stdout.extend_from_slice(&chunk);
+        }
+    }
+    let status = child
+        .wait()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    Ok(CmdOutput {
+        ok: status.success(),
+        stdout: String::from_utf8_lossy(&stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&stderr).into_owned(),
+    })
+}
+
+fn pipe_tagged<R: Read + Send + 'static>(
+    source: Option<R>,
+    is_err: bool,
+    tx: Sender<(bool, Vec<u8>)>,
+) {
+    let Some(mut source) = source else { return };
+    std::thread::spawn(move || {
+        let mut buf = [0u8; 8192];
+        loop {
+            match source.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    if tx.send((is_err, buf[..n].to_vec())).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+}
+
 // --- persistent command channel ---
 //
-// One long-lived `ssh host bash -l` per authority. Windows OpenSSH has no connection
-// multiplexing, so reconnecting per command would cost a full handshake every time.
-// Commands are framed by markers carrying the exit code.
+// Long-lived `ssh host bash -l` processes, pooled per authority. Windows OpenSSH has no
+// connection multiplexing, so reconnecting per command would cost a full handshake every
+// time. Commands are framed by markers carrying the exit code.
 
 /// How the command channel's shell is started. A windows host has no bash on PATH by
 /// default, so the git-for-windows one is tried by full path.
@@ -400,6 +856,10 @@ const SHELL_LAUNCHERS: &[&str] = &[
     r#""C:\Program Files (x86)\Git\bin\bash.exe" -l"#,
 ];
 
+/// Shells for containers and wsl distros, handed over by `sh -c`. Slim images often ship
+/// without bash.
+const LINUX_SHELL_LAUNCHERS: &[&str] = &["exec bash -l", "exec sh -l"];
+
 struct Conn {
     child: Child,
     stdin: ChildStdin,
@@ -408,11 +868,16 @@ struct Conn {
     token: String,
     /// The shell sees a windows filesystem (msys/cygwin), so `C:/...` paths resolve.
     msys: bool,
+    /// The shell is bash, whose job control gives a streamed command a process group.
+    bash: bool,
 }
 
 /// True when the ssh server hands commands to cmd.exe, which only a windows host does.
 /// Cached per host: it decides the session dialect and which shells are usable.
 fn host_is_windows(target: &SshTarget) -> bool {
+    if target.transport != Transport::Ssh {
+        return false;
+    }
     static CACHED: OnceLock<Mutex<HashMap<String, bool>>> = OnceLock::new();
     let cache = CACHED.get_or_init(|| Mutex::new(HashMap::new()));
     if let Ok(cache) = cache.lock() {
@@ -466,14 +931,34 @@ fn is_msys_uname(stdout: &str) -> bool {
 
 impl Conn {
     fn open(target: &SshTarget) -> Result<Conn, String> {
+        if !is_multiplexed(target) {
+            return Conn::open_direct(target);
+        }
+        clear_dead_master(target);
+        match Conn::open_direct(target) {
+            Ok(conn) => Ok(conn),
+            Err(err) if master_failed(target, &err) => {
+                // the master broke, not the host; connect the old way for now
+                disable_multiplexing(target);
+                Conn::open_direct(target)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    fn open_direct(target: &SshTarget) -> Result<Conn, String> {
         // a windows host needs a windows-hosted shell: wsl's bash would connect fine and
         // then resolve every path against the wrong filesystem. the path says which we
         // are on, except while browsing before a path is picked.
         let needs_msys =
             target.windows || (path_is_unset(&target.path) && host_is_windows(target));
 
+        let launchers = match target.transport {
+            Transport::Ssh => SHELL_LAUNCHERS,
+            Transport::Docker | Transport::Wsl => LINUX_SHELL_LAUNCHERS,
+        };
         let mut last_err = "no shell found".to_string();
-        for launcher in SHELL_LAUNCHERS {
+        for launcher in launchers {
             match Conn::open_with(target, launcher) {
                 Ok(conn) if needs_msys && !conn.msys => {
                     last_err = format!("`{}` on {} is not a windows shell", launcher, target.host);
@@ -483,7 +968,12 @@ impl Conn {
             }
         }
         Err(format!(
-            "ssh to {} failed: {}{}",
+            "{} {} failed: {}{}",
+            match target.transport {
+                Transport::Ssh => "ssh to",
+                Transport::Docker => "docker exec into",
+                Transport::Wsl => "wsl.exe into",
+            },
             target.user_host(),
             last_err,
             if needs_msys {
@@ -495,10 +985,9 @@ impl Conn {
     }
 
     fn open_with(target: &SshTarget, launcher: &str) -> Result<Conn, String> {
-        let ssh = find_ssh_exe()?;
-        let mut cmd = Command::new(ssh);
-        cmd.args(ssh_args(target, false));
-        cmd.arg(launcher);
+        let (program, args) = shell_argv(target, false, launcher)?;
+        let mut cmd = Command::new(program);
+        cmd.args(args);
         for (name, value) in ssh_env(target)? {
             cmd.env(name, value);
         }
@@ -512,13 +1001,14 @@ impl Conn {
         #[cfg(windows)]
         cmd.creation_flags(CREATE_NO_WINDOW);
 
+        let program = cmd.get_program().to_string_lossy().to_string();
         let mut child = cmd
             .spawn()
-            .map_err(|e| format!("Failed to start ssh: {}", e))?;
+            .map_err(|e| format!("Failed to start {}: {}", program, e))?;
         let stdin = child
             .stdin
             .take()
-            .ok_or_else(|| "Failed to open ssh stdin".to_string())?;
+            .ok_or_else(|| format!("Failed to open {} stdin", program))?;
         let stdout = pipe_reader(child.stdout.take());
         let stderr = pipe_reader(child.stderr.take());
 
@@ -529,20 +1019,35 @@ impl Conn {
             stderr,
             token: uuid::Uuid::new_v4().simple().to_string(),
             msys: false,
+            bash: false,
         };
 
         // first command doubles as the connect probe and swallows any login banner
         let probe = conn
-            .exec("uname -s", CONNECT_TIMEOUT_SECS)
+            .exec(
+                "printf 'shell=%s\\n' \"${BASH_VERSION:+bash}\"; uname -s",
+                CONNECT_TIMEOUT_SECS,
+            )
             .map_err(|e| e.message().to_string())?;
         if !probe.ok {
             return Err(probe.err_text());
         }
         conn.msys = is_msys_uname(&probe.stdout);
+        conn.bash = probe.stdout.lines().any(|line| line.trim() == "shell=bash");
         Ok(conn)
     }
 
     fn exec(&mut self, script: &str, timeout_secs: u64) -> Result<CmdOutput, ExecError> {
+        self.exec_observed(script, timeout_secs, None)
+    }
+
+    /// `exec`, handing stdout to `on_stdout` as it arrives rather than only at the end.
+    fn exec_observed(
+        &mut self,
+        script: &str,
+        timeout_secs: u64,
+        on_stdout: Option<OutputSink<'_>>,
+    ) -> Result<CmdOutput, ExecError> {
         let out_mark = format!("__CCO{}", self.token);
         let err_mark = format!("__CCE{}", self.token);
         // subshell so a stray `exit` can't kill the channel, and no command can eat the
@@ -557,9 +1062,9 @@ impl Conn {
             .map_err(|e| ExecError::Dead(format!("connection lost: {}", e)))?;
 
         let deadline = Instant::now() + Duration::from_secs(timeout_secs);
-        let (stdout, code) = read_until(&self.stdout, &out_mark, true, deadline)
+        let (stdout, code) = read_until(&self.stdout, &out_mark, true, deadline, on_stdout)
             .map_err(|e| self.describe_failure(e))?;
-        let (stderr, _) = read_until(&self.stderr, &err_mark, false, deadline)
+        let (stderr, _) = read_until(&self.stderr, &err_mark, false, deadline, None)
             .map_err(|e| self.describe_failure(e))?;
 
         Ok(CmdOutput {
@@ -582,6 +1087,131 @@ impl Conn {
             err.with_detail(detail)
         }
     }
+
+    /// Runs `script` in a process group of its own, handing stdout and stderr to
+    /// `on_chunk` as they arrive. Cancelling, or passing `deadline`, writes the line a
+    /// watcher in the frame waits for: it kills the group and the channel lives on.
+    ///
+    /// Only bash has job control without a tty; under a plain sh the group comes from
+    /// `setsid`, and where that's missing too the command and its children are killed.
+    fn exec_stream(
+        &mut self,
+        script: &str,
+        deadline: Instant,
+        cancel: &CancelToken,
+        on_chunk: &mut dyn FnMut(StreamChunk),
+    ) -> Result<StreamEnd, ExecError> {
+        let out_mark = format!("__CCO{}", self.token);
+        let err_mark = format!("__CCE{}", self.token);
+        let launch = if self.bash {
+            format!(
+                "set -m\n( {} ) < /dev/null &\n__cc_pid=$!\nset +m\n",
+                script
+            )
+        } else {
+            format!(
+                "if command -v setsid >/dev/null 2>&1; then setsid sh -c {quoted} < /dev/null &\n\
+                 else ( {script} ) < /dev/null &\nfi\n__cc_pid=$!\n",
+                quoted = q(script),
+                script = script,
+            )
+        };
+        // `kill -SIG -pid` rather than `-- -pid`, which dash's kill rejects
+        let stop = |signal: &str| {
+            format!(
+                "{{ kill -{sig} -$__cc_pid || {{ pkill -{sig} -P $__cc_pid; kill -{sig} $__cc_pid; }}; }}",
+                sig = signal
+            )
+        };
+        // the braces make the shell read the whole frame before running any of it, so all
+        // the watcher's `read` can ever see is the stop line. it reads a copy of stdin:
+        // without job control a background job's own stdin is /dev/null. dash's `wait`
+        // reports a killed job on stderr, which isn't the command's to stream
+        let framed = format!(
+            "{{\n{}exec 3<&0\n( read -r __cc_line <&3 && {{ {}; sleep {}; {}; }} ) 2>/dev/null &\nexec 3<&-\n\
+             __cc_stop=$!\nwait $__cc_pid 2>/dev/null\n__cc_status=$?\n\
+             kill $__cc_stop 2>/dev/null\nwait $__cc_stop 2>/dev/null\n\
+             printf '\\n{}%d\\n' \"$__cc_status\"\nprintf '\\n{}\\n' 1>&2\n}}\n",
+            launch,
+            stop("TERM"),
+            KILL_AFTER.as_secs(),
+            stop("KILL"),
+            out_mark,
+            err_mark
+        );
+        self.stdin
+            .write_all(framed.as_bytes())
+            .and_then(|_| self.stdin.flush())
+            .map_err(|e| ExecError::Dead(format!("connection lost: {}", e)))?;
+
+        let mut stdout = Frame::new(&out_mark, true);
+        let mut stderr = Frame::new(&err_mark, false);
+        let mut code = None;
+        let mut stderr_done = false;
+        let mut stopping: Option<(StreamEnd, Instant)> = None;
+        while code.is_none() || !stderr_done {
+            match stopping {
+                None => {
+                    let stop = if cancel.is_cancelled() {
+                        Some(StreamEnd::Cancelled)
+                    } else if Instant::now() >= deadline {
+                        Some(StreamEnd::TimedOut)
+                    } else {
+                        None
+                    };
+                    if let Some(end) = stop {
+                        self.stdin
+                            .write_all(b"\n")
+                            .and_then(|_| self.stdin.flush())
+                            .map_err(|e| ExecError::Dead(format!("connection lost: {}", e)))?;
+                        stopping = Some((end, Instant::now() + KILL_AFTER + STOP_GRACE));
+                    }
+                }
+                Some((_, give_up)) if Instant::now() >= give_up => {
+                    return Err(ExecError::Timeout(
+                        "remote command did not stop".to_string(),
+                    ));
+                }
+                Some(_) => {}
+            }
+
+            if code.is_none() {
+                let chunk =
+                    poll_chunk(&self.stdout, STREAM_POLL).map_err(|e| self.describe_failure(e))?;
+                if let Some(chunk) = chunk {
+                    let mut emit = |bytes: &[u8]| {
+                        on_chunk(StreamChunk::Stdout {
+                            bytes: bytes.to_vec(),
+                        })
+                    };
+                    code = stdout.push(&chunk, Some(&mut emit));
+                }
+            }
+            if !stderr_done {
+                // with stdout finished there's nothing else to wait on
+                let wait = if code.is_some() {
+                    STREAM_POLL
+                } else {
+                    Duration::ZERO
+                };
+                if let Some(chunk) = poll_chunk(&self.stderr, wait)? {
+                    let mut emit = |bytes: &[u8]| {
+                        on_chunk(StreamChunk::Stderr {
+                            bytes: bytes.to_vec(),
+                        })
+                    };
+                    stderr_done = stderr.push(&chunk, Some(&mut emit)).is_some();
+                }
+            }
+        }
+
+        Ok(match stopping {
+            Some((end, _)) => end,
+            None => StreamEnd::Exited {
+                code: code.unwrap_or(-1),
+            },
+        })
+    }
 }
 
 #[derive(Debug)]
@@ -640,58 +1270,96 @@ fn read_until(
     marker: &str,
     with_code: bool,
     deadline: Instant,
+    mut emit: Option<OutputSink<'_>>,
 ) -> Result<(Vec<u8>, i32), ExecError> {
-    let needle = marker.as_bytes();
-    let mut buf: Vec<u8> = Vec::new();
-    let mut searched = 0usize;
-
+    let mut frame = Frame::new(marker, with_code);
     loop {
-        if let Some(at) = find(&buf[searched..], needle).map(|i| i + searched) {
-            let after = at + needle.len();
-            let code = if with_code {
-                match buf[after..].iter().position(|b| *b == b'\n') {
-                    Some(end) => String::from_utf8_lossy(&buf[after..after + end])
-                        .trim()
-                        .parse::<i32>()
-                        .unwrap_or(-1),
-                    None => {
-                        // exit code line not fully arrived yet
-                        searched = at;
-                        recv_more(rx, &mut buf, deadline)?;
-                        continue;
-                    }
+        let chunk = recv_chunk(rx, deadline)?;
+        let sink = emit.as_mut().map(|e| &mut **e as OutputSink<'_>);
+        if let Some(code) = frame.push(&chunk, sink) {
+            return Ok((frame.buf, code));
+        }
+    }
+}
+
+/// One pipe of a framed command, split at its end marker as the bytes arrive.
+struct Frame {
+    needle: Vec<u8>,
+    with_code: bool,
+    buf: Vec<u8>,
+    searched: usize,
+    emitted: usize,
+}
+
+impl Frame {
+    fn new(marker: &str, with_code: bool) -> Frame {
+        Frame {
+            needle: marker.as_bytes().to_vec(),
+            with_code,
+            buf: Vec::new(),
+            searched: 0,
+            emitted: 0,
+        }
+    }
+
+    /// Adds a chunk and returns the exit code once the marker is in (0 without
+    /// `with_code`); `buf` then holds the output. `emit` sees the same bytes as they
+    /// arrive, held back only while they could still be the start of the marker.
+    fn push(&mut self, chunk: &[u8], emit: Option<OutputSink<'_>>) -> Option<i32> {
+        self.buf.extend_from_slice(chunk);
+        let needle_len = self.needle.len();
+        let Some(at) = find(&self.buf[self.searched..], &self.needle).map(|i| i + self.searched)
+        else {
+            self.searched = self.buf.len().saturating_sub(needle_len);
+            if let Some(emit) = emit {
+                // the +1 holds back the newline injected ahead of the marker
+                let settled = self.buf.len().saturating_sub(needle_len + 1);
+                if settled > self.emitted {
+                    emit(&self.buf[self.emitted..settled]);
+                    self.emitted = settled;
                 }
-            } else {
-                0
-            };
-            let mut out = buf;
-            out.truncate(at);
-            // drop the newline we injected ahead of the marker
-            if out.last() == Some(&b'\n') {
-                out.pop();
             }
-            return Ok((out, code));
-        }
+            return None;
+        };
 
-        searched = buf.len().saturating_sub(needle.len());
-        recv_more(rx, &mut buf, deadline)?;
+        let after = at + needle_len;
+        let code = if self.with_code {
+            match self.buf[after..].iter().position(|b| *b == b'\n') {
+                Some(end) => String::from_utf8_lossy(&self.buf[after..after + end])
+                    .trim()
+                    .parse::<i32>()
+                    .unwrap_or(-1),
+                None => {
+                    // exit code line not fully arrived yet
+                    self.searched = at;
+                    return None;
+                }
+            }
+        } else {
+            0
+        };
+        self.buf.truncate(at);
+        // drop the newline we injected ahead of the marker
+        if self.buf.last() == Some(&b'\n') {
+            self.buf.pop();
+        }
+        if let Some(emit) = emit {
+            if self.buf.len() > self.emitted {
+                emit(&self.buf[self.emitted..]);
+            }
+        }
+        self.emitted = self.buf.len();
+        Some(code)
     }
 }
 
-fn recv_more(
-    rx: &Receiver<Vec<u8>>,
-    buf: &mut Vec<u8>,
-    deadline: Instant,
-) -> Result<(), ExecError> {
+fn recv_chunk(rx: &Receiver<Vec<u8>>, deadline: Instant) -> Result<Vec<u8>, ExecError> {
     let remaining = deadline.saturating_duration_since(Instant::now());
     if remaining.is_zero() {
         return Err(ExecError::Timeout("remote command timed out".to_string()));
     }
     match rx.recv_timeout(remaining) {
-        Ok(chunk) => {
-            buf.extend_from_slice(&chunk);
-            Ok(())
-        }
+        Ok(chunk) => Ok(chunk),
         Err(RecvTimeoutError::Timeout) => {
             Err(ExecError::Timeout("remote command timed out".to_string()))
         }
@@ -701,6 +1369,17 @@ fn recv_more(
     }
 }
 
+/// The next chunk off a pipe, if one arrives within `wait`.
+fn poll_chunk(rx: &Receiver<Vec<u8>>, wait: Duration) -> Result<Option<Vec<u8>>, ExecError> {
+    match rx.recv_timeout(wait) {
+        Ok(chunk) => Ok(Some(chunk)),
+        Err(RecvTimeoutError::Timeout) => Ok(None),
+        Err(RecvTimeoutError::Disconnected) => {
+            Err(ExecError::Dead("connection closed".to_string()))
+        }
+    }
+}
+
 fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
     if needle.is_empty() || haystack.len() < needle.len() {
         return None;
@@ -708,66 +1387,275 @@ fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
     haystack.windows(needle.len()).position(|w| w == needle)
 }
 
-type Slot = Arc<Mutex<Option<Conn>>>;
+// --- channel pool ---
+//
+// Each authority gets a few channels so one slow command (a big `git status`) doesn't
+// hold up the file tree. Commands queue for a free channel in one of two lanes.
+
+/// Channels per authority unless its remote config says otherwise.
+const DEFAULT_CHANNELS: usize = 2;
+const MAX_CHANNELS: usize = 8;
+
+/// How often queued commands wake to check whether they were cancelled.
+const QUEUE_POLL: Duration = Duration::from_millis(250);
+
+/// Which queue a command waits in when every channel is busy. Interactive commands go
+/// first, and background work never takes a host's last free channel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Lane {
+    Interactive,
+    Background,
+}
+
+thread_local! {
+    static LANE: Cell<Lane> = const { Cell::new(Lane::Interactive) };
+}
+
+/// Runs `f` with every remote command it issues on this thread queued as background
+/// work, for polling that nobody is waiting on.
+pub fn in_background<T>(f: impl FnOnce() -> T) -> T {
+    // put back on drop, so a panic in `f` can't leave a pooled thread in the background
+    struct Restore(Lane);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            LANE.with(|lane| lane.set(self.0));
+        }
+    }
+    let _restore = Restore(LANE.with(|lane| lane.replace(Lane::Background)));
+    f()
+}
+
+struct HostPool {
+    state: Mutex<PoolState>,
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct PoolState {
+    idle: Vec<Conn>,
+    /// Channels leased out, including ones still being opened.
+    busy: usize,
+    busy_background: usize,
+    waiting_interactive: usize,
+    /// Bumped by `cancel_queued`; waiters that joined before it give up.
+    cancel_epoch: u64,
+}
+
+impl HostPool {
+    fn lock(&self) -> MutexGuard<'_, PoolState> {
+        match self.state.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
 
-fn pool() -> &'static Mutex<HashMap<String, Slot>> {
-    static POOL: OnceLock<Mutex<HashMap<String, Slot>>> = OnceLock::new();
+fn pool() -> &'static Mutex<HashMap<String, Arc<HostPool>>> {
+    static POOL: OnceLock<Mutex<HashMap<String, Arc<HostPool>>>> = OnceLock::new();
     POOL.get_or_init(|| Mutex::new(HashMap::new()))
 }
 
-fn slot_for(authority: &str) -> Slot {
+fn host_pool(authority: &str) -> Arc<HostPool> {
     let mut pool = match pool().lock() {
         Ok(pool) => pool,
         Err(poisoned) => poisoned.into_inner(),
     };
     pool.entry(authority.to_string())
-        .or_insert_with(|| Arc::new(Mutex::new(None)))
+        .or_insert_with(|| {
+            Arc::new(HostPool {
+                state: Mutex::new(PoolState::default()),
+                changed: Condvar::new(),
+            })
+        })
         .clone()
 }
 
-pub fn shutdown() {
-    let slots: Vec<Slot> = match pool().lock() {
-        Ok(mut pool) => pool.drain().map(|(_, slot)| slot).collect(),
-        Err(poisoned) => poisoned.into_inner().drain().map(|(_, s)| s).collect(),
-    };
-    // a slot busy with an in-flight command is left alone — its ssh exits on its own
-    // once our end of the pipe closes, and waiting here would stall app exit
-    for slot in slots {
-        if let Ok(mut guard) = slot.try_lock() {
-            *guard = None;
+fn channel_limit(authority: &str) -> usize {
+    lookup(authority)
+        .channels
+        .unwrap_or(DEFAULT_CHANNELS)
+        .clamp(1, MAX_CHANNELS)
+}
+
+/// A channel checked out of its host's pool; dropping it hands the channel back.
+struct Lease {
+    pool: Arc<HostPool>,
+    conn: Option<Conn>,
+    lane: Lane,
+    limit: usize,
+}
+
+impl Lease {
+    /// The leased channel, connecting it first if it was never opened or was discarded.
+    fn conn(&mut self, target: &SshTarget) -> Result<&mut Conn, String> {
+        if self.conn.is_none() {
+            self.conn = Some(Conn::open(target)?);
         }
+        self.conn
+            .as_mut()
+            .ok_or_else(|| "no connection".to_string())
+    }
+
+    /// Drops a channel that can't be trusted anymore instead of returning it.
+    fn discard(&mut self) {
+        self.conn = None;
     }
 }
 
-/// Runs a shell command on the remote host, optionally after cd'ing somewhere.
-pub fn run(target: &SshTarget, cwd: Option<&str>, command: &str) -> Result<CmdOutput, String> {
-    let script = match cwd {
-        Some(dir) => format!("{{ {} && {}; }}", cd_to(dir), command),
-        None => command.to_string(),
-    };
+impl Drop for Lease {
+    fn drop(&mut self) {
+        let mut state = self.pool.lock();
+        state.busy -= 1;
+        if self.lane == Lane::Background {
+            state.busy_background -= 1;
+        }
+        // a lowered limit shrinks the pool as channels come back
+        if let Some(conn) = self.conn.take() {
+            if state.idle.len() + state.busy < self.limit {
+                state.idle.push(conn);
+            }
+        }
+        drop(state);
+        self.pool.changed.notify_all();
+    }
+}
 
-    let slot = slot_for(&target.authority);
-    let mut guard = match slot.lock() {
-        Ok(guard) => guard,
-        Err(poisoned) => poisoned.into_inner(),
-    };
+/// Waits for a free channel in the calling thread's lane.
+fn checkout(authority: &str) -> Result<Lease, String> {
+    checkout_unless(authority, None)
+}
 
-    let mut last_err = "no connection".to_string();
-    for attempt in 0..2 {
-        if guard.is_none() {
-            *guard = Some(Conn::open(target)?);
+/// `checkout`, also giving up once `cancel` fires.
+fn checkout_unless(authority: &str, cancel: Option<&CancelToken>) -> Result<Lease, String> {
+    let lane = LANE.with(Cell::get);
+    let pool = host_pool(authority);
+    let limit = channel_limit(authority);
+    let mut state = pool.lock();
+    let epoch = state.cancel_epoch;
+    if lane == Lane::Interactive {
+        state.waiting_interactive += 1;
+    }
+    let conn = loop {
+        if state.cancel_epoch != epoch || cancel.is_some_and(CancelToken::is_cancelled) {
+            if lane == Lane::Interactive {
+                state.waiting_interactive -= 1;
+            }
+            return Err("cancelled".to_string());
         }
-        let Some(conn) = guard.as_mut() else {
-            break;
+        let lane_open = match lane {
+            Lane::Interactive => true,
+            Lane::Background => {
+                state.waiting_interactive == 0 && (limit == 1 || state.busy_background < limit - 1)
+            }
+        };
+        if lane_open {
+            if let Some(conn) = state.idle.pop() {
+                break Some(conn);
+            }
+            if state.busy < limit {
+                break None;
+            }
+        }
+        state = match pool.changed.wait_timeout(state, QUEUE_POLL) {
+            Ok((state, _)) => state,
+            Err(poisoned) => poisoned.into_inner().0,
+        };
+    };
+    if lane == Lane::Interactive {
+        state.waiting_interactive -= 1;
+    }
+    state.busy += 1;
+    if lane == Lane::Background {
+        state.busy_background += 1;
+    }
+    drop(state);
+    Ok(Lease {
+        pool,
+        conn,
+        lane,
+        limit,
+    })
+}
+
+/// Fails every command still queued for a channel on `authority`; ones already running
+/// are left to finish.
+pub fn cancel_queued(authority: &str) {
+    let pool = host_pool(authority);
+    pool.lock().cancel_epoch += 1;
+    pool.changed.notify_all();
+}
+
+pub fn shutdown() {
+    let hosts: Vec<Arc<HostPool>> = match pool().lock() {
+        Ok(mut pool) => pool.drain().map(|(_, host)| host).collect(),
+        Err(poisoned) => poisoned.into_inner().drain().map(|(_, h)| h).collect(),
+    };
+    // channels busy with an in-flight command are left alone — their ssh exits on its
+    // own once our end of the pipe closes, and waiting here would stall app exit
+    for host in hosts {
+        let idle = std::mem::take(&mut host.lock().idle);
+        drop(idle);
+    }
+    if let Ok(mut channels) = health_channels().lock() {
+        channels.clear();
+    }
+    close_all_forwards();
+    close_masters();
+}
+
+/// Runs a shell command on the remote host, optionally after cd'ing somewhere.
+pub fn run(target: &SshTarget, cwd: Option<&str>, command: &str) -> Result<CmdOutput, String> {
+    run_observed(target, cwd, command, None)
+}
+
+/// Like `run`, but hands output to `on_output` as it arrives. stderr is folded into
+/// stdout so the two interleave the way they would in a terminal.
+pub fn run_streaming(
+    target: &SshTarget,
+    cwd: Option<&str>,
+    command: &str,
+    on_output: OutputSink<'_>,
+) -> Result<CmdOutput, String> {
+    let merged = format!("{{ {}; }} 2>&1", command);
+    run_observed(target, cwd, &merged, Some(on_output))
+}
+
+fn run_observed(
+    target: &SshTarget,
+    cwd: Option<&str>,
+    command: &str,
+    mut on_output: Option<OutputSink<'_>>,
+) -> Result<CmdOutput, String> {
+    let script = match cwd {
+        Some(dir) => format!("{{ {} && {}; }}", cd_to(dir), command),
+        None => command.to_string(),
+    };
+
+    let mut lease = checkout(&target.authority)?;
+
+    let mut last_err = "no connection".to_string();
+    for attempt in 0..2 {
+        let conn = lease.conn(target)?;
+        let mut streamed = false;
+        let result = match on_output.as_deref_mut() {
+            Some(forward) => {
+                let mut relay = |chunk: &[u8]| {
+                    streamed = true;
+                    forward(chunk);
+                };
+                conn.exec_observed(&script, RUN_TIMEOUT_SECS, Some(&mut relay))
+            }
+            None => conn.exec(&script, RUN_TIMEOUT_SECS),
         };
-        match conn.exec(&script, RUN_TIMEOUT_SECS) {
+        match result {
             Ok(output) => return Ok(output),
             Err(err) => {
                 // either way the channel is unusable: a timed-out command leaves its
                 // output stranded in the pipe, which would desync the next one
                 last_err = err.message().to_string();
-                let retryable = matches!(err, ExecError::Dead(_));
-                *guard = None;
+                // output already handed out would be repeated by a retry
+                let retryable = matches!(err, ExecError::Dead(_)) && !streamed;
+                lease.discard();
                 if !retryable || attempt == 1 {
                     break;
                 }
@@ -777,6 +1665,45 @@ pub fn run(target: &SshTarget, cwd: Option<&str>, command: &str) -> Result<CmdOu
     Err(last_err)
 }
 
+type HealthChannel = Arc<Mutex<Option<Conn>>>;
+
+/// One channel per authority set aside for pings, outside the pool, so a host whose
+/// pooled channels are all busy (or stuck) is still measured.
+fn health_channels() -> &'static Mutex<HashMap<String, HealthChannel>> {
+    static CHANNELS: OnceLock<Mutex<HashMap<String, HealthChannel>>> = OnceLock::new();
+    CHANNELS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Round-trips a no-op over the authority's health channel, connecting it first if
+/// needed.
+pub fn ping(target: &SshTarget, timeout_secs: u64) -> Result<Duration, String> {
+    let mut channels = match health_channels().lock() {
+        Ok(channels) => channels,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    let channel = channels
+        .entry(target.authority.clone())
+        .or_default()
+        .clone();
+    drop(channels);
+    let mut slot = match channel.lock() {
+        Ok(slot) => slot,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    let conn = match slot.as_mut() {
+        Some(conn) => conn,
+        None => slot.insert(Conn::open(target)?),
+    };
+    let started = Instant::now();
+    match conn.exec(":", timeout_secs) {
+        Ok(_) => Ok(started.elapsed()),
+        Err(err) => {
+            *slot = None;
+            Err(err.message().to_string())
+        }
+    }
+}
+
 /// Runs and fails if the command did.
 pub fn run_checked(target: &SshTarget, cwd: Option<&str>, command: &str) -> Result<String, String> {
     let output = run(target, cwd, command)?;
@@ -787,6 +1714,156 @@ pub fn run_checked(target: &SshTarget, cwd: Option<&str>, command: &str) -> Resu
     }
 }
 
+// --- streaming execution ---
+//
+// For commands that can run for minutes (`git push`, test suites, searches): output is
+// forwarded as it arrives, and the caller picks the timeout and can stop the command.
+
+/// How long a stopped command gets to exit on SIGTERM before its group is killed.
+const KILL_AFTER: Duration = Duration::from_secs(3);
+
+/// How much longer a killed command may take to report back before its channel is
+/// dropped instead.
+const STOP_GRACE: Duration = Duration::from_secs(5);
+
+/// How often a streamed command checks for cancellation while its output is quiet.
+const STREAM_POLL: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", content = "data")]
+pub enum StreamChunk {
+    Stdout { bytes: Vec<u8> },
+    Stderr { bytes: Vec<u8> },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(tag = "type", content = "data")]
+pub enum StreamEnd {
+    Exited { code: i32 },
+    Cancelled,
+    TimedOut,
+}
+
+/// Stops a streamed command from another thread; clones share the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Runs a command, sending its stdout and stderr to `output` as they arrive. A stopped
+/// command (cancelled or past `timeout`) has its whole process group killed on the host.
+pub fn run_stream(
+    target: &SshTarget,
+    cwd: Option<&str>,
+    command: &str,
+    timeout: Duration,
+    output: &Sender<StreamChunk>,
+    cancel: &CancelToken,
+) -> Result<StreamEnd, String> {
+    let script = match cwd {
+        Some(dir) => format!("{{ {} && {}; }}", cd_to(dir), command),
+        None => command.to_string(),
+    };
+    let deadline = Instant::now() + timeout;
+
+    let mut lease = match checkout_unless(&target.authority, Some(cancel)) {
+        Err(_) if cancel.is_cancelled() => return Ok(StreamEnd::Cancelled),
+        lease => lease?,
+    };
+
+    let mut last_err = "no connection".to_string();
+    for attempt in 0..2 {
+        let conn = lease.conn(target)?;
+        let mut streamed = false;
+        let mut forward = |chunk: StreamChunk| {
+            streamed = true;
+            let _ = output.send(chunk);
+        };
+        match conn.exec_stream(&script, deadline, cancel, &mut forward) {
+            Ok(end) => return Ok(end),
+            Err(err) => {
+                last_err = err.message().to_string();
+                let retryable = matches!(err, ExecError::Dead(_)) && !streamed;
+                lease.discard();
+                if !retryable || attempt == 1 {
+                    break;
+                }
+            }
+        }
+    }
+    Err(last_err)
+}
+
+/// `run` with the caller's timeout in place of the usual one, for commands that can
+/// legitimately take minutes.
+pub fn run_with_timeout(
+    target: &SshTarget,
+    cwd: Option<&str>,
+    command: &str,
+    timeout: Duration,
+) -> Result<CmdOutput, String> {
+    let (tx, rx) = channel();
+    let end = run_stream(target, cwd, command, timeout, &tx, &CancelToken::default())?;
+    drop(tx);
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    for chunk in rx {
+        match chunk {
+            StreamChunk::Stdout { bytes } => stdout.extend_from_slice(&bytes),
+            StreamChunk::Stderr { bytes } => stderr.extend_from_slice(&bytes),
+        }
+    }
+    match end {
+        StreamEnd::Exited { code } => Ok(CmdOutput {
+            ok: code == 0,
+            stdout: String::from_utf8_lossy(&stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&stderr).into_owned(),
+        }),
+        StreamEnd::TimedOut => Err(format!(
+            "remote command timed out after {}s",
+            timeout.as_secs()
+        )),
+        StreamEnd::Cancelled => Err("cancelled".to_string()),
+    }
+}
+
+/// Cancel handles for streamed runs started from the frontend, by run id.
+fn streamed_runs() -> &'static Mutex<HashMap<String, CancelToken>> {
+    static RUNS: OnceLock<Mutex<HashMap<String, CancelToken>>> = OnceLock::new();
+    RUNS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Registers a run under `run_id` so `cancel_run` can reach it until `end_run`.
+pub fn begin_run(run_id: &str) -> CancelToken {
+    let cancel = CancelToken::default();
+    if let Ok(mut runs) = streamed_runs().lock() {
+        runs.insert(run_id.to_string(), cancel.clone());
+    }
+    cancel
+}
+
+pub fn end_run(run_id: &str) {
+    if let Ok(mut runs) = streamed_runs().lock() {
+        runs.remove(run_id);
+    }
+}
+
+/// Stops a run started with `begin_run`; false if it already finished.
+pub fn cancel_run(run_id: &str) -> bool {
+    match streamed_runs().lock() {
+        Ok(runs) => runs.get(run_id).map(CancelToken::cancel).is_some(),
+        Err(_) => false,
+    }
+}
+
 // --- remote file access ---
 
 pub fn read_text(target: &SshTarget, path: &str) -> Result<String, String> {
@@ -814,6 +1891,630 @@ pub fn write_text(target: &SshTarget, path: &str, content: &str) -> Result<(), S
     }
 }
 
+// --- port forwarding ---
+//
+// Each forward is its own `ssh -N` process rather than a request on the shared master, so
+// closing one can't disturb the sessions riding that master, and windows clients (which
+// have no master) work the same way.
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ForwardDirection {
+    /// `-L`: a local port reaches a port on the remote side.
+    Local,
+    /// `-R`: a remote port reaches a port on this machine.
+    Remote,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortForward {
+    pub direction: ForwardDirection,
+    pub local_port: u16,
+    pub remote_port: u16,
+    /// Where the far end of a `-L` forward connects, as seen from the remote host.
+    #[serde(default)]
+    pub remote_host: Option<String>,
+}
+
+impl PortForward {
+    fn ssh_spec(&self) -> [String; 2] {
+        let host = self.remote_host.as_deref().unwrap_or("localhost");
+        match self.direction {
+            ForwardDirection::Local => [
+                "-L".into(),
+                format!(
+                    "127.0.0.1:{}:{}:{}",
+                    self.local_port, host, self.remote_port
+                ),
+            ],
+            ForwardDirection::Remote => [
+                "-R".into(),
+                format!("{}:localhost:{}", self.remote_port, self.local_port),
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ForwardState {
+    Opening,
+    Open,
+    Closed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForwardStatus {
+    pub project_path: String,
+    pub forward: PortForward,
+    pub state: ForwardState,
+    pub error: Option<String>,
+}
+
+/// How long a `-R` forward must stay up before it counts as open; there is no local port
+/// to probe, and ExitOnForwardFailure makes a refused bind exit well within this.
+const REMOTE_FORWARD_SETTLE: Duration = Duration::from_secs(3);
+
+struct ActiveForward {
+    child: Arc<Mutex<Child>>,
+    state: ForwardState,
+}
+
+type ForwardKey = (String, PortForward);
+
+fn forwards() -> &'static Mutex<HashMap<ForwardKey, ActiveForward>> {
+    static FORWARDS: OnceLock<Mutex<HashMap<ForwardKey, ActiveForward>>> = OnceLock::new();
+    FORWARDS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn lock_forwards() -> std::sync::MutexGuard<'static, HashMap<ForwardKey, ActiveForward>> {
+    match forwards().lock() {
+        Ok(forwards) => forwards,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+/// Starts forwarding for a project. `on_status` sees every state change until the forward
+/// closes or fails; opening one that's already up is a no-op.
+pub fn open_forward(
+    project_path: &str,
+    target: &SshTarget,
+    forward: PortForward,
+    on_status: impl Fn(ForwardStatus) + Send + 'static,
+) -> Result<(), String> {
+    match target.transport {
+        Transport::Ssh => {}
+        Transport::Docker => {
+            return Err("Port forwarding isn't available for container projects".to_string())
+        }
+        // wsl2 already reaches the distro's listeners through localhost
+        Transport::Wsl => {
+            return Err("wsl ports are already reachable on localhost".to_string());
+        }
+    }
+    let key = (project_path.to_string(), forward.clone());
+    let mut active = lock_forwards();
+    if active.contains_key(&key) {
+        return Ok(());
+    }
+
+    let mut cmd = Command::new(find_ssh_exe()?);
+    // ssh keeps the first value it sees for an option, so these beat the mux ones
+    cmd.args(["-o", "ControlMaster=no", "-o", "ControlPath=none"])
+        .args(["-o", "ExitOnForwardFailure=yes", "-N"])
+        .args(forward.ssh_spec())
+        .args(ssh_args(target, false));
+    for (name, value) in ssh_env(target)? {
+        cmd.env(name, value);
+    }
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+    #[cfg(windows)]
+    cmd.creation_flags(CREATE_NO_WINDOW);
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to start ssh: {}", e))?;
+    let stderr = pipe_reader(child.stderr.take());
+    let child = Arc::new(Mutex::new(child));
+    active.insert(
+        key.clone(),
+        ActiveForward {
+            child: child.clone(),
+            state: ForwardState::Opening,
+        },
+    );
+    drop(active);
+
+    let status = move |state: ForwardState, error: Option<String>| ForwardStatus {
+        project_path: key.0.clone(),
+        forward: key.1.clone(),
+        state,
+        error,
+    };
+    on_status(status(ForwardState::Opening, None));
+
+    let key = (project_path.to_string(), forward);
+    std::thread::spawn(move || {
+        let started = Instant::now();
+        let mut state = ForwardState::Opening;
+        let exit = loop {
+            let exited = match child.lock() {
+                Ok(mut child) => child.try_wait().ok().flatten(),
+                Err(_) => break None,
+            };
+            if exited.is_some() {
+                break exited;
+            }
+            if state == ForwardState::Opening && forward_is_up(&key.1, started) {
+                state = ForwardState::Open;
+                if let Some(entry) = lock_forwards().get_mut(&key) {
+                    entry.state = state;
+                }
+                on_status(status(state, None));
+            }
+            std::thread::sleep(Duration::from_millis(250));
+        };
+
+        // closed on purpose if close_forward already took it out of the table
+        let ours = {
+            let mut active = lock_forwards();
+            match active.get(&key) {
+                Some(entry) if Arc::ptr_eq(&entry.child, &child) => active.remove(&key).is_some(),
+                _ => false,
+            }
+        };
+        if !ours {
+            return;
+        }
+        let detail: Vec<u8> = stderr.try_iter().flatten().collect();
+        let detail = String::from_utf8_lossy(&detail).trim().to_string();
+        let failed = state == ForwardState::Opening || exit.is_none_or(|s| !s.success());
+        if failed {
+            let error = if detail.is_empty() {
+                "ssh exited".to_string()
+            } else {
+                detail
+            };
+            on_status(status(ForwardState::Failed, Some(error)));
+        } else {
+            on_status(status(ForwardState::Closed, None));
+        }
+    });
+    Ok(())
+}
+
+fn forward_is_up(forward: &PortForward, started: Instant) -> bool {
+    match forward.direction {
+        ForwardDirection::Local => std::net::TcpStream::connect_timeout(
+            &std::net::SocketAddr::from(([127, 0, 0, 1], forward.local_port)),
+            Duration::from_millis(200),
+        )
+        .is_ok(),
+        ForwardDirection::Remote => started.elapsed() >= REMOTE_FORWARD_SETTLE,
+    }
+}
+
+/// Stops a forward. Returns the Closed status to report, or None if it wasn't open.
+pub fn close_forward(project_path: &str, forward: &PortForward) -> Option<ForwardStatus> {
+    let entry = lock_forwards().remove(&(project_path.to_string(), forward.clone()))?;
+    if let Ok(mut child) = entry.child.lock() {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+    Some(ForwardStatus {
+        project_path: project_path.to_string(),
+        forward: forward.clone(),
+        state: ForwardState::Closed,
+        error: None,
+    })
+}
+
+pub fn list_forwards(project_path: &str) -> Vec<ForwardStatus> {
+    lock_forwards()
+        .iter()
+        .filter(|((path, _), _)| path == project_path)
+        .map(|((path, forward), entry)| ForwardStatus {
+            project_path: path.clone(),
+            forward: forward.clone(),
+            state: entry.state,
+            error: None,
+        })
+        .collect()
+}
+
+fn close_all_forwards() {
+    let entries: Vec<ActiveForward> = lock_forwards().drain().map(|(_, e)| e).collect();
+    for entry in entries {
+        if let Ok(mut child) = entry.child.lock() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListeningPort {
+    pub port: u16,
+    pub address: String,
+    pub process: Option<String>,
+    pub pid: Option<u32>,
+}
+
+/// `ss` output, then a `---` line, then "<pid> <cwd>" for every pid it mentions. `ss -p`
+/// only names our own processes, which is all a session could have started anyway.
+const LISTENERS_SCRIPT: &str = r#"command -v ss >/dev/null 2>&1 || { echo "ss is not installed" >&2; exit 1; }
+out=$(ss -ltnpH 2>/dev/null)
+printf '%s\n' "$out"
+echo ---
+for p in $(printf '%s\n' "$out" | grep -o 'pid=[0-9]*' | cut -d= -f2 | sort -u); do
+  printf '%s %s\n' "$p" "$(readlink "/proc/$p/cwd" 2>/dev/null)"
+done"#;
+
+/// TCP ports listening on the remote host from processes running inside the project,
+/// which is how a dev server an agent started there shows up.
+pub fn listening_ports(target: &SshTarget) -> Result<Vec<ListeningPort>, String> {
+    let output = run_checked(target, None, LISTENERS_SCRIPT)
+        .map_err(|e| format!("Failed to list ports on {}: {}", target.host, e))?;
+    let root = expand_home(target, &target.path);
+    Ok(parse_listeners(&output, &root))
+}
+
+/// `~/x` as the remote shell would see it, for comparing against absolute paths.
+fn expand_home(target: &SshTarget, path: &str) -> String {
+    let Some(rest) = path.strip_prefix("~/") else {
+        return path.to_string();
+    };
+    match run_checked(target, None, "printf %s \"$HOME\"") {
+        Ok(home) => format!("{}/{}", home.trim_end_matches('/'), rest),
+        Err(_) => path.to_string(),
+    }
+}
+
+/// Whether `path` is `root` or somewhere below it.
+fn within(root: &str, path: &str) -> bool {
+    let root = root.trim_end_matches('/');
+    path == root || path.starts_with(&format!("{}/", root))
+}
+
+fn parse_listeners(output: &str, project_root: &str) -> Vec<ListeningPort> {
+    let (listeners, cwds) = output.split_once("\n---\n").unwrap_or((output, ""));
+    let cwds: HashMap<u32, &str> = cwds
+        .lines()
+        .filter_map(|line| {
+            let (pid, cwd) = line.split_once(' ')?;
+            Some((pid.parse().ok()?, cwd.trim()))
+        })
+        .collect();
+    let mut ports: Vec<ListeningPort> = Vec::new();
+    for line in listeners.lines() {
+        // State Recv-Q Send-Q Local:Port Peer:Port Process
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let Some(local) = fields.get(3) else { continue };
+        let Some((address, port)) = local.rsplit_once(':') else {
+            continue;
+        };
+        let Ok(port) = port.parse::<u16>() else {
+            continue;
+        };
+        let users = fields.get(5).copied().unwrap_or("");
+        let pid = users
+            .split("pid=")
+            .nth(1)
+            .and_then(|rest| rest.split(|c: char| !c.is_ascii_digit()).next())
+            .and_then(|pid| pid.parse::<u32>().ok());
+        let Some(pid) = pid.filter(|p| cwds.get(p).is_some_and(|cwd| within(project_root, cwd)))
+        else {
+            continue;
+        };
+        // dual-stack servers list once per family
+        if ports.iter().any(|p| p.port == port) {
+            continue;
+        }
+        let process = users
+            .split("((\"")
+            .nth(1)
+            .and_then(|rest| rest.split('"').next())
+            .map(str::to_string);
+        ports.push(ListeningPort {
+            port,
+            address: address.trim_matches(|c| c == '[' || c == ']').to_string(),
+            process,
+            pid: Some(pid),
+        });
+    }
+    ports.sort_by_key(|p| p.port);
+    ports
+}
+
+// --- detached sessions ---
+
+/// Prefix of the tmux sessions and dtach sockets the app starts, so they can be told apart
+/// from the user's own.
+pub const DETACHED_PREFIX: &str = "circuitclaude-";
+
+/// dtach can't report a session's directory, so a `<name>.cwd` file sits beside each socket.
+const DTACH_DIR: &str = r#""${TMPDIR:-/tmp}/circuitclaude-dtach-$(id -u)""#;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DetachedSession {
+    pub name: String,
+    /// "tmux" or "dtach".
+    pub backend: String,
+    pub session_type: String,
+    pub created_at_ms: Option<u64>,
+    /// Whether some client (maybe another machine) is attached right now.
+    pub attached: bool,
+}
+
+/// A fresh name for a detached session running `session_type`; the type is kept in the
+/// name so a listing can reopen it as the right kind of tab.
+pub fn new_detached_name(session_type: &str) -> String {
+    let kind = session_type.replace(|c: char| !c.is_ascii_alphanumeric() && c != '-', "_");
+    let id = uuid::Uuid::new_v4().simple().to_string();
+    format!("{}{}-{}", DETACHED_PREFIX, kind, &id[..8])
+}
+
+/// Names come back from the frontend; tmux reads `:` and `.` in one as a window or pane.
+pub fn check_detached_name(name: &str) -> Result<(), String> {
+    let valid = name.strip_prefix(DETACHED_PREFIX).is_some_and(|rest| {
+        !rest.is_empty()
+            && rest
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    });
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid detached session name: {}", name))
+    }
+}
+
+/// Whether a session running `command` can be left running on the host. Windows hosts
+/// have neither tmux nor dtach, and a windows shell session is started as a plain one.
+pub fn can_detach(target: &SshTarget, command: &str) -> bool {
+    !target.windows && !is_windows_shell(command)
+}
+
+/// Like `login_script`, but runs `command` inside a tmux session called `name` (dtach when
+/// tmux is missing) so it outlives the ssh connection, attaching instead if that session
+/// is still running. Hosts with neither just run the command.
+pub fn detached_login_script(target: &SshTarget, command: &str, name: &str) -> String {
+    if !can_detach(target, command) {
+        return login_script(target, command);
+    }
+    let shell = login_shell(target);
+    let run = format!("exec {} -lc {}", shell, q(command));
+    let name = q(name);
+    format!(
+        "{cd} && if command -v tmux >/dev/null 2>&1; then \
+         exec tmux new-session -A -s {name} -c \"$PWD\" {tmux_run} \\; set-option status off; \
+         fi; d={dir}; \
+         if command -v dtach >/dev/null 2>&1 && mkdir -p -m 700 \"$d\"; then \
+         printf %s \"$PWD\" > \"$d\"/{name}.cwd; exec dtach -A \"$d\"/{name} -r winch {shell} -lc {cmd}; \
+         fi; {run}",
+        cd = cd_to(&target.path),
+        tmux_run = q(&run),
+        dir = DTACH_DIR,
+        cmd = q(command),
+    )
+}
+
+/// Attaches to the detached session `name`, exiting 1 if it has ended.
+pub fn attach_script(name: &str) -> String {
+    format!(
+        "if tmux has-session -t {exact} 2>/dev/null; then exec tmux attach-session -t {exact}; fi; \
+         d={dir}; [ -S \"$d\"/{name} ] && exec dtach -a \"$d\"/{name} -r winch; \
+         echo 'detached session has ended' >&2; exit 1",
+        exact = q(&format!("={}", name)),
+        dir = DTACH_DIR,
+        name = q(name),
+    )
+}
+
+/// One line per session: backend, created (unix secs), attached clients, name, cwd.
+fn list_detached_script() -> String {
+    format!(
+        r#"if command -v tmux >/dev/null 2>&1; then
+  tmux list-sessions -F 'tmux #{{session_created}} #{{session_attached}} #{{session_name}} #{{pane_current_path}}' 2>/dev/null
+fi
+d={dir}
+for s in "$d"/{prefix}*; do
+  [ -S "$s" ] || continue
+  t=$(stat -c %Y "$s" 2>/dev/null || stat -f %m "$s" 2>/dev/null)
+  printf 'dtach %s 0 %s %s\n' "${{t:-0}}" "${{s##*/}}" "$(cat "$s.cwd" 2>/dev/null)"
+done
+true"#,
+        dir = DTACH_DIR,
+        prefix = DETACHED_PREFIX,
+    )
+}
+
+/// The app's detached sessions on the host that were started inside the project.
+pub fn list_detached_sessions(target: &SshTarget) -> Result<Vec<DetachedSession>, String> {
+    let output = run_checked(target, None, &list_detached_script())
+        .map_err(|e| format!("Failed to list sessions on {}: {}", target.host, e))?;
+    let root = expand_home(target, &target.path);
+    Ok(parse_detached(&output, &root))
+}
+
+fn parse_detached(output: &str, project_root: &str) -> Vec<DetachedSession> {
+    let mut sessions: Vec<DetachedSession> = output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(5, ' ');
+            let backend = fields.next()?;
+            let created: u64 = fields.next()?.parse().ok()?;
+            let attached: u32 = fields.next()?.parse().ok()?;
+            let name = fields.next()?;
+            let cwd = fields.next().unwrap_or("").trim();
+            check_detached_name(name).ok()?;
+            if !within(project_root, cwd) {
+                return None;
+            }
+            let (session_type, _) = name.strip_prefix(DETACHED_PREFIX)?.rsplit_once('-')?;
+            Some(DetachedSession {
+                name: name.to_string(),
+                backend: backend.to_string(),
+                session_type: session_type.to_string(),
+                created_at_ms: (created > 0).then_some(created * 1000),
+                attached: attached > 0,
+            })
+        })
+        .collect();
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.created_at_ms));
+    sessions
+}
+
+/// Ends a detached session and whatever is running in it.
+pub fn kill_detached_session(target: &SshTarget, name: &str) -> Result<(), String> {
+    let script = format!(
+        "tmux kill-session -t {exact} 2>/dev/null; d={dir}; \
+         if [ -S \"$d\"/{name} ]; then pkill -f -- \"dtach -A $d/\"{name}; fi; \
+         rm -f \"$d\"/{name} \"$d\"/{name}.cwd",
+        exact = q(&format!("={}", name)),
+        dir = DTACH_DIR,
+        name = q(name),
+    );
+    run_checked(target, None, &script).map(|_| ())
+}
+
+// --- host provisioning check ---
+
+/// Tools probed on a host: name, the flag that makes it print a version, and what
+/// stops working without it. tmux and dtach are judged together.
+const PROBED_TOOLS: &[(&str, &str, &str)] = &[
+    ("git", "--version", "git status and commits are unavailable"),
+    ("claude", "--version", "claude sessions can't start"),
+    ("pi", "--version", "pi sessions can't start"),
+    ("node", "--version", "node-based tools such as pi won't run"),
+    (
+        "inotifywait",
+        "--help",
+        "open files are checked for outside changes by polling",
+    ),
+    ("tmux", "-V", ""),
+    ("dtach", "", ""),
+    ("ss", "-V", "ports to forward can't be detected"),
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolStatus {
+    pub name: String,
+    /// Where `command -v` found it; `None` when it's missing.
+    pub path: Option<String>,
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostDiagnosis {
+    /// `uname -srm`.
+    pub os: String,
+    /// The command channel's shell and version, e.g. "bash 5.2.15(1)-release".
+    pub shell: String,
+    /// The account's own shell ($SHELL), which interactive sessions start in.
+    pub login_shell: Option<String>,
+    /// A windows host, reached through Git for Windows' bash.
+    pub windows: bool,
+    pub tools: Vec<ToolStatus>,
+    pub missing: Vec<String>,
+    /// Features that won't work, or will work worse, given what's missing.
+    pub degraded: Vec<String>,
+}
+
+fn diagnose_script() -> String {
+    let mut script = String::from(
+        "printf 'os\\t%s\\n' \"$(uname -srm 2>/dev/null)\"\n\
+         printf 'shell\\tbash %s\\n' \"$BASH_VERSION\"\n\
+         printf 'login\\t%s\\n' \"${SHELL:-}\"\n\
+         to=; command -v timeout >/dev/null 2>&1 && to='timeout 10'\n",
+    );
+    for (name, flag, _) in PROBED_TOOLS {
+        script.push_str(&format!(
+            "if p=$(command -v {name} 2>/dev/null); then \
+             v=$([ -n {flag} ] && $to \"$p\" {flag} 2>&1 < /dev/null | head -n 1); \
+             printf 'tool\\t%s\\t%s\\t%s\\n' {name} \"$p\" \"$v\"; \
+             else printf 'tool\\t%s\\t\\t\\n' {name}; fi\n",
+            name = name,
+            flag = q(flag),
+        ));
+    }
+    script
+}
+
+/// Probes the host for the tools the app leans on. Runs over a command channel, so the
+/// channel's connect probe has already settled which shell the host gives us.
+pub fn diagnose(target: &SshTarget) -> Result<HostDiagnosis, String> {
+    let output = run(target, None, &diagnose_script())?;
+    Ok(parse_diagnosis(&output.stdout))
+}
+
+fn parse_diagnosis(output: &str) -> HostDiagnosis {
+    let mut diagnosis = HostDiagnosis {
+        os: String::new(),
+        shell: String::new(),
+        login_shell: None,
+        windows: false,
+        tools: Vec::new(),
+        missing: Vec::new(),
+        degraded: Vec::new(),
+    };
+    let non_empty = |value: &str| Some(value.trim().to_string()).filter(|v| !v.is_empty());
+    for line in output.lines() {
+        let fields: Vec<&str> = line.trim_end_matches('\r').split('\t').collect();
+        match fields.as_slice() {
+            ["os", os] => diagnosis.os = os.trim().to_string(),
+            ["shell", shell] => diagnosis.shell = shell.trim().to_string(),
+            ["login", shell] => diagnosis.login_shell = non_empty(shell),
+            ["tool", name, path, version] => diagnosis.tools.push(ToolStatus {
+                name: name.to_string(),
+                path: non_empty(path),
+                version: non_empty(version),
+            }),
+            _ => {}
+        }
+    }
+    // the shell is msys/cygwin exactly when its uname says so
+    diagnosis.windows = is_msys_uname(&diagnosis.os);
+
+    let has = |name: &str| {
+        diagnosis
+            .tools
+            .iter()
+            .any(|tool| tool.name == name && tool.path.is_some())
+    };
+    let mut missing = Vec::new();
+    let mut degraded = Vec::new();
+    for (name, _, consequence) in PROBED_TOOLS {
+        if has(name) {
+            continue;
+        }
+        missing.push(name.to_string());
+        if !consequence.is_empty() {
+            degraded.push(format!("no {}: {}", name, consequence));
+        }
+    }
+    match (has("tmux"), has("dtach")) {
+        (true, _) => {}
+        (false, true) => {
+            degraded.push("no tmux: sessions kept on the host use dtach, without scrollback".into())
+        }
+        (false, false) => {
+            degraded.push("no tmux or dtach: sessions end when the connection drops".into())
+        }
+    }
+    diagnosis.missing = missing;
+    diagnosis.degraded = degraded;
+    diagnosis
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -821,7 +2522,11 @@ mod tests {
     /// Drives the framing protocol against a local login bash — same plumbing as a real
     /// channel (including profile noise the probe has to swallow), no network.
     fn local_conn() -> Option<Conn> {
-        let mut cmd = Command::new("bash");
+        local_conn_with("bash")
+    }
+
+    fn local_conn_with(shell: &str) -> Option<Conn> {
+        let mut cmd = Command::new(shell);
         cmd.arg("-l");
         Conn::spawn(cmd).ok()
     }
@@ -852,6 +2557,89 @@ mod tests {
         assert_eq!(out.stdout.len(), 40000);
     }
 
+    #[test]
+    fn streams_output_before_the_frame_ends() {
+        let Some(mut conn) = local_conn() else { return };
+
+        let mut seen: Vec<u8> = Vec::new();
+        let mut sink = |chunk: &[u8]| seen.extend_from_slice(chunk);
+        let out = conn
+            .exec_observed(
+                "printf 'one\\n'; sleep 0.3; printf 'two'",
+                30,
+                Some(&mut sink),
+            )
+            .unwrap();
+        assert_eq!(out.stdout, "one\ntwo");
+        // the streamed bytes match the framed result exactly: no marker, no injected newline
+        assert_eq!(seen, b"one\ntwo");
+    }
+
+    #[test]
+    fn streams_both_pipes_and_stops_without_losing_the_channel() {
+        let Some(mut conn) = local_conn() else { return };
+        assert!(conn.bash);
+        streams_and_stops(&mut conn);
+    }
+
+    #[test]
+    fn stops_streamed_commands_under_a_plain_sh_too() {
+        // dash has no job control without a tty, as in slim containers
+        let Some(mut conn) = local_conn_with("dash") else {
+            return;
+        };
+        assert!(!conn.bash);
+        streams_and_stops(&mut conn);
+    }
+
+    fn streams_and_stops(conn: &mut Conn) {
+        let far = Instant::now() + Duration::from_secs(30);
+        let none = CancelToken::default();
+
+        let mut chunks = Vec::new();
+        let script = "printf out; printf err 1>&2; exit 4";
+        let end = conn
+            .exec_stream(script, far, &none, &mut |c| chunks.push(c))
+            .unwrap();
+        assert_eq!(end, StreamEnd::Exited { code: 4 });
+        let stdout: Vec<u8> = chunks
+            .iter()
+            .flat_map(|c| match c {
+                StreamChunk::Stdout { bytes } => bytes.clone(),
+                StreamChunk::Stderr { .. } => Vec::new(),
+            })
+            .collect();
+        assert_eq!(stdout, b"out");
+        assert!(chunks.contains(&StreamChunk::Stderr {
+            bytes: b"err".to_vec()
+        }));
+
+        // cancelling kills the background child too, not just the subshell
+        let cancel = CancelToken::default();
+        let started = Instant::now();
+        let script = "sleep 30 & sleep 30";
+        let trigger = cancel.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(300));
+            trigger.cancel();
+        });
+        let mut chunks = Vec::new();
+        let end = conn
+            .exec_stream(script, far, &cancel, &mut |c| chunks.push(c))
+            .unwrap();
+        assert_eq!(end, StreamEnd::Cancelled);
+        assert!(started.elapsed() < Duration::from_secs(5));
+        // no shell complaints about job control leak into the command's output
+        assert_eq!(chunks, []);
+
+        let soon = Instant::now() + Duration::from_millis(300);
+        let end = conn.exec_stream("sleep 9", soon, &none, &mut |_| {});
+        assert_eq!(end.unwrap(), StreamEnd::TimedOut);
+
+        let out = conn.exec("echo still here", 30).unwrap();
+        assert_eq!(out.stdout, "still here\n");
+    }
+
     #[test]
     fn quotes_arguments_for_the_remote_shell() {
         assert_eq!(q("plain"), "'plain'");
@@ -892,6 +2680,65 @@ mod tests {
         assert!(!is_remote("C:\\Projects\\app"));
     }
 
+    #[test]
+    fn container_urls_run_through_docker_exec() {
+        let target = target_from_url("docker://node@app/workspaces/app").unwrap();
+        assert_eq!(target.transport, Transport::Docker);
+        assert_eq!(target.authority, "docker:node@app");
+        assert_eq!(target.host, "app");
+        assert_eq!(target.path, "/workspaces/app");
+        assert_eq!(target.url_for("/tmp"), "docker://node@app/tmp");
+        assert!(is_remote("docker://app/src"));
+
+        let (program, args) = shell_argv(&target, true, "claude").unwrap();
+        assert_eq!(program, "docker");
+        let expected = [
+            "exec",
+            "-i",
+            "-t",
+            "-e",
+            "TERM=xterm-256color",
+            "-u",
+            "node",
+            "app",
+            "sh",
+            "-c",
+            "claude",
+        ];
+        assert_eq!(args, expected);
+        assert!(control_path(&target).is_none());
+    }
+
+    #[test]
+    fn parses_wsl_targets() {
+        let target = target_from_url("wsl://Ubuntu/home/me/app").unwrap();
+        assert_eq!(target.transport, Transport::Wsl);
+        assert_eq!(target.authority, "wsl:Ubuntu");
+        assert_eq!(target.url_for("/srv"), "wsl://Ubuntu/srv");
+        let (program, args) = wsl_runner(&target);
+        assert_eq!(program, "wsl.exe");
+        assert_eq!(args, ["-d", "Ubuntu", "-e"]);
+
+        assert_eq!(
+            wsl_mount_path(r"C:\Users\me\shot.png").as_deref(),
+            Some("/mnt/c/Users/me/shot.png")
+        );
+        assert_eq!(wsl_mount_path("/home/me"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn wsl_targets_run_through_the_substituted_runner() {
+        let dir = std::env::temp_dir().to_string_lossy().to_string();
+        let target = target_from_url(&format!("wsl://Ubuntu{}", dir)).unwrap();
+        WSL_RUNNER.with(|runner| runner.set(Some("env")));
+        let mut conn = Conn::open(&target).unwrap();
+        let out = conn
+            .exec(&format!("{} && pwd", cd_to(&target.path)), 30)
+            .unwrap();
+        assert_eq!(out.stdout.trim(), dir.trim_end_matches('/'));
+    }
+
     #[test]
     fn authority_omits_the_default_port() {
         let target = make_target(
@@ -921,6 +2768,55 @@ mod tests {
         assert!(env.iter().any(|(name, value)| name == "SSH_ASKPASS_REQUIRE" && value == "force"));
     }
 
+    #[test]
+    fn key_passphrases_go_through_askpass_too() {
+        let key = Some("~/.ssh/id".to_string());
+        let mut target = make_target(None, "box".into(), None, key, "/srv".into());
+        target.passphrase = Some("secret".into());
+
+        let args = ssh_args(&target, false);
+        assert!(!args.iter().any(|arg| arg == "PubkeyAuthentication=no"));
+        assert!(!args.iter().any(|arg| arg == "BatchMode=yes"));
+
+        let names: Vec<String> = ssh_env(&target).unwrap().into_iter().map(|e| e.0).collect();
+        assert!(names.contains(&"CIRCUITCLAUDE_SSH_PASSPHRASE".to_string()));
+        assert!(!names.contains(&"CIRCUITCLAUDE_SSH_PASSWORD".to_string()));
+    }
+
+    #[test]
+    fn per_remote_options_take_precedence() {
+        let mut target = make_target(None, "box".into(), None, None, "/srv".into());
+        target.ssh_options = vec!["StrictHostKeyChecking=yes".into()];
+        let args = ssh_args(&target, false);
+        let strict = args.iter().find(|a| a.starts_with("StrictHostKeyChecking"));
+        assert_eq!(strict.unwrap(), "StrictHostKeyChecking=yes");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn shares_one_connection_per_host_on_unix() {
+        let target = make_target(None, "mux-box".into(), None, None, "/srv".into());
+        let path = control_path(&target).unwrap();
+        let args = ssh_args(&target, true);
+        assert!(args.iter().any(|arg| arg == "ControlMaster=auto"));
+        assert!(args.contains(&format!("ControlPath={}", path.to_string_lossy())));
+        assert_eq!(args.last().map(String::as_str), Some("mux-box"));
+
+        let other = make_target(None, "mux-box".into(), Some(2222), None, "/srv".into());
+        assert_ne!(control_path(&other).unwrap(), path);
+
+        // a host whose master failed connects directly for a while
+        disable_multiplexing(&target);
+        let args = ssh_args(&target, false);
+        assert!(!args.iter().any(|arg| arg.starts_with("ControlPath=")));
+
+        // auth failures aren't the master's fault, so they don't turn sharing off
+        let refused = "ssh to mux-box failed: user@mux-box: Permission denied (publickey).";
+        assert!(!master_failed(&target, refused));
+        let stale = "Control socket connect(/tmp/x): Connection refused";
+        assert!(master_failed(&target, stale));
+    }
+
     #[test]
     fn joins_paths_per_location() {
         assert_eq!(
@@ -951,6 +2847,28 @@ mod tests {
         );
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn container_sessions_fall_back_to_sh() {
+        let container = target_from_url("docker://app/").unwrap();
+        let script = login_script(&container, "echo $0");
+        assert!(script.contains("exec \"$(command -v bash || echo sh)\" -lc 'echo $0'"));
+
+        // a PATH with sh but no bash on it, like a slim image
+        let dir = std::env::temp_dir().join(format!("no-bash-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let _ = std::os::unix::fs::symlink("/bin/sh", dir.join("sh"));
+        let out = Command::new("/bin/sh")
+            .arg("-c")
+            .arg(&script)
+            .env("PATH", &dir)
+            .env("HOME", &dir)
+            .output()
+            .unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(String::from_utf8_lossy(&out.stdout).trim(), "sh");
+    }
+
     #[test]
     fn runs_windows_sessions_under_cmd() {
         let windows = make_target(None, "box".into(), None, None, "C:/Projects/app".into());
@@ -998,6 +2916,147 @@ mod tests {
         assert!(!is_msys_uname("mingw notes of the day\nLinux\n"));
     }
 
+    #[test]
+    fn keeps_listeners_started_inside_the_project() {
+        let output = "\
+LISTEN 0      511          0.0.0.0:5173      0.0.0.0:*    users:((\"node\",pid=4242,fd=21))
+LISTEN 0      511             [::]:5173         [::]:*    users:((\"node\",pid=4242,fd=22))
+LISTEN 0      128        127.0.0.1:6379      0.0.0.0:*    users:((\"redis-server\",pid=99,fd=6))
+LISTEN 0      4096               *:8080            *:*
+LISTEN 0      511            [::1]:3000         [::]:*    users:((\"python3\",pid=7,fd=3))
+---
+4242 /srv/app
+99 /var/lib/redis
+7 /srv/app/docs
+";
+        let ports = parse_listeners(output, "/srv/app/");
+        assert_eq!(
+            ports,
+            vec![
+                ListeningPort {
+                    port: 3000,
+                    address: "::1".to_string(),
+                    process: Some("python3".to_string()),
+                    pid: Some(7),
+                },
+                ListeningPort {
+                    port: 5173,
+                    address: "0.0.0.0".to_string(),
+                    process: Some("node".to_string()),
+                    pid: Some(4242),
+                },
+            ]
+        );
+        assert!(parse_listeners(output, "/srv/ap").is_empty());
+    }
+
+    #[test]
+    fn background_work_leaves_a_channel_free_and_queues_can_be_cancelled() {
+        let authority = "pool-test";
+        let info = RemoteInfo {
+            channels: Some(3),
+            ..Default::default()
+        };
+        registry()
+            .lock()
+            .unwrap()
+            .insert(authority.to_string(), info);
+        let queued = |lane: Lane| {
+            let (tx, rx) = channel();
+            std::thread::spawn(move || {
+                let lease = match lane {
+                    Lane::Interactive => checkout(authority),
+                    Lane::Background => in_background(|| checkout(authority)),
+                };
+                let _ = tx.send(lease.map(|_| ()));
+            });
+            rx
+        };
+        let wait = Duration::from_millis(600);
+
+        let first = in_background(|| checkout(authority)).unwrap();
+        let second = in_background(|| checkout(authority)).unwrap();
+        // two of three channels are background work, so the third is kept for the user
+        let third = queued(Lane::Background);
+        assert!(third.recv_timeout(wait).is_err());
+        let interactive = checkout(authority).unwrap();
+
+        cancel_queued(authority);
+        let cancelled = third.recv_timeout(wait).unwrap();
+        assert_eq!(cancelled, Err("cancelled".to_string()));
+
+        drop(first);
+        assert_eq!(queued(Lane::Background).recv_timeout(wait).unwrap(), Ok(()));
+        drop((second, interactive));
+    }
+
+    #[test]
+    fn a_panic_in_background_work_restores_the_lane() {
+        let panicked = std::panic::catch_unwind(|| in_background(|| panic!("probe failed")));
+        assert!(panicked.is_err());
+        assert_eq!(LANE.with(Cell::get), Lane::Interactive);
+    }
+
+    #[test]
+    fn lists_only_the_apps_sessions_inside_the_project() {
+        let name = new_detached_name("claude");
+        assert!(check_detached_name(&name).is_ok());
+        assert!(check_detached_name("circuitclaude-x:0.1").is_err());
+        assert!(check_detached_name("work").is_err());
+
+        let output = "tmux 1700000000 1 circuitclaude-claude-1a2b3c4d /srv/app/src
+tmux 1700000500 0 work /srv/app
+tmux 1700000900 0 circuitclaude-codex-99aa88bb /srv/other
+dtach 1700000300 0 circuitclaude-pi_agent-0f0f0f0f /srv/app
+dtach 1700000400 0 circuitclaude-shell-12345678 
+";
+        let sessions = parse_detached(output, "/srv/app/");
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].name, "circuitclaude-pi_agent-0f0f0f0f");
+        assert_eq!(sessions[1].name, "circuitclaude-claude-1a2b3c4d");
+        assert_eq!(sessions[0].session_type, "pi_agent");
+        assert_eq!(sessions[0].backend, "dtach");
+        assert!(sessions[1].attached);
+        assert_eq!(sessions[1].created_at_ms, Some(1_700_000_000_000));
+    }
+
+    #[test]
+    fn reports_missing_tools_and_what_they_cost() {
+        let output = "os\tLinux 6.1.0 x86_64\n\
+                      shell\tbash 5.2.15(1)-release\n\
+                      login\t/usr/bin/zsh\n\
+                      tool\tgit\t/usr/bin/git\tgit version 2.43.0\n\
+                      tool\tclaude\t/home/me/.local/bin/claude\t2.0.14 (Claude Code)\n\
+                      tool\ttmux\t\t\n\
+                      tool\tdtach\t/usr/bin/dtach\t\n";
+        let diagnosis = parse_diagnosis(output);
+        assert!(!diagnosis.windows);
+        assert_eq!(diagnosis.os, "Linux 6.1.0 x86_64");
+        assert_eq!(diagnosis.login_shell.as_deref(), Some("/usr/bin/zsh"));
+        let git = &diagnosis.tools[0];
+        assert_eq!(git.version.as_deref(), Some("git version 2.43.0"));
+
+        // tools the script never reported count as missing too
+        let missing = ["pi", "node", "inotifywait", "tmux", "ss"];
+        assert_eq!(diagnosis.missing, missing);
+        assert!(diagnosis.degraded.iter().any(|d| d.starts_with("no ss:")));
+        assert!(diagnosis.degraded.iter().any(|d| d.contains("use dtach")));
+        assert!(!diagnosis.degraded.iter().any(|d| d.starts_with("no git")));
+    }
+
+    #[test]
+    fn diagnose_script_finds_tools_on_the_local_shell() {
+        let Some(mut conn) = local_conn() else { return };
+        let out = conn.exec(&diagnose_script(), 30).unwrap();
+        let diagnosis = parse_diagnosis(&out.stdout);
+        assert_eq!(diagnosis.windows, conn.msys);
+        assert!(diagnosis.shell.starts_with("bash "));
+        assert_eq!(diagnosis.tools.len(), PROBED_TOOLS.len());
+        let has_git = Command::new("git").arg("--version").output().is_ok();
+        let git_missing = diagnosis.missing.iter().any(|name| name == "git");
+        assert_eq!(git_missing, !has_git);
+    }
+
     #[test]
     fn encodes_base64() {
         assert_eq!(base64_encode(b""), "");
@@ -1007,6 +3066,20 @@ mod tests {
         assert_eq!(base64_encode(b"hello world\n"), "aGVsbG8gd29ybGQK");
     }
 
+    #[test]
+    fn decodes_base64() {
+        assert_eq!(base64_decode("").unwrap(), b"");
+        assert_eq!(base64_decode("Zg==").unwrap(), b"f");
+        assert_eq!(base64_decode("Zm8=").unwrap(), b"fo");
+        assert_eq!(
+            base64_decode("aGVsbG8g\nd29ybGQK\n").unwrap(),
+            b"hello world\n"
+        );
+        let bytes: Vec<u8> = (0..=255).collect();
+        assert_eq!(base64_decode(&base64_encode(&bytes)).unwrap(), bytes);
+        assert!(base64_decode("not*base64").is_err());
+    }
+
     #[test]
     fn writes_content_verbatim_through_base64() {
         let Some(mut conn) = local_conn() else { return };
@@ -1023,7 +3096,7 @@ mod tests {
 
 const B64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
 
-fn base64_encode(bytes: &[u8]) -> String {
+pub(crate) fn base64_encode(bytes: &[u8]) -> String {
     let mut out = String::with_capacity((bytes.len() + 2) / 3 * 4);
     for chunk in bytes.chunks(3) {
         let b0 = chunk[0] as u32;
@@ -1045,3 +3118,28 @@ fn base64_encode(bytes: &[u8]) -> String {
     }
     out
 }
+
+/// Decodes standard base64, skipping the line breaks `base64` wraps its output with.
+pub(crate) fn base64_decode(text: &str) -> Result<Vec<u8>, String> {
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    let mut acc: u32 = 0;
+    let mut bits = 0;
+    for byte in text.bytes() {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' | b'\n' | b'\r' | b' ' => continue,
+            _ => return Err(format!("invalid base64 byte 0x{:02x}", byte)),
+        };
+        acc = (acc << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    Ok(out)
+}
@@ -0,0 +1,209 @@
+// per-host connection health for remote projects, measured by pinging over a command
+// channel of its own, so a host whose pooled channels are all busy is still measured
+use crate::remote::{self, Location, SshTarget};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::Emitter;
+
+/// Ping interval while the host answers.
+const PING_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Ping interval once the host stops answering, so recovery shows up quickly.
+const RETRY_INTERVAL: Duration = Duration::from_secs(3);
+
+const PING_TIMEOUT_SECS: u64 = 5;
+
+/// Round trips slower than this mark the host degraded.
+const SLOW_PING: Duration = Duration::from_millis(800);
+
+/// Consecutive failed pings before a host counts as disconnected rather than degraded.
+const FAILURES_TO_DISCONNECT: u32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HealthState {
+    Connected,
+    Degraded,
+    Disconnected,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostHealth {
+    pub authority: String,
+    pub state: HealthState,
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+    pub checked_at_ms: u64,
+}
+
+struct StopFlag(Arc<AtomicBool>);
+
+impl Drop for StopFlag {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+struct HostEntry {
+    health: Option<HostHealth>,
+    watchers: usize,
+    _stop: StopFlag,
+}
+
+#[derive(Clone)]
+pub struct RemoteHealthMonitor {
+    app: tauri::AppHandle,
+    hosts: Arc<Mutex<HashMap<String, HostEntry>>>,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// The state after a ping, given how many pings in a row have now failed.
+fn classify(latency: Option<Duration>, failures: u32) -> HealthState {
+    match latency {
+        _ if failures >= FAILURES_TO_DISCONNECT => HealthState::Disconnected,
+        _ if failures > 0 => HealthState::Degraded,
+        Some(latency) if latency > SLOW_PING => HealthState::Degraded,
+        _ => HealthState::Connected,
+    }
+}
+
+impl RemoteHealthMonitor {
+    pub fn new(app: tauri::AppHandle) -> Self {
+        Self {
+            app,
+            hosts: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Last known health of every monitored host.
+    pub fn status(&self) -> Vec<HostHealth> {
+        let Ok(hosts) = self.hosts.lock() else {
+            return Vec::new();
+        };
+        let mut list: Vec<HostHealth> = hosts.values().filter_map(|h| h.health.clone()).collect();
+        list.sort_by(|a, b| a.authority.cmp(&b.authority));
+        list
+    }
+
+    /// Starts monitoring the host behind `project_path`; local projects are ignored.
+    /// Projects on the same host share one monitor.
+    pub fn watch(&self, project_path: &str) -> Result<(), String> {
+        let Location::Remote(target) = remote::locate(project_path) else {
+            return Ok(());
+        };
+        let mut hosts = self.hosts.lock().map_err(|e| e.to_string())?;
+        if let Some(entry) = hosts.get_mut(&target.authority) {
+            entry.watchers += 1;
+            return Ok(());
+        }
+        let authority = target.authority.clone();
+        let stop = self.monitor(target);
+        hosts.insert(
+            authority,
+            HostEntry {
+                health: None,
+                watchers: 1,
+                _stop: stop,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn unwatch(&self, project_path: &str) -> Result<(), String> {
+        let Location::Remote(target) = remote::locate(project_path) else {
+            return Ok(());
+        };
+        let mut hosts = self.hosts.lock().map_err(|e| e.to_string())?;
+        match hosts.get_mut(&target.authority) {
+            Some(entry) if entry.watchers > 1 => entry.watchers -= 1,
+            Some(_) => {
+                hosts.remove(&target.authority);
+            }
+            None => {}
+        }
+        Ok(())
+    }
+
+    pub fn cleanup(&self) {
+        if let Ok(mut hosts) = self.hosts.lock() {
+            hosts.clear();
+        }
+    }
+
+    /// Records a ping result and emits `remote-health-changed` when the state or the
+    /// rounded latency moved.
+    fn record(&self, health: HostHealth) {
+        let changed = {
+            let Ok(mut hosts) = self.hosts.lock() else {
+                return;
+            };
+            let Some(entry) = hosts.get_mut(&health.authority) else {
+                return;
+            };
+            let changed = entry.health.as_ref().is_none_or(|old| {
+                old.state != health.state
+                    || old.error != health.error
+                    || old.latency_ms.map(|ms| ms / 50) != health.latency_ms.map(|ms| ms / 50)
+            });
+            entry.health = Some(health.clone());
+            changed
+        };
+        if changed {
+            let _ = self.app.emit("remote-health-changed", health);
+        }
+    }
+
+    fn monitor(&self, target: SshTarget) -> StopFlag {
+        let stop = Arc::new(AtomicBool::new(false));
+        let monitor = self.clone();
+        let thread_stop = stop.clone();
+        std::thread::spawn(move || {
+            let mut failures = 0;
+            while !thread_stop.load(Ordering::Relaxed) {
+                let (latency, error) = match remote::ping(&target, PING_TIMEOUT_SECS) {
+                    Ok(latency) => (Some(latency), None),
+                    Err(err) => (None, Some(err)),
+                };
+                failures = if error.is_some() { failures + 1 } else { 0 };
+                monitor.record(HostHealth {
+                    authority: target.authority.clone(),
+                    state: classify(latency, failures),
+                    latency_ms: latency.map(|d| d.as_millis() as u64),
+                    error,
+                    checked_at_ms: now_ms(),
+                });
+                std::thread::sleep(if failures > 0 {
+                    RETRY_INTERVAL
+                } else {
+                    PING_INTERVAL
+                });
+            }
+        });
+        StopFlag(stop)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_miss_degrades_and_two_disconnect() {
+        let fast = Some(Duration::from_millis(40));
+        let slow = Some(Duration::from_secs(2));
+        assert_eq!(classify(fast, 0), HealthState::Connected);
+        assert_eq!(classify(slow, 0), HealthState::Degraded);
+        assert_eq!(classify(None, 1), HealthState::Degraded);
+        assert_eq!(classify(None, 2), HealthState::Disconnected);
+    }
+}
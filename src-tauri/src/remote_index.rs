@@ -0,0 +1,372 @@
+// whole-tree file listings for remote projects: fetched in one round trip, then kept in
+// step with git status, so browsing a deep tree over a slow link isn't a round trip per
+// directory
+use crate::git::GitStatus;
+use crate::remote::{self, SshTarget};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Listings bigger than this are left to per-directory browsing.
+const INDEX_BYTE_CAP: usize = 8 << 20;
+
+/// Outside a repo there's no status to follow file changes with, so the tree is fetched
+/// again once it's this old.
+const PLAIN_INDEX_TTL: Duration = Duration::from_secs(10);
+
+/// Tracked files plus untracked ones git doesn't ignore, minus tracked files deleted from
+/// the work tree, then the untracked directories; outside a repo, every file and then the
+/// empty directories. NUL-separated, the first field saying which.
+fn index_script() -> String {
+    format!(
+        "{{ if git rev-parse --is-inside-work-tree >/dev/null 2>&1; then \
+         printf 'git\\0'; git ls-files -z --cached --others --exclude-standard; \
+         printf '\\0'; git ls-files -z --deleted; \
+         printf '\\0'; git ls-files -z --others --exclude-standard --directory; \
+         else printf 'plain\\0'; find . -name .git -prune -o -type f -print0; \
+         printf '\\0'; find . -name .git -prune -o -type d -empty -print0; fi; }} | head -c {}",
+        INDEX_BYTE_CAP + 1
+    )
+}
+
+/// Project-relative file paths out of `index_script`'s output, or `None` if it hit the
+/// cap. Directories with nothing indexed inside (nested repos, empty directories) keep a
+/// trailing slash, marking a directory the index can't see into.
+fn parse_index(output: &str) -> Option<BTreeSet<String>> {
+    if output.len() > INDEX_BYTE_CAP {
+        return None;
+    }
+    let mut fields = output.split('\0');
+    let mut files = BTreeSet::new();
+    let section = |fields: &mut std::str::Split<'_, char>| -> Vec<String> {
+        fields
+            .take_while(|f| !f.is_empty())
+            .map(|f| f.trim_start_matches("./").to_string())
+            .collect()
+    };
+    match fields.next() {
+        Some("git") => {
+            files.extend(section(&mut fields));
+            for deleted in section(&mut fields) {
+                files.remove(&deleted);
+            }
+        }
+        Some("plain") => files.extend(section(&mut fields)),
+        _ => return None,
+    }
+    // untracked directories come back whole, so only the ones with no files under them
+    // add anything
+    for dir in section(&mut fields) {
+        let dir = format!("{}/", dir.trim_end_matches('/'));
+        let has_files = files
+            .range(dir.clone()..)
+            .next()
+            .is_some_and(|path| path.starts_with(&dir));
+        if dir != "./" && dir != "/" && !has_files {
+            files.insert(dir);
+        }
+    }
+    Some(files)
+}
+
+/// Immediate children of `rel_dir` as (name, is_dir), or `None` when the index can't
+/// say (the directory is a nested repo, or empty).
+fn children(files: &BTreeSet<String>, rel_dir: &str) -> Option<Vec<(String, bool)>> {
+    let prefix = if rel_dir.is_empty() {
+        String::new()
+    } else {
+        format!("{}/", rel_dir.trim_end_matches('/'))
+    };
+    if !prefix.is_empty() && files.contains(&prefix) {
+        return None;
+    }
+    let mut entries: Vec<(String, bool)> = Vec::new();
+    for path in files
+        .range(prefix.clone()..)
+        .take_while(|path| path.starts_with(&prefix))
+    {
+        let rest = &path[prefix.len()..];
+        let entry = match rest.split_once('/') {
+            Some((dir, _)) => (dir.to_string(), true),
+            None => (rest.to_string(), false),
+        };
+        // sorted input puts a directory's files next to each other
+        if !entry.0.is_empty() && entries.last() != Some(&entry) {
+            entries.push(entry);
+        }
+    }
+    Some(entries)
+}
+
+struct ProjectIndex {
+    /// `None` when the tree was too big to index.
+    files: Option<BTreeSet<String>>,
+    /// The tracked file set may have moved (commit, checkout, pull); the next read
+    /// fetches the tree again.
+    stale: bool,
+    /// Listed outside a repo, so it expires rather than waiting to be marked stale.
+    expires: Option<Instant>,
+}
+
+impl ProjectIndex {
+    fn current(&self) -> bool {
+        !self.stale && self.expires.is_none_or(|at| Instant::now() < at)
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct RemoteFileIndex {
+    projects: Arc<Mutex<HashMap<String, ProjectIndex>>>,
+    /// Mirrors the `remote_file_index` setting, so listings don't read settings.json.
+    enabled: Arc<AtomicBool>,
+}
+
+impl RemoteFileIndex {
+    pub fn new(enabled: bool) -> Self {
+        let index = Self::default();
+        index.enabled.store(enabled, Ordering::Relaxed);
+        index
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Follows the setting; turning the index off drops what it holds.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+        if !enabled {
+            self.cleanup();
+        }
+    }
+
+    /// Runs `f` over the project's index, fetching the tree first if it isn't cached or
+    /// has gone stale. `Ok(None)` means the tree is too big to index.
+    fn with_files<T>(
+        &self,
+        project_path: &str,
+        target: &SshTarget,
+        f: impl FnOnce(&BTreeSet<String>) -> Option<T>,
+    ) -> Result<Option<T>, String> {
+        let cached = {
+            let projects = self.projects.lock().map_err(|e| e.to_string())?;
+            projects
+                .get(project_path)
+                .is_some_and(ProjectIndex::current)
+        };
+        if !cached {
+            let output = remote::run_checked(target, Some(&target.path), &index_script())?;
+            let files = parse_index(&output);
+            let expires = output
+                .starts_with("plain\0")
+                .then(|| Instant::now() + PLAIN_INDEX_TTL);
+            let mut projects = self.projects.lock().map_err(|e| e.to_string())?;
+            projects.insert(
+                project_path.to_string(),
+                ProjectIndex {
+                    files,
+                    stale: false,
+                    expires,
+                },
+            );
+        }
+        let projects = self.projects.lock().map_err(|e| e.to_string())?;
+        Ok(projects
+            .get(project_path)
+            .and_then(|p| p.files.as_ref())
+            .and_then(f))
+    }
+
+    /// Entries directly inside `rel_dir` as (name, is_dir), or `None` when the caller
+    /// should list the directory itself.
+    pub fn read_directory(
+        &self,
+        project_path: &str,
+        target: &SshTarget,
+        rel_dir: &str,
+    ) -> Result<Option<Vec<(String, bool)>>, String> {
+        self.with_files(project_path, target, |files| children(files, rel_dir))
+    }
+
+    /// Every file in the project, or `None` when the tree is too big to index.
+    pub fn files(
+        &self,
+        project_path: &str,
+        target: &SshTarget,
+    ) -> Result<Option<Vec<String>>, String> {
+        self.with_files(project_path, target, |files| {
+            Some(
+                files
+                    .iter()
+                    .filter(|f| !f.ends_with('/'))
+                    .cloned()
+                    .collect(),
+            )
+        })
+    }
+
+    /// Refetches the tree on next read.
+    pub fn invalidate(&self, project_path: &str) {
+        if let Ok(mut projects) = self.projects.lock() {
+            if let Some(index) = projects.get_mut(project_path) {
+                index.stale = true;
+            }
+        }
+    }
+
+    /// Folds a git status change into the index: new and deleted work tree files show up
+    /// in status, so they don't need the tree fetched again.
+    pub fn apply_status(&self, project_path: &str, old: Option<&GitStatus>, new: &GitStatus) {
+        let Ok(mut projects) = self.projects.lock() else {
+            return;
+        };
+        let Some(files) = projects
+            .get_mut(project_path)
+            .and_then(|p| p.files.as_mut())
+        else {
+            return;
+        };
+        let key = |path: &str, status: &str| {
+            if status == "S" {
+                format!("{}/", path)
+            } else {
+                path.to_string()
+            }
+        };
+        let current: HashSet<&str> = new.files.iter().map(|f| f.path.as_str()).collect();
+        for entry in &new.files {
+            if entry.status == "D" {
+                files.remove(&entry.path);
+            } else {
+                files.insert(key(&entry.path, &entry.status));
+            }
+        }
+        // untracked files leave status by being deleted, or by being committed, which
+        // marks the index stale anyway
+        for entry in old.map(|s| s.files.as_slice()).unwrap_or_default() {
+            let untracked = entry.status == "?" || entry.status == "S";
+            if untracked && !current.contains(entry.path.as_str()) {
+                files.remove(&key(&entry.path, &entry.status));
+            }
+        }
+    }
+
+    pub fn cleanup(&self) {
+        if let Ok(mut projects) = self.projects.lock() {
+            projects.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::GitFileEntry;
+
+    fn status(files: &[(&str, &str)]) -> GitStatus {
+        GitStatus {
+            is_repo: true,
+            branch: "main".to_string(),
+            files: files
+                .iter()
+                .map(|(path, status)| GitFileEntry {
+                    path: path.to_string(),
+                    status: status.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn lists_a_directory_from_the_whole_tree() {
+        let output =
+            "git\0README.md\0src/main.rs\0src/ui/app.rs\0gone.txt\0vendor/lib/\0\0gone.txt\0";
+        let files = parse_index(output).unwrap();
+        assert!(!files.contains("gone.txt"));
+
+        let root = children(&files, "").unwrap();
+        let expected = [("README.md", false), ("src", true), ("vendor", true)];
+        let expected: Vec<(String, bool)> =
+            expected.iter().map(|(n, d)| (n.to_string(), *d)).collect();
+        assert_eq!(root, expected);
+
+        let src = children(&files, "src").unwrap();
+        assert_eq!(
+            src,
+            vec![("main.rs".to_string(), false), ("ui".to_string(), true)]
+        );
+        // a nested repo's contents aren't in the index
+        assert_eq!(children(&files, "vendor/lib"), None);
+
+        assert_eq!(
+            parse_index("plain\0./a/b.txt\0"),
+            Some(BTreeSet::from(["a/b.txt".to_string()]))
+        );
+    }
+
+    #[test]
+    fn keeps_directories_with_nothing_indexed_inside() {
+        let output = "git\0a.rs\0new/b.rs\0vendor/lib/\0\0\0docs/\0new/\0vendor/lib/\0";
+        let files = parse_index(output).unwrap();
+        let root = children(&files, "").unwrap();
+        let names: Vec<&str> = root.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["a.rs", "docs", "new", "vendor"]);
+        assert!(root
+            .iter()
+            .all(|(name, is_dir)| *is_dir == (name != "a.rs")));
+        // the empty one is listed on the host, the untracked one out of the index
+        assert_eq!(children(&files, "docs"), None);
+        assert_eq!(
+            children(&files, "new"),
+            Some(vec![("b.rs".to_string(), false)])
+        );
+
+        let plain = parse_index("plain\0./a/b.txt\0\0./a/empty\0.\0").unwrap();
+        let expected = ["a/b.txt", "a/empty/"];
+        assert_eq!(
+            plain.iter().map(String::as_str).collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    #[test]
+    fn indexes_outside_a_repo_expire() {
+        let index = |expires| ProjectIndex {
+            files: Some(BTreeSet::new()),
+            stale: false,
+            expires,
+        };
+        assert!(index(None).current());
+        assert!(index(Some(Instant::now() + PLAIN_INDEX_TTL)).current());
+        assert!(!index(Some(Instant::now())).current());
+    }
+
+    #[test]
+    fn follows_status_changes_without_refetching() {
+        let index = RemoteFileIndex::new(true);
+        let files = parse_index("git\0a.rs\0b.rs\0\0").unwrap();
+        index.projects.lock().unwrap().insert(
+            "ssh://box/p".to_string(),
+            ProjectIndex {
+                files: Some(files),
+                stale: false,
+                expires: None,
+            },
+        );
+
+        let before = status(&[("new.txt", "?")]);
+        index.apply_status("ssh://box/p", None, &before);
+        let after = status(&[("b.rs", "D"), ("nested", "S")]);
+        index.apply_status("ssh://box/p", Some(&before), &after);
+
+        let projects = index.projects.lock().unwrap();
+        assert!(projects["ssh://box/p"].current());
+        let files = projects["ssh://box/p"].files.as_ref().unwrap();
+        let expected = ["a.rs", "nested/"];
+        assert_eq!(
+            files.iter().map(String::as_str).collect::<Vec<_>>(),
+            expected
+        );
+    }
+}
@@ -0,0 +1,414 @@
+// watches files on a remote host over one ssh process per host, since the command
+// channel is strictly request/response and notify only sees local filesystems
+use crate::remote::{self, SshTarget};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+#[cfg(windows)]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+/// Wait before reconnecting a watch whose ssh process went away; doubled for each exit
+/// that follows a reconnect straight away, up to `MAX_RECONNECT_DELAY`.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(300);
+
+/// A reconnected process that lasts this long counts as back up: only then are watchers
+/// told about edits they may have missed, and the backoff resets.
+const STABLE_AFTER: Duration = Duration::from_secs(10);
+
+/// Prints the index of each watched file (the positional parameters) that changes.
+/// inotifywait watches the parent dirs so editors that save by renaming over a file are
+/// still seen; hosts without it fall back to polling mtime and size (GNU, then BSD
+/// `stat` flags).
+const WATCH_SCRIPT: &str = r#"
+report() { p=$1; shift; i=0; for f; do [ "$f" = "$p" ] && printf '%s\n' "$i"; i=$((i+1)); done; }
+if command -v inotifywait >/dev/null 2>&1; then
+  for f; do dirname -- "$f"; done | sort -u |
+  inotifywait -m -q -e close_write,moved_to,moved_from,delete --format '%w%f' --fromfile - |
+  while IFS= read -r p; do report "$(printf '%s' "$p" | sed 's://*:/:g')" "$@"; done
+  exit
+fi
+sig() { stat -c '%Y %s' -- "$1" 2>/dev/null || stat -f '%m %z' -- "$1" 2>/dev/null || echo gone; }
+i=0; for f; do eval "last_$i=\$(sig \"\$f\")"; i=$((i+1)); done
+while sleep 2; do
+  i=0
+  for f; do
+    cur=$(sig "$f"); eval "prev=\$last_$i"
+    [ "$cur" = "$prev" ] || { printf '%s\n' "$i"; eval "last_$i=\$cur"; }
+    i=$((i+1))
+  done
+done
+"#;
+
+fn assign_paths(paths: &[String]) -> String {
+    let mut script = "set --".to_string();
+    for path in paths {
+        match path.strip_prefix("~/") {
+            Some(rest) => script.push_str(&format!(" \"$HOME\"/{}", remote::q(rest))),
+            None => script.push_str(&format!(" {}", remote::q(path))),
+        }
+    }
+    script.push('\n');
+    script
+}
+
+/// Starts the watcher process for a host's files.
+type Spawner = fn(&SshTarget, &[String]) -> Result<Child, String>;
+
+type OnChange = Arc<dyn Fn() + Send + Sync>;
+
+/// Every watched file on one host, served by a single watcher process.
+struct HostWatch {
+    target: SshTarget,
+    files: HashMap<u64, (String, OnChange)>,
+    next_id: u64,
+    /// Bumped whenever `files` changes, so the running process is replaced.
+    generation: u64,
+    child: Option<Child>,
+    /// Counts processes started, so a delayed check can tell it's still the same one.
+    spawned: u64,
+}
+
+type Hosts = HashMap<String, Arc<Mutex<HostWatch>>>;
+
+fn hosts() -> &'static Mutex<Hosts> {
+    static HOSTS: OnceLock<Mutex<Hosts>> = OnceLock::new();
+    HOSTS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+/// A watched file; dropping it stops watching, and the host's process with the last one.
+pub struct RemoteWatch {
+    host: Arc<Mutex<HostWatch>>,
+    id: u64,
+}
+
+impl Drop for RemoteWatch {
+    fn drop(&mut self) {
+        let mut host = lock(&self.host);
+        host.files.remove(&self.id);
+        host.generation += 1;
+        // the host's thread sees the process end and restarts it without this file
+        if let Some(child) = host.child.as_mut() {
+            let _ = child.kill();
+        }
+    }
+}
+
+fn ssh_command(target: &SshTarget) -> Result<Command, String> {
+    // a windows host's ssh server hands commands to cmd.exe; the script needs git's bash
+    let shell = if target.windows {
+        r#""C:\Program Files\Git\bin\bash.exe" -s"#
+    } else {
+        "sh -s"
+    };
+    let (program, args) = remote::shell_argv(target, false, shell)?;
+    let mut cmd = Command::new(program);
+    cmd.args(args);
+    for (name, value) in remote::ssh_env(target)? {
+        cmd.env(name, value);
+    }
+    Ok(cmd)
+}
+
+fn spawn_ssh(target: &SshTarget, paths: &[String]) -> Result<Child, String> {
+    spawn_script(ssh_command(target)?, paths)
+}
+
+/// Runs the watch script for `paths` under `cmd`, a shell reading its script from stdin.
+fn spawn_script(mut cmd: Command, paths: &[String]) -> Result<Child, String> {
+    cmd.stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    #[cfg(windows)]
+    cmd.creation_flags(CREATE_NO_WINDOW);
+    let program = cmd.get_program().to_string_lossy().to_string();
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", program, e))?;
+    // dropping stdin after the script hands the shell its EOF, so it runs what it read
+    let mut stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| format!("Failed to open {} stdin", program))?;
+    let script = assign_paths(paths) + WATCH_SCRIPT;
+    if let Err(e) = stdin.write_all(script.as_bytes()) {
+        let _ = child.kill();
+        let _ = child.wait();
+        return Err(format!("Failed to start remote watch: {}", e));
+    }
+    Ok(child)
+}
+
+/// Calls `on_change` whenever `target.path` changes on the host. Files on the same host
+/// share one ssh process, which reconnects if it drops; once a reconnect holds, every
+/// watcher hears `on_change` since edits may have been missed.
+pub fn watch(
+    target: SshTarget,
+    on_change: impl Fn() + Send + Sync + 'static,
+) -> Result<RemoteWatch, String> {
+    watch_with(target, Arc::new(on_change), spawn_ssh)
+}
+
+fn watch_with(
+    target: SshTarget,
+    on_change: OnChange,
+    spawn: Spawner,
+) -> Result<RemoteWatch, String> {
+    if let Some(watch) = join_host(&target, &on_change) {
+        return Ok(watch);
+    }
+
+    // the first file on a host connects up front, so a host that can't be reached
+    // fails the watch instead of retrying in the background
+    let paths = vec![target.path.clone()];
+    let mut first = spawn(&target, &paths)?;
+    let mut hosts = lock(hosts());
+    if hosts.contains_key(&target.authority) {
+        // another file on the host got there while this one connected
+        drop(hosts);
+        let _ = first.kill();
+        let _ = first.wait();
+        return join_host(&target, &on_change)
+            .ok_or_else(|| format!("Remote watch on {} stopped", target.host));
+    }
+    let authority = target.authority.clone();
+    let host = Arc::new(Mutex::new(HostWatch {
+        target,
+        files: HashMap::from([(0, (paths[0].clone(), on_change))]),
+        next_id: 1,
+        generation: 0,
+        child: None,
+        spawned: 0,
+    }));
+    hosts.insert(authority.clone(), host.clone());
+    let thread_host = host.clone();
+    std::thread::spawn(move || run_host(authority, thread_host, spawn, (first, paths, 0)));
+    Ok(RemoteWatch { host, id: 0 })
+}
+
+/// Adds the file to the host's running watcher, if there is one.
+fn join_host(target: &SshTarget, on_change: &OnChange) -> Option<RemoteWatch> {
+    let hosts = lock(hosts());
+    let host = hosts.get(&target.authority)?.clone();
+    let mut state = lock(&host);
+    let id = state.next_id;
+    state.next_id += 1;
+    state
+        .files
+        .insert(id, (target.path.clone(), on_change.clone()));
+    state.generation += 1;
+    if let Some(child) = state.child.as_mut() {
+        let _ = child.kill();
+    }
+    drop(state);
+    Some(RemoteWatch { host, id })
+}
+
+/// The host's files and their generation, or None once nothing is watched any more.
+fn snapshot(host: &Mutex<HostWatch>) -> Option<(SshTarget, Vec<String>, u64)> {
+    let host = lock(host);
+    let mut paths: Vec<String> = host.files.values().map(|(p, _)| p.clone()).collect();
+    paths.sort();
+    paths.dedup();
+    if paths.is_empty() {
+        return None;
+    }
+    Some((host.target.clone(), paths, host.generation))
+}
+
+/// Drops the host from the registry if nothing is watched on it, so the next watch
+/// starts a fresh one. False if a watch arrived in the meantime.
+fn retire(authority: &str, host: &Arc<Mutex<HostWatch>>) -> bool {
+    let mut hosts = lock(hosts());
+    if !lock(host).files.is_empty() {
+        return false;
+    }
+    if hosts.get(authority).is_some_and(|h| Arc::ptr_eq(h, host)) {
+        hosts.remove(authority);
+    }
+    true
+}
+
+fn backoff(failures: u32) -> Duration {
+    let factor = 1u32 << failures.saturating_sub(1).min(10);
+    (RECONNECT_DELAY * factor).min(MAX_RECONNECT_DELAY)
+}
+
+fn run_host(
+    authority: String,
+    host: Arc<Mutex<HostWatch>>,
+    spawn: Spawner,
+    first: (Child, Vec<String>, u64),
+) {
+    let mut next = Some(first);
+    let mut failures = 0u32;
+    let mut reconnecting = false;
+    loop {
+        let (child, paths, generation) = match next.take() {
+            Some(started) => started,
+            None => {
+                let Some((target, paths, generation)) = snapshot(&host) else {
+                    if retire(&authority, &host) {
+                        return;
+                    }
+                    continue;
+                };
+                match spawn(&target, &paths) {
+                    Ok(child) => (child, paths, generation),
+                    Err(_) => {
+                        failures += 1;
+                        std::thread::sleep(backoff(failures));
+                        continue;
+                    }
+                }
+            }
+        };
+
+        let stdout = {
+            let mut state = lock(&host);
+            let mut child = child;
+            let stdout = child.stdout.take();
+            // files changed while it started: it ends at once and is replaced
+            if state.generation != generation {
+                let _ = child.kill();
+            }
+            state.child = Some(child);
+            state.spawned += 1;
+            if reconnecting {
+                catch_up_when_stable(host.clone(), state.spawned);
+                reconnecting = false;
+            }
+            stdout
+        };
+
+        let started = Instant::now();
+        if let Some(stdout) = stdout {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                let Some(path) = line.trim().parse::<usize>().ok().and_then(|i| paths.get(i))
+                else {
+                    continue;
+                };
+                let callbacks: Vec<OnChange> = lock(&host)
+                    .files
+                    .values()
+                    .filter(|(p, _)| p == path)
+                    .map(|(_, on_change)| on_change.clone())
+                    .collect();
+                for on_change in callbacks {
+                    on_change();
+                }
+            }
+        }
+
+        // the process ended: reap it, then restart for the new set of files, or
+        // reconnect after a pause if it dropped on its own
+        let replaced = {
+            let mut state = lock(&host);
+            if let Some(mut old) = state.child.take() {
+                let _ = old.kill();
+                let _ = old.wait();
+            }
+            state.generation != generation
+        };
+        if replaced {
+            continue;
+        }
+        reconnecting = true;
+        failures = if started.elapsed() >= STABLE_AFTER {
+            0
+        } else {
+            failures + 1
+        };
+        std::thread::sleep(backoff(failures));
+    }
+}
+
+/// Tells every watcher on the host to re-read once the reconnected process has stayed
+/// up, rather than on every attempt of a connection that keeps dropping.
+fn catch_up_when_stable(host: Arc<Mutex<HostWatch>>, spawned: u64) {
+    std::thread::spawn(move || {
+        std::thread::sleep(STABLE_AFTER);
+        let callbacks: Vec<OnChange> = {
+            let mut state = lock(&host);
+            let alive = state.spawned == spawned
+                && state
+                    .child
+                    .as_mut()
+                    .is_some_and(|c| matches!(c.try_wait(), Ok(None)));
+            if !alive {
+                return;
+            }
+            state.files.values().map(|(_, f)| f.clone()).collect()
+        };
+        for on_change in callbacks {
+            on_change();
+        }
+    });
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+
+    fn spawn_local(_: &SshTarget, paths: &[String]) -> Result<Child, String> {
+        let mut shell = Command::new("sh");
+        shell.arg("-s");
+        spawn_script(shell, paths)
+    }
+
+    #[test]
+    fn files_on_one_host_share_a_watcher() {
+        let dir = std::env::temp_dir().join(format!("cc-watch-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let host = format!("watch-{}", uuid::Uuid::new_v4().simple());
+        let watch_file = |name: &str| {
+            let file = dir.join(name);
+            std::fs::write(&file, "one").unwrap();
+            let path = file.to_string_lossy().to_string();
+            let target = remote::make_target(None, host.clone(), None, None, path);
+            let (tx, rx) = channel();
+            let on_change: OnChange = Arc::new(move || {
+                let _ = tx.send(());
+            });
+            (watch_with(target, on_change, spawn_local).unwrap(), rx)
+        };
+
+        let (notes, notes_rx) = watch_file("notes.txt");
+        let (todo, todo_rx) = watch_file("todo.txt");
+        assert!(Arc::ptr_eq(&notes.host, &todo.host));
+        assert_eq!(lock(&notes.host).files.len(), 2);
+
+        // give the restarted process time to take its first stat
+        std::thread::sleep(Duration::from_millis(1200));
+        std::fs::write(dir.join("other.txt"), "ignored").unwrap();
+        std::fs::write(dir.join("todo.txt"), "two, longer").unwrap();
+        assert!(todo_rx.recv_timeout(Duration::from_secs(10)).is_ok());
+        assert!(notes_rx.try_recv().is_err());
+
+        drop(notes);
+        drop(todo);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn reconnects_back_off_while_they_keep_dropping() {
+        assert_eq!(backoff(0), RECONNECT_DELAY);
+        assert_eq!(backoff(1), RECONNECT_DELAY);
+        assert_eq!(backoff(3), RECONNECT_DELAY * 4);
+        assert_eq!(backoff(30), MAX_RECONNECT_DELAY);
+    }
+}
@@ -0,0 +1,272 @@
+// reads the user's OpenSSH client config so its host aliases can be offered as remotes.
+// ssh applies the config itself (ProxyJump, IdentityFile, ...) when given an alias as the
+// host, so this only needs enough of it to list and describe the aliases.
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Include nesting ssh itself allows before giving up.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SshHost {
+    pub alias: String,
+    pub host_name: Option<String>,
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    pub identity_file: Option<String>,
+    pub proxy_jump: Option<String>,
+}
+
+/// One `keyword args...` line, keyword lowercased.
+struct Directive {
+    keyword: String,
+    args: Vec<String>,
+}
+
+fn ssh_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".ssh"))
+}
+
+/// Every concrete alias in `~/.ssh/config`, with the settings ssh would use for it.
+pub fn known_hosts() -> Vec<SshHost> {
+    let Some(dir) = ssh_dir() else {
+        return Vec::new();
+    };
+    let Ok(text) = std::fs::read_to_string(dir.join("config")) else {
+        return Vec::new();
+    };
+    let mut directives = Vec::new();
+    read_directives(&text, &dir, 0, &mut directives);
+    resolve(&directives)
+}
+
+fn split_line(line: &str) -> Option<Directive> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    // "Keyword value", "Keyword=value" and "Keyword = value" are all valid
+    let split = line.find(|c: char| c.is_whitespace() || c == '=')?;
+    let keyword = line[..split].to_lowercase();
+    let rest = line[split..].trim_start();
+    let rest = rest.strip_prefix('=').unwrap_or(rest).trim();
+
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in rest.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    args.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        args.push(current);
+    }
+    Some(Directive { keyword, args })
+}
+
+/// Flattens `text` into directives, splicing `Include`d files in where they appear.
+fn read_directives(text: &str, ssh_dir: &Path, depth: usize, out: &mut Vec<Directive>) {
+    for directive in text.lines().filter_map(split_line) {
+        if directive.keyword != "include" {
+            out.push(directive);
+            continue;
+        }
+        if depth >= MAX_INCLUDE_DEPTH {
+            continue;
+        }
+        for pattern in &directive.args {
+            for path in expand_include(pattern, ssh_dir) {
+                if let Ok(included) = std::fs::read_to_string(&path) {
+                    read_directives(&included, ssh_dir, depth + 1, out);
+                }
+            }
+        }
+    }
+}
+
+/// Relative includes resolve against ~/.ssh; a `*` or `?` in the file name globs
+/// within its directory.
+fn expand_include(pattern: &str, ssh_dir: &Path) -> Vec<PathBuf> {
+    let path = match pattern.strip_prefix("~/") {
+        Some(rest) => match dirs::home_dir() {
+            Some(home) => home.join(rest),
+            None => return Vec::new(),
+        },
+        None if Path::new(pattern).is_absolute() => PathBuf::from(pattern),
+        None => ssh_dir.join(pattern),
+    };
+    let Some(name) = path.file_name().map(|n| n.to_string_lossy().to_string()) else {
+        return Vec::new();
+    };
+    if !name.contains(['*', '?']) {
+        return vec![path];
+    }
+    let Some(parent) = path.parent() else {
+        return Vec::new();
+    };
+    let Ok(entries) = std::fs::read_dir(parent) else {
+        return Vec::new();
+    };
+    let mut matches: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|e| glob_match(&name, &e.file_name().to_string_lossy()))
+        .map(|e| e.path())
+        .collect();
+    matches.sort();
+    matches
+}
+
+/// ssh's pattern syntax: `*` for any run of characters, `?` for exactly one.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// A `Host` line applies when any pattern matches and no negated one does.
+fn host_matches(patterns: &[String], alias: &str) -> bool {
+    let mut matched = false;
+    for pattern in patterns {
+        if let Some(negated) = pattern.strip_prefix('!') {
+            if glob_match(negated, alias) {
+                return false;
+            }
+        } else if glob_match(pattern, alias) {
+            matched = true;
+        }
+    }
+    matched
+}
+
+/// Settings per alias, first value wins as in ssh. Options before any `Host` line apply
+/// to every host; `Match` blocks are skipped since their conditions need a live connect.
+fn resolve(directives: &[Directive]) -> Vec<SshHost> {
+    let mut aliases: Vec<String> = Vec::new();
+    for d in directives.iter().filter(|d| d.keyword == "host") {
+        for pattern in &d.args {
+            let concrete = !pattern.starts_with('!') && !pattern.contains(['*', '?']);
+            if concrete && !aliases.contains(pattern) {
+                aliases.push(pattern.clone());
+            }
+        }
+    }
+
+    aliases
+        .into_iter()
+        .map(|alias| {
+            let mut host = SshHost {
+                alias: alias.clone(),
+                ..Default::default()
+            };
+            let mut applies = true;
+            for d in directives {
+                match d.keyword.as_str() {
+                    "host" => applies = host_matches(&d.args, &alias),
+                    "match" => applies = false,
+                    _ if !applies => {}
+                    keyword => apply(&mut host, keyword, &d.args),
+                }
+            }
+            host
+        })
+        .collect()
+}
+
+fn apply(host: &mut SshHost, keyword: &str, args: &[String]) {
+    let Some(value) = args.first().cloned() else {
+        return;
+    };
+    match keyword {
+        "hostname" if host.host_name.is_none() => host.host_name = Some(value),
+        "user" if host.user.is_none() => host.user = Some(value),
+        "port" if host.port.is_none() => host.port = value.parse().ok(),
+        "identityfile" if host.identity_file.is_none() => host.identity_file = Some(value),
+        // "none" is an explicit "no jump host", and still counts as the first value
+        "proxyjump" if host.proxy_jump.is_none() => host.proxy_jump = Some(value),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Vec<SshHost> {
+        let mut directives = Vec::new();
+        read_directives(text, Path::new("/nonexistent"), 0, &mut directives);
+        resolve(&directives)
+    }
+
+    #[test]
+    fn resolves_aliases_first_value_wins() {
+        let hosts = parse(
+            "# work boxes
+Host build build-2
+    HostName 10.0.0.5
+    User deploy
+    ProxyJump bastion
+Host bastion
+    HostName=bastion.example.com
+    Port = 2222
+Host *.internal !skip.internal
+    User internal
+Host *
+    User fallback
+    IdentityFile \"~/.ssh/id work\"
+Match host build
+    User ignored
+",
+        );
+        let aliases: Vec<&str> = hosts.iter().map(|h| h.alias.as_str()).collect();
+        assert_eq!(aliases, vec!["build", "build-2", "bastion"]);
+
+        assert_eq!(
+            hosts[0],
+            SshHost {
+                alias: "build".into(),
+                host_name: Some("10.0.0.5".into()),
+                user: Some("deploy".into()),
+                port: None,
+                identity_file: Some("~/.ssh/id work".into()),
+                proxy_jump: Some("bastion".into()),
+            }
+        );
+        assert_eq!(hosts[2].port, Some(2222));
+        assert_eq!(hosts[2].user.as_deref(), Some("fallback"));
+    }
+
+    #[test]
+    fn matches_ssh_host_patterns() {
+        let patterns = vec!["*.internal".to_string(), "!skip.internal".to_string()];
+        assert!(host_matches(&patterns, "db.internal"));
+        assert!(!host_matches(&patterns, "skip.internal"));
+        assert!(!host_matches(&patterns, "internal"));
+        assert!(glob_match("web-?", "web-1"));
+        assert!(!glob_match("web-?", "web-10"));
+    }
+}
@@ -0,0 +1,185 @@
+// binary-safe file copies to and from remote hosts over the command channel
+use crate::remote::{self, SshTarget};
+use serde::Serialize;
+
+/// Raw bytes per command. Each chunk travels as base64 on the channel's stdin, so this
+/// stays well clear of anything a shell might choke on while keeping round trips few.
+const CHUNK_BYTES: usize = 192 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferProgress {
+    pub transferred: u64,
+    pub total: u64,
+}
+
+/// CRC the way POSIX `cksum` computes it, so both ends can check a copy with tools every
+/// unix already has.
+fn cksum(data: &[u8]) -> u32 {
+    fn feed(crc: u32, byte: u8) -> u32 {
+        let mut crc = crc ^ ((byte as u32) << 24);
+        for _ in 0..8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ 0x04C1_1DB7
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    }
+    let mut crc = data.iter().fold(0u32, |crc, b| feed(crc, *b));
+    // cksum folds the length in after the data, low byte first
+    let mut len = data.len();
+    while len > 0 {
+        crc = feed(crc, (len & 0xff) as u8);
+        len >>= 8;
+    }
+    !crc
+}
+
+/// Runs `cksum` on the remote file: "<crc> <size>".
+fn remote_cksum(target: &SshTarget, path: &str) -> Result<(u32, u64), String> {
+    let output = remote::run_checked(target, None, &format!("cksum < {}", remote::q(path)))?;
+    let mut fields = output.split_whitespace();
+    let crc = fields.next().and_then(|f| f.parse().ok());
+    let size = fields.next().and_then(|f| f.parse().ok());
+    match (crc, size) {
+        (Some(crc), Some(size)) => Ok((crc, size)),
+        _ => Err(format!("Unexpected cksum output: {}", output.trim())),
+    }
+}
+
+/// Writes `data` to `path` on the remote host. Chunks land in a temp file next to the
+/// target, which only replaces it once its checksum matches.
+pub fn upload(
+    target: &SshTarget,
+    data: &[u8],
+    path: &str,
+    on_progress: &mut dyn FnMut(TransferProgress),
+) -> Result<(), String> {
+    let total = data.len() as u64;
+    let part = format!(
+        "{}.part-{}",
+        path,
+        &uuid::Uuid::new_v4().simple().to_string()[..8]
+    );
+    let cleanup = |target: &SshTarget| {
+        let _ = remote::run(target, None, &format!("rm -f -- {}", remote::q(&part)));
+    };
+
+    remote::run_checked(target, None, &format!(": > {}", remote::q(&part)))
+        .map_err(|e| format!("Failed to create {}: {}", path, e))?;
+    on_progress(TransferProgress {
+        transferred: 0,
+        total,
+    });
+    let mut sent = 0u64;
+    for chunk in data.chunks(CHUNK_BYTES) {
+        let command = format!(
+            "printf %s {} | base64 -d >> {}",
+            remote::q(&remote::base64_encode(chunk)),
+            remote::q(&part)
+        );
+        if let Err(e) = remote::run_checked(target, None, &command) {
+            cleanup(target);
+            return Err(format!("Failed to upload {}: {}", path, e));
+        }
+        sent += chunk.len() as u64;
+        on_progress(TransferProgress {
+            transferred: sent,
+            total,
+        });
+    }
+
+    let (crc, size) = match remote_cksum(target, &part) {
+        Ok(sum) => sum,
+        Err(e) => {
+            cleanup(target);
+            return Err(e);
+        }
+    };
+    if size != total || crc != cksum(data) {
+        cleanup(target);
+        return Err(format!(
+            "Upload of {} failed verification (sent {} bytes, remote has {})",
+            path, total, size
+        ));
+    }
+    remote::run_checked(
+        target,
+        None,
+        &format!("mv -f -- {} {}", remote::q(&part), remote::q(path)),
+    )
+    .map_err(|e| format!("Failed to move {} into place: {}", path, e))?;
+    Ok(())
+}
+
+/// Reads `path` off the remote host in chunks and checks the result against the remote
+/// checksum.
+pub fn download(
+    target: &SshTarget,
+    path: &str,
+    on_progress: &mut dyn FnMut(TransferProgress),
+) -> Result<Vec<u8>, String> {
+    let (crc, total) =
+        remote_cksum(target, path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    on_progress(TransferProgress {
+        transferred: 0,
+        total,
+    });
+
+    let mut data: Vec<u8> = Vec::with_capacity(total as usize);
+    let chunks = (total as usize).div_ceil(CHUNK_BYTES);
+    for index in 0..chunks {
+        let command = format!(
+            "dd if={} bs={} skip={} count=1 2>/dev/null | base64",
+            remote::q(path),
+            CHUNK_BYTES,
+            index
+        );
+        let encoded = remote::run_checked(target, None, &command)
+            .map_err(|e| format!("Failed to download {}: {}", path, e))?;
+        data.extend(remote::base64_decode(&encoded)?);
+        on_progress(TransferProgress {
+            transferred: data.len() as u64,
+            total,
+        });
+    }
+
+    if data.len() as u64 != total || cksum(&data) != crc {
+        return Err(format!(
+            "Download of {} failed verification (file changed while copying?)",
+            path
+        ));
+    }
+    Ok(data)
+}
+
+/// Per-user temp dir on the remote host for pasted screenshots. Anything in it older than
+/// a week is pruned each time it's looked up, mirroring the local screenshots dir.
+pub fn remote_screenshots_dir(target: &SshTarget) -> Result<String, String> {
+    let command = "d=\"${TMPDIR:-/tmp}/circuitclaude-screenshots-$(id -u)\" && \
+                   mkdir -p \"$d\" && chmod 700 \"$d\" && \
+                   find \"$d\" -type f -mtime +7 -exec rm -f {} + 2>/dev/null; \
+                   printf %s \"$d\"";
+    let dir = remote::run_checked(target, None, command)
+        .map_err(|e| format!("Failed to create remote screenshots dir: {}", e))?;
+    let dir = dir.trim();
+    if dir.is_empty() {
+        return Err("Failed to create remote screenshots dir".to_string());
+    }
+    Ok(dir.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_posix_cksum() {
+        // reference values from `printf ... | cksum`
+        assert_eq!(cksum(b""), 4294967295);
+        assert_eq!(cksum(b"a"), 1220704766);
+        assert_eq!(cksum(b"hello world\n"), 3733384285);
+    }
+}
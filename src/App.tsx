@@ -18,7 +18,7 @@ import { usePinnedFilesStore } from "./stores/pinnedFilesStore";
 import { applyThemeToDOM, applySyntaxThemeToDOM } from "./lib/themes";
 import { useHotkeys } from "./hooks/useHotkeys";
 import { useUpdateCheck } from "./hooks/useUpdateCheck";
-import { useGitPolling } from "./hooks/useGitPolling";
+import { useGitStatusWatch } from "./hooks/useGitStatusWatch";
 import { useSessionHibernation } from "./hooks/useSessionHibernation";
 import "./App.css";
 
@@ -28,7 +28,7 @@ function App() {
   const initializedRef = useRef(false);
   const { status: updateStatus, updateInfo, install: installUpdate, dismiss: dismissUpdate } = useUpdateCheck();
   useHotkeys();
-  useGitPolling();
+  useGitStatusWatch();
   useSessionHibernation();
 
   // Load settings and projects on startup
@@ -5,16 +5,26 @@ import { getNextProjectTheme } from "../lib/themes";
 import { SegmentedControl } from "./SegmentedControl";
 import {
   baseName,
+  containerUrl,
+  diagnoseRemoteHost,
   isRootPath,
   listRemoteDirs,
+  listSshHosts,
   parentDir,
   rememberRemote,
+  remoteAuthority,
+  remoteHostLabel,
+  resolveDevcontainer,
+  storeRemoteCredentials,
   remoteUrl,
+  splitRemotePath,
+  wslUrlFromUnc,
+  type HostDiagnosis,
   type RemoteListing,
 } from "../lib/remote";
-import type { Project, RemoteSpec } from "../types";
+import type { Project, RemoteSpec, SshHost } from "../types";
 
-type Mode = "local" | "remote";
+type Mode = "local" | "remote" | "container";
 
 interface AddProjectDialogProps {
   isOpen: boolean;
@@ -31,10 +41,17 @@ export function AddProjectDialog({ isOpen, onClose, onAdded }: AddProjectDialogP
   const [user, setUser] = useState("");
   const [port, setPort] = useState("");
   const [keyPath, setKeyPath] = useState("");
+  const [container, setContainer] = useState("");
+  const [containerUser, setContainerUser] = useState("");
+  const [containerPath, setContainerPath] = useState("");
   const [password, setPassword] = useState("");
+  const [passphrase, setPassphrase] = useState("");
+  const [saveToKeyring, setSaveToKeyring] = useState(false);
   const [name, setName] = useState("");
   const [nameEdited, setNameEdited] = useState(false);
   const [listing, setListing] = useState<RemoteListing | null>(null);
+  const [diagnosis, setDiagnosis] = useState<HostDiagnosis | null>(null);
+  const [sshHosts, setSshHosts] = useState<SshHost[]>([]);
   const [busy, setBusy] = useState(false);
   const [error, setError] = useState<string | null>(null);
   const hostRef = useRef<HTMLInputElement>(null);
@@ -43,10 +60,14 @@ export function AddProjectDialog({ isOpen, onClose, onAdded }: AddProjectDialogP
     if (!isOpen) return;
     setMode("local");
     setLocalPath("");
+    setContainerPath("");
     setPassword("");
+    setPassphrase("");
+    setSaveToKeyring(false);
     setName("");
     setNameEdited(false);
     setListing(null);
+    setDiagnosis(null);
     setError(null);
     setBusy(false);
   }, [isOpen]);
@@ -64,6 +85,11 @@ export function AddProjectDialog({ isOpen, onClose, onAdded }: AddProjectDialogP
     if (isOpen && mode === "remote") hostRef.current?.focus();
   }, [isOpen, mode]);
 
+  useEffect(() => {
+    if (!isOpen || mode !== "remote") return;
+    listSshHosts().then(setSshHosts).catch(() => setSshHosts([]));
+  }, [isOpen, mode]);
+
   if (!isOpen) return null;
 
   const spec: RemoteSpec = {
@@ -72,6 +98,7 @@ export function AddProjectDialog({ isOpen, onClose, onAdded }: AddProjectDialogP
     port: port.trim() ? Number(port.trim()) : undefined,
     keyPath: keyPath.trim() || undefined,
     password: password || undefined,
+    passphrase: passphrase || undefined,
   };
 
   function applyPath(path: string) {
@@ -81,8 +108,33 @@ export function AddProjectDialog({ isOpen, onClose, onAdded }: AddProjectDialogP
   async function pickLocalFolder() {
     const selected = await open({ directory: true, multiple: false, title: "Select project folder" });
     if (typeof selected !== "string") return;
-    setLocalPath(selected);
-    applyPath(selected.replace(/\\/g, "/"));
+    // a folder inside a wsl distro runs through wsl.exe rather than over the network share
+    const path = wslUrlFromUnc(selected) ?? selected;
+    setLocalPath(path);
+    applyPath(path.replace(/\\/g, "/"));
+  }
+
+  /** Fills the container fields from the running dev container of a local folder. */
+  async function pickDevcontainer() {
+    const selected = await open({ directory: true, multiple: false, title: "Select dev container folder" });
+    if (typeof selected !== "string") return;
+    setBusy(true);
+    setError(null);
+    try {
+      const url = await resolveDevcontainer(selected);
+      const parsed = splitRemotePath(url);
+      if (!parsed) return;
+      // authority is "docker:user@name" or "docker:name"
+      const at = parsed.authority.indexOf("@");
+      setContainer(remoteHostLabel(url) ?? "");
+      setContainerUser(at === -1 ? "" : parsed.authority.slice("docker:".length, at));
+      setContainerPath(parsed.path);
+      applyPath(parsed.path);
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    } finally {
+      setBusy(false);
+    }
   }
 
   async function pickKeyFile() {
@@ -105,33 +157,55 @@ export function AddProjectDialog({ isOpen, onClose, onAdded }: AddProjectDialogP
       const result = await listRemoteDirs(spec, path);
       setListing(result);
       applyPath(result.path);
+      // check the host once per connect, not on every folder change
+      if (path === undefined) {
+        setDiagnosis(null);
+        diagnoseRemoteHost(spec).then(setDiagnosis).catch(() => setDiagnosis(null));
+      }
     } catch (e) {
       setListing(null);
+      setDiagnosis(null);
       setError(e instanceof Error ? e.message : String(e));
     } finally {
       setBusy(false);
     }
   }
 
-  const targetPath = mode === "local" ? localPath : listing?.path ?? "";
+  const targetPath =
+    mode === "local" ? localPath : mode === "container" ? containerPath.trim() : listing?.path ?? "";
   // say why the button is dead instead of leaving it mysteriously grey
+  const missingPath =
+    mode === "local" ? "pick a folder" : mode === "container" ? "path required" : ":connect to pick a folder";
   const blockedReason = busy
     ? null
-    : !targetPath
-      ? mode === "local"
-        ? "pick a folder"
-        : ":connect to pick a folder"
-      : !name.trim()
-        ? "name required"
-        : null;
+    : mode === "container" && !container.trim()
+      ? "container required"
+      : !targetPath
+        ? missingPath
+        : !name.trim()
+          ? "name required"
+          : null;
   const canAdd = !blockedReason && !busy;
 
   async function handleAdd() {
     if (!canAdd) return;
     const theme = getNextProjectTheme(useProjectStore.getState().projects);
     try {
-      const path = mode === "remote" ? remoteUrl(spec, targetPath) : targetPath;
-      if (mode === "remote") await rememberRemote(spec);
+      const path =
+        mode === "remote"
+          ? remoteUrl(spec, targetPath)
+          : mode === "container"
+            ? containerUrl(container.trim(), containerUser.trim() || undefined, targetPath)
+            : targetPath;
+      if (mode === "remote") {
+        await rememberRemote(spec);
+        if (saveToKeyring && (spec.password || spec.passphrase)) {
+          await storeRemoteCredentials(remoteAuthority(spec), {
+            password: spec.password,
+            passphrase: spec.passphrase,
+          });
+        }
+      }
       const project: Project = { name: name.trim(), path, theme };
       await addProject(project);
       onClose();
@@ -151,6 +225,7 @@ export function AddProjectDialog({ isOpen, onClose, onAdded }: AddProjectDialogP
             options={[
               { label: "local", value: "local" },
               { label: "remote", value: "remote" },
+              { label: "container", value: "container" },
             ]}
             onChange={(next) => { setMode(next); setError(null); }}
           />
@@ -167,6 +242,55 @@ export function AddProjectDialog({ isOpen, onClose, onAdded }: AddProjectDialogP
               </span>
               <span className="add-project-action">:browse</span>
             </button>
+          ) : mode === "container" ? (
+            <>
+              <label className="add-project-row">
+                <span className="add-project-marker">&gt;</span>
+                <span className="add-project-label">container</span>
+                <input
+                  className="add-project-input"
+                  value={container}
+                  placeholder="name or id of a running container"
+                  spellCheck={false}
+                  autoFocus
+                  onChange={(e) => setContainer(e.target.value)}
+                />
+              </label>
+              <label className="add-project-row">
+                <span className="add-project-marker">&gt;</span>
+                <span className="add-project-label">user</span>
+                <input
+                  className="add-project-input"
+                  value={containerUser}
+                  placeholder="defaults to the image's user"
+                  spellCheck={false}
+                  onChange={(e) => setContainerUser(e.target.value)}
+                />
+              </label>
+              <label className="add-project-row">
+                <span className="add-project-marker">&gt;</span>
+                <span className="add-project-label">path</span>
+                <input
+                  className="add-project-input"
+                  value={containerPath}
+                  placeholder="/workspaces/app"
+                  spellCheck={false}
+                  onChange={(e) => { setContainerPath(e.target.value); applyPath(e.target.value); }}
+                  onKeyDown={(e) => { if (e.key === "Enter") handleAdd(); }}
+                />
+              </label>
+              <div className="add-project-row add-project-row--static">
+                <span className="add-project-marker">{" "}</span>
+                <button
+                  className="add-project-action"
+                  disabled={busy}
+                  onClick={pickDevcontainer}
+                  title="Pick a local folder whose dev container is running"
+                >
+                  {busy ? ":resolving..." : ":from dev container"}
+                </button>
+              </div>
+            </>
           ) : (
             <>
               <label className="add-project-row">
@@ -176,11 +300,22 @@ export function AddProjectDialog({ isOpen, onClose, onAdded }: AddProjectDialogP
                   ref={hostRef}
                   className="add-project-input"
                   value={host}
-                  placeholder="10.0.0.5 or box.local"
+                  placeholder="10.0.0.5, box.local or an ssh config alias"
                   spellCheck={false}
+                  list="add-project-ssh-hosts"
                   onChange={(e) => setHost(e.target.value)}
                   onKeyDown={(e) => { if (e.key === "Enter") browse(); }}
                 />
+                <datalist id="add-project-ssh-hosts">
+                  {sshHosts.map((h) => (
+                    <option key={h.alias} value={h.alias}>
+                      {[
+                        h.user ? `${h.user}@${h.hostName ?? h.alias}` : h.hostName,
+                        h.proxyJump && h.proxyJump !== "none" ? `via ${h.proxyJump}` : null,
+                      ].filter(Boolean).join(" ")}
+                    </option>
+                  ))}
+                </datalist>
               </label>
               <label className="add-project-row">
                 <span className="add-project-marker">&gt;</span>
@@ -230,12 +365,39 @@ export function AddProjectDialog({ isOpen, onClose, onAdded }: AddProjectDialogP
                   className="add-project-input"
                   type="password"
                   value={password}
-                  placeholder="kept until app closes"
+                  placeholder={saveToKeyring ? "saved to keyring" : "kept until app closes"}
                   autoComplete="new-password"
                   onChange={(e) => setPassword(e.target.value)}
                   onKeyDown={(e) => { if (e.key === "Enter") browse(); }}
                 />
               </label>
+              {keyPath.trim() && (
+                <label className="add-project-row">
+                  <span className="add-project-marker">&gt;</span>
+                  <span className="add-project-label">passphrase</span>
+                  <input
+                    className="add-project-input"
+                    type="password"
+                    value={passphrase}
+                    placeholder="if the key is encrypted"
+                    autoComplete="new-password"
+                    onChange={(e) => setPassphrase(e.target.value)}
+                    onKeyDown={(e) => { if (e.key === "Enter") browse(); }}
+                  />
+                </label>
+              )}
+              {(password || passphrase) && (
+                <div className="add-project-row add-project-row--static">
+                  <span className="add-project-marker">{" "}</span>
+                  <button
+                    className="add-project-action"
+                    onClick={() => setSaveToKeyring((v) => !v)}
+                    title="Store in the OS keyring so it survives restarts"
+                  >
+                    {saveToKeyring ? "[x]" : "[ ]"} save to keyring
+                  </button>
+                </div>
+              )}
 
               <div className="add-project-row add-project-row--static">
                 <span className="add-project-marker">{" "}</span>
@@ -250,6 +412,28 @@ export function AddProjectDialog({ isOpen, onClose, onAdded }: AddProjectDialogP
                 )}
               </div>
 
+              {listing && diagnosis && (
+                <div
+                  className="add-project-diagnosis"
+                  title={diagnosis.tools
+                    .map((t) => `${t.name}: ${t.path ? t.version ?? t.path : "missing"}`)
+                    .join("\n")}
+                >
+                  <div className="add-project-diagnosis-host">
+                    {[diagnosis.os, diagnosis.shell].filter(Boolean).join(" · ")}
+                  </div>
+                  {diagnosis.degraded.length === 0 ? (
+                    <div className="add-project-diagnosis-ok">all tools found</div>
+                  ) : (
+                    diagnosis.degraded.map((line) => (
+                      <div key={line} className="add-project-diagnosis-warning">
+                        ! {line}
+                      </div>
+                    ))
+                  )}
+                </div>
+              )}
+
               {listing && (
                 <div className="add-project-browser">
                   {!isRootPath(listing.path) && (
@@ -297,7 +481,7 @@ export function AddProjectDialog({ isOpen, onClose, onAdded }: AddProjectDialogP
         <div className="add-project-footer">
           {blockedReason && <span className="add-project-hint">{blockedReason}</span>}
           <button className="add-project-submit" disabled={!canAdd} onClick={handleAdd}>
-            + add{mode === "remote" ? " remote" : ""} project
+            + add{mode === "local" ? "" : ` ${mode}`} project
           </button>
         </div>
       </div>
@@ -22,11 +22,16 @@ export function CommitDialog({ isOpen, onClose, projectPath }: CommitDialogProps
     diffStats,
     diffStatsLoading,
     commitError,
+    commitHook,
+    commitOutput,
+    noVerify,
+    setNoVerify,
     generatingMessage,
     generateCommitMessage,
   } = useGitStore();
 
   const textareaRef = useRef<HTMLTextAreaElement>(null);
+  const outputRef = useRef<HTMLPreElement>(null);
 
   useEffect(() => {
     if (isOpen && textareaRef.current) {
@@ -45,6 +50,13 @@ export function CommitDialog({ isOpen, onClose, projectPath }: CommitDialogProps
     return () => window.removeEventListener("keydown", handleKey);
   }, [isOpen, onClose]);
 
+  // keep the newest hook output in view
+  useEffect(() => {
+    if (outputRef.current) {
+      outputRef.current.scrollTop = outputRef.current.scrollHeight;
+    }
+  }, [commitOutput]);
+
   if (!isOpen) return null;
 
   const status = statuses[projectPath];
@@ -124,6 +136,16 @@ export function CommitDialog({ isOpen, onClose, projectPath }: CommitDialogProps
             }}
             disabled={busy || generatingMessage}
           />
+          {(commitHook || commitOutput.length > 0) && (
+            <div className="commit-dialog-hook">
+              {commitHook && (
+                <div className={`commit-dialog-hook-name${committing ? " commit-dialog-generating" : ""}`}>
+                  {committing ? `running ${commitHook}...` : commitHook}
+                </div>
+              )}
+              <pre ref={outputRef} className="commit-dialog-hook-output">{commitOutput.join("\n")}</pre>
+            </div>
+          )}
           {commitError && (
             <div className="commit-dialog-error">{commitError}</div>
           )}
@@ -142,6 +164,14 @@ export function CommitDialog({ isOpen, onClose, projectPath }: CommitDialogProps
             >
               {pushing ? ":pushing..." : ":commit & push"}
             </button>
+            <button
+              className={`commit-dialog-no-verify${noVerify ? " commit-dialog-no-verify--on" : ""}`}
+              disabled={busy}
+              onClick={() => setNoVerify(!noVerify)}
+              title="skip pre-commit and commit-msg hooks (--no-verify)"
+            >
+              {noVerify ? "[x] skip hooks" : "[ ] skip hooks"}
+            </button>
           </div>
         </div>
       </div>
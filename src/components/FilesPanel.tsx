@@ -1,6 +1,8 @@
 import { useSessionStore } from "../stores/sessionStore";
 import { useFileTreeStore } from "../stores/fileTreeStore";
 import { FileTreeView } from "./FileTreeView";
+import { refreshRemoteFileIndex } from "../lib/files";
+import { isRemotePath } from "../lib/remote";
 
 export function FilesPanel() {
   const activeProjectPath = useSessionStore((s) => s.activeProjectPath);
@@ -8,7 +10,10 @@ export function FilesPanel() {
 
   if (!activeProjectPath) return null;
 
-  function handleRefresh() {
+  async function handleRefresh() {
+    if (isRemotePath(activeProjectPath!)) {
+      await refreshRemoteFileIndex(activeProjectPath!).catch(() => {});
+    }
     clearProject();
     fetchDirectory(activeProjectPath!);
   }
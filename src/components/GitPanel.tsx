@@ -383,6 +383,10 @@ function ActionBar({ projectPath }: { projectPath: string }) {
     statuses,
     revertFiles,
     openCommitDialog,
+    undoHistory,
+    undoing,
+    fetchUndoHistory,
+    undoLast,
   } = useGitStore();
   const [revertConfirming, setRevertConfirming] = useState(false);
   const revertTimerRef = useRef<ReturnType<typeof setTimeout> | null>(null);
@@ -393,6 +397,12 @@ function ActionBar({ projectPath }: { projectPath: string }) {
     };
   }, []);
 
+  useEffect(() => {
+    fetchUndoHistory(projectPath);
+  }, [projectPath, fetchUndoHistory]);
+
+  const lastUndo = undoHistory[projectPath]?.[0];
+
   const selCount = Object.keys(selectedFiles).length;
   const canOpenCommit = selCount > 0 && !committing;
   const canRevert = selCount > 0 && !reverting;
@@ -443,6 +453,16 @@ function ActionBar({ projectPath }: { projectPath: string }) {
             :revert{selCount > 0 ? ` [${selCount}]` : ""}
           </button>
         )}
+        {lastUndo && (
+          <button
+            className="git-action-btn"
+            disabled={undoing || reverting}
+            onClick={() => undoLast(projectPath).catch(() => {})}
+            title={`undo: ${lastUndo.summary}`}
+          >
+            {undoing ? ":undoing..." : `:undo [${undoHistory[projectPath].length}]`}
+          </button>
+        )}
       </div>
     </div>
   );
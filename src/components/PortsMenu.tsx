@@ -0,0 +1,125 @@
+import { useEffect, useRef, useState } from "react";
+import { listen } from "@tauri-apps/api/event";
+import {
+  closePortForward,
+  detectRemotePorts,
+  forwardKey,
+  listPortForwards,
+  loadRemotes,
+  openPortForward,
+  splitRemotePath,
+} from "../lib/remote";
+import type { ForwardStatus, ListeningPort, PortForward } from "../types";
+
+/** How often detected ports are re-listed while the menu is open. */
+const DETECT_INTERVAL_MS = 5000;
+
+function describe(forward: PortForward): string {
+  return forward.direction === "local"
+    ? `localhost:${forward.localPort} -> ${forward.remoteHost ?? "remote"}:${forward.remotePort}`
+    : `remote:${forward.remotePort} -> localhost:${forward.localPort}`;
+}
+
+export function PortsMenu({ projectPath }: { projectPath: string }) {
+  const [open, setOpen] = useState(false);
+  const [statuses, setStatuses] = useState<Record<string, ForwardStatus>>({});
+  const [declared, setDeclared] = useState<PortForward[]>([]);
+  const [detected, setDetected] = useState<ListeningPort[]>([]);
+  const [detectError, setDetectError] = useState<string | null>(null);
+  const ref = useRef<HTMLDivElement>(null);
+
+  useEffect(() => {
+    setStatuses({});
+    listPortForwards(projectPath)
+      .then((list) => setStatuses(Object.fromEntries(list.map((s) => [forwardKey(s.forward), s]))))
+      .catch(() => {});
+    const unlisten = listen<ForwardStatus>("port-forward-changed", (event) => {
+      if (event.payload.projectPath !== projectPath) return;
+      setStatuses((prev) => ({ ...prev, [forwardKey(event.payload.forward)]: event.payload }));
+    });
+    return () => { unlisten.then((fn) => fn()); };
+  }, [projectPath]);
+
+  useEffect(() => {
+    if (!open) return;
+    const authority = splitRemotePath(projectPath)?.authority;
+    loadRemotes()
+      .then((remotes) => setDeclared(remotes.find((r) => r.authority === authority)?.forwards ?? []))
+      .catch(() => setDeclared([]));
+    const detect = () =>
+      detectRemotePorts(projectPath)
+        .then((ports) => { setDetected(ports); setDetectError(null); })
+        .catch((err) => setDetectError(String(err)));
+    detect();
+    const timer = setInterval(detect, DETECT_INTERVAL_MS);
+    const handleClick = (e: MouseEvent) => {
+      if (ref.current && !ref.current.contains(e.target as Node)) setOpen(false);
+    };
+    document.addEventListener("mousedown", handleClick);
+    return () => {
+      clearInterval(timer);
+      document.removeEventListener("mousedown", handleClick);
+    };
+  }, [open, projectPath]);
+
+  const toggle = (forward: PortForward) => {
+    const state = statuses[forwardKey(forward)]?.state;
+    const action = state === "open" || state === "opening" ? closePortForward : openPortForward;
+    action(projectPath, forward).catch((err) =>
+      setStatuses((prev) => ({
+        ...prev,
+        [forwardKey(forward)]: { projectPath, forward, state: "failed", error: String(err) },
+      }))
+    );
+  };
+
+  const active = Object.values(statuses).filter((s) => s.state === "open").length;
+  // detected ports forward to the same port number locally
+  const detectedForwards: Array<{ forward: PortForward; label: string }> = detected.map((p) => ({
+    forward: { direction: "local", localPort: p.port, remotePort: p.port, remoteHost: null },
+    label: `${p.port}${p.process ? ` ${p.process}` : ""}`,
+  }));
+  const declaredKeys = new Set(declared.map(forwardKey));
+  const rows = [
+    ...declared.map((forward) => ({ forward, label: describe(forward) })),
+    ...detectedForwards.filter((d) => !declaredKeys.has(forwardKey(d.forward))),
+  ];
+
+  return (
+    <div className="ports-menu" ref={ref}>
+      <button
+        className={`project-header-text-btn${open ? " active" : ""}`}
+        onClick={() => setOpen((v) => !v)}
+        title="Forward ports from the remote host"
+      >
+        :ports{active > 0 && ` [${active}]`}
+      </button>
+      {open && (
+        <div className="ports-menu-dropdown">
+          {rows.length === 0 && (
+            <div className="ports-menu-empty">
+              {detectError ?? "no listening ports in this project"}
+            </div>
+          )}
+          {rows.map(({ forward, label }) => {
+            const status = statuses[forwardKey(forward)];
+            const state = status?.state ?? "closed";
+            return (
+              <button
+                key={forwardKey(forward)}
+                className={`ports-menu-row ports-menu-row--${state}`}
+                onClick={() => toggle(forward)}
+                title={status?.error ?? describe(forward)}
+              >
+                <span className="ports-menu-mark">
+                  {state === "open" ? "[x]" : state === "opening" ? "[~]" : state === "failed" ? "[!]" : "[ ]"}
+                </span>
+                <span className="ports-menu-label">{label}</span>
+              </button>
+            );
+          })}
+        </div>
+      )}
+    </div>
+  );
+}
@@ -3,9 +3,10 @@ import { useProjectStore } from "../stores/projectStore";
 import { useSettingsStore } from "../stores/settingsStore";
 import { useGitStore } from "../stores/gitStore";
 import { WindowControls } from "./WindowControls";
+import { PortsMenu } from "./PortsMenu";
 import { readClaudeMd, readAgentsMd } from "../lib/config";
 import { openFileTab } from "../lib/sessions";
-import { isRemotePath, remoteHostLabel } from "../lib/remote";
+import { isRemotePath, isSshPath, remoteHostLabel } from "../lib/remote";
 import type { RightPanelTab } from "../types";
 
 const PANEL_TABS: Array<{ label: string; value: RightPanelTab }> = [
@@ -53,6 +54,7 @@ export function ProjectHeader() {
         >
           :agents.md
         </button>
+        {isSshPath(activeProjectPath) && <PortsMenu projectPath={activeProjectPath} />}
       </div>
       <div className="project-header-actions">
         <div className="panel-tabs">
@@ -1,12 +1,14 @@
 import { useEffect, useRef, useState } from "react";
-import { spawnNewSession } from "../lib/sessions";
+import { attachDetachedSession, spawnNewSession } from "../lib/sessions";
 import { useSettingsStore } from "../stores/settingsStore";
 import { useProjectStore } from "../stores/projectStore";
 import { useSessionStore } from "../stores/sessionStore";
 import { AddProjectDialog } from "./AddProjectDialog";
-import { getProjectSessionTypes } from "../lib/sessionTypes";
+import { getProjectSessionTypes, getSessionDisplayName } from "../lib/sessionTypes";
 import { THEMES } from "../lib/themes";
-import { displayPath, isRemotePath, remoteHostLabel } from "../lib/remote";
+import { displayPath, isRemotePath, killDetachedSession, listDetachedSessions, remoteHostLabel } from "../lib/remote";
+import { formatAge } from "../lib/time";
+import type { DetachedSession } from "../types";
 
 export function SessionLauncher() {
   const activeProjectPath = useSessionStore((s) => s.activeProjectPath);
@@ -14,6 +16,8 @@ export function SessionLauncher() {
   const projects = useProjectStore((s) => s.projects);
   const removeProject = useProjectStore((s) => s.removeProject);
   const togglePinned = useProjectStore((s) => s.togglePinned);
+  const togglePersistentSessions = useProjectStore((s) => s.togglePersistentSessions);
+  const sessions = useSessionStore((s) => s.sessions);
   const editableSessionTypes = useSettingsStore((s) => s.settings.sessionTypes);
   const defaultSessionType = useSettingsStore((s) => s.settings.defaultSessionType);
 
@@ -22,6 +26,7 @@ export function SessionLauncher() {
   const [confirmingDelete, setConfirmingDelete] = useState<string | null>(null);
   const [query, setQuery] = useState("");
   const [highlight, setHighlight] = useState(0);
+  const [detached, setDetached] = useState<DetachedSession[]>([]);
   const inputRef = useRef<HTMLInputElement>(null);
   const listRef = useRef<HTMLDivElement>(null);
   // only arrow keys should scroll, or hovering a clipped row scrolls it under the cursor and loops
@@ -30,6 +35,10 @@ export function SessionLauncher() {
   const project = projects.find((p) => p.path === activeProjectPath) ?? null;
   const sessionTypes = getProjectSessionTypes(project?.path ?? null, editableSessionTypes);
 
+  const isRemote = project !== null && isRemotePath(project.path);
+  const openDetached = new Set(sessions.map((s) => s.detachedName).filter(Boolean));
+  const leftRunning = detached.filter((d) => !openDetached.has(d.name));
+
   const needle = query.trim().toLowerCase();
   // pinned projects are the ones you reach for, so they lead the list
   const ordered = [...projects].sort((a, b) => Number(!!b.pinned) - Number(!!a.pinned));
@@ -37,6 +46,24 @@ export function SessionLauncher() {
     ? ordered.filter((p) => p.name.toLowerCase().includes(needle) || p.path.toLowerCase().includes(needle))
     : ordered;
 
+  // sessions left running on the host, e.g. from before a disconnect or on another machine
+  useEffect(() => {
+    setDetached([]);
+    if (!isRemote || !project?.persistentSessions) return;
+    let cancelled = false;
+    listDetachedSessions(project.path)
+      .then((list) => { if (!cancelled) setDetached(list); })
+      .catch(() => {});
+    return () => { cancelled = true; };
+  }, [isRemote, project?.path, project?.persistentSessions]);
+
+  function handleKillDetached(d: DetachedSession) {
+    if (!project) return;
+    killDetachedSession(project.path, d.name)
+      .then(() => setDetached((list) => list.filter((x) => x.name !== d.name)))
+      .catch((err) => console.error("Failed to end detached session:", err));
+  }
+
   useEffect(() => {
     if (!projectOpen) return;
     function closeAll() {
@@ -227,6 +254,43 @@ export function SessionLauncher() {
             ))}
           </div>
         )}
+
+        {project && isRemote && (
+          <div className="session-launcher-list">
+            <div className="session-launcher-list-label">on {remoteHostLabel(project.path)}</div>
+            <button
+              className="session-launcher-entry"
+              title="Run new sessions inside tmux (or dtach) on the host so they keep going when the connection drops"
+              onClick={() => togglePersistentSessions(project.path)}
+            >
+              <span className="session-launcher-entry-prefix">{project.persistentSessions ? "[x]" : "[ ]"}</span>
+              <span className="session-launcher-entry-name">keep sessions running on the host</span>
+            </button>
+            {leftRunning.map((d) => (
+              <div key={d.name} className="session-launcher-detached">
+                <button
+                  className="session-launcher-entry"
+                  title={`${d.backend} session ${d.name}`}
+                  onClick={() => attachDetachedSession(project.path, d)}
+                >
+                  <span className="session-launcher-entry-prefix">&amp;</span>
+                  <span className="session-launcher-entry-name">
+                    {getSessionDisplayName(d.sessionType)}
+                    {d.createdAtMs !== null && ` ${formatAge(d.createdAtMs)}`}
+                    {d.attached && " (attached elsewhere)"}
+                  </span>
+                </button>
+                <button
+                  className="session-launcher-detached-kill"
+                  title="End this session on the host"
+                  onClick={() => handleKillDetached(d)}
+                >
+                  x
+                </button>
+              </div>
+            ))}
+          </div>
+        )}
       </div>
       <AddProjectDialog isOpen={addOpen} onClose={() => setAddOpen(false)} />
     </div>
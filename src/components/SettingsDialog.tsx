@@ -1,11 +1,12 @@
 import { useState, useRef, useEffect, useCallback } from "react";
 import { getVersion } from "@tauri-apps/api/app";
 import { useSettingsStore } from "../stores/settingsStore";
-import { DEFAULT_SETTINGS, ThemeName, SyntaxThemeName, type ResumeStrategy, type VoiceEngine, type SessionTypeConfig, type Settings } from "../types";
+import { DEFAULT_SETTINGS, ThemeName, SyntaxThemeName, type ResumeStrategy, type VoiceEngine, type SessionTypeConfig, type Settings, type CredentialsState, type RemoteConfig } from "../types";
 import { THEME_OPTIONS, SYNTAX_THEME_OPTIONS } from "../lib/themes";
 import { whisperGetAvailableModels, whisperDownloadModel, type ModelInfo, type DownloadProgress } from "../lib/whisper";
 import { checkForUpdate, downloadAndInstallUpdate } from "../lib/updater";
 import { Channel } from "@tauri-apps/api/core";
+import { forgetRemoteCredentials, getRemoteCredentialsState, loadRemotes, saveRemotes, unlockRemoteCredentials } from "../lib/remote";
 
 export function GearIcon() {
   return (
@@ -610,6 +611,37 @@ function SettingsMainPage({
         )}
       </div>
 
+      <div className="settings-section">
+        <div className="settings-section-title">~git</div>
+        <div className="settings-row">
+          <div className="settings-row-label">
+            <span className="settings-row-name">sign commits</span>
+          </div>
+          <button
+            className={`settings-toggle ${settings.signCommits ? "settings-toggle--on" : ""}`}
+            onClick={() => update({ signCommits: !settings.signCommits })}
+          >
+            {settings.signCommits ? "[on]" : "[off]"}
+          </button>
+        </div>
+        {settings.signCommits && (
+          <div className="settings-row">
+            <div className="settings-row-label">
+              <span className="settings-row-name">signing key</span>
+            </div>
+            <input
+              className="settings-input"
+              value={settings.signingKey ?? ""}
+              onChange={(e) => update({ signingKey: e.target.value || null })}
+              placeholder="git default"
+            />
+          </div>
+        )}
+      </div>
+
+      <RemoteCredentialsSection settings={settings} update={update} />
+      <RemoteChannelsSection />
+
       <div className="settings-section">
         <div className="settings-section-title">~sound</div>
         <div className="settings-row">
@@ -683,6 +715,150 @@ function SettingsMainPage({
   );
 }
 
+/* ------------------------------------------------------------------ */
+/*  Remote Credentials                                                */
+/* ------------------------------------------------------------------ */
+/** Keyring-backed remote secrets; locked (never read) until unlocked here or at launch. */
+function RemoteCredentialsSection({
+  settings,
+  update,
+}: {
+  settings: Settings;
+  update: (partial: Partial<Settings>) => Promise<void>;
+}) {
+  const [state, setState] = useState<CredentialsState | null>(null);
+  const [status, setStatus] = useState<string | null>(null);
+
+  useEffect(() => {
+    getRemoteCredentialsState().then(setState).catch(() => {});
+  }, []);
+
+  const unlock = async () => {
+    setStatus("unlocking...");
+    try {
+      setState(await unlockRemoteCredentials());
+      setStatus(null);
+    } catch (e) {
+      setStatus(String(e));
+    }
+  };
+
+  const forget = async (authority: string) => {
+    try {
+      await forgetRemoteCredentials(authority);
+      setState(await getRemoteCredentialsState());
+    } catch (e) {
+      setStatus(String(e));
+    }
+  };
+
+  return (
+    <div className="settings-section">
+      <div className="settings-section-title">~remote</div>
+      <div className="settings-row">
+        <div className="settings-row-label">
+          <span className="settings-row-name">unlock keyring on launch</span>
+        </div>
+        <button
+          className={`settings-toggle ${settings.unlockCredentialsOnStart ? "settings-toggle--on" : ""}`}
+          onClick={() => update({ unlockCredentialsOnStart: !settings.unlockCredentialsOnStart })}
+        >
+          {settings.unlockCredentialsOnStart ? "[on]" : "[off]"}
+        </button>
+      </div>
+      <div className="settings-row">
+        <div className="settings-row-label">
+          <span className="settings-row-name">index remote file trees</span>
+        </div>
+        <button
+          className={`settings-toggle ${settings.remoteFileIndex ? "settings-toggle--on" : ""}`}
+          onClick={() => update({ remoteFileIndex: !settings.remoteFileIndex })}
+        >
+          {settings.remoteFileIndex ? "[on]" : "[off]"}
+        </button>
+      </div>
+      <div className="settings-row">
+        <div className="settings-row-label">
+          <span className="settings-row-name">
+            {status ?? (state?.unlocked ? "saved credentials unlocked" : "saved credentials locked")}
+          </span>
+        </div>
+        {!state?.unlocked && (
+          <button className="settings-toggle" onClick={unlock}>
+            :unlock
+          </button>
+        )}
+      </div>
+      {state?.stored.map((authority) => (
+        <div className="settings-row" key={authority}>
+          <div className="settings-row-label">
+            <span className="settings-row-name">{authority}</span>
+          </div>
+          <button className="settings-toggle" onClick={() => forget(authority)}>
+            :forget
+          </button>
+        </div>
+      ))}
+    </div>
+  );
+}
+
+/* ------------------------------------------------------------------ */
+/*  Remote Channels                                                   */
+/* ------------------------------------------------------------------ */
+/** Mirrors the backend's channel pool defaults (remote.rs). */
+const REMOTE_DEFAULT_CHANNELS = 2;
+const REMOTE_MAX_CHANNELS = 8;
+
+/** How many command channels each saved remote host may keep open at once. */
+function RemoteChannelsSection() {
+  const [remotes, setRemotes] = useState<RemoteConfig[]>([]);
+  const [status, setStatus] = useState<string | null>(null);
+
+  useEffect(() => {
+    loadRemotes().then(setRemotes).catch(() => {});
+  }, []);
+
+  const setChannels = async (authority: string, channels: number) => {
+    const next = remotes.map((r) => (r.authority === authority ? { ...r, channels } : r));
+    setRemotes(next);
+    try {
+      await saveRemotes(next);
+      setStatus(null);
+    } catch (e) {
+      setStatus(String(e));
+    }
+  };
+
+  if (remotes.length === 0) return null;
+
+  return (
+    <div className="settings-section">
+      <div className="settings-section-title">~remote channels</div>
+      {status && (
+        <div className="settings-row">
+          <div className="settings-row-label">
+            <span className="settings-row-name">{status}</span>
+          </div>
+        </div>
+      )}
+      {remotes.map((remote) => (
+        <div className="settings-row" key={remote.authority}>
+          <div className="settings-row-label">
+            <span className="settings-row-name">{remote.authority}</span>
+          </div>
+          <Stepper
+            value={remote.channels ?? REMOTE_DEFAULT_CHANNELS}
+            min={1}
+            max={REMOTE_MAX_CHANNELS}
+            onChange={(v) => setChannels(remote.authority, v)}
+          />
+        </div>
+      ))}
+    </div>
+  );
+}
+
 /* ------------------------------------------------------------------ */
 /*  Hotkeys Page                                                       */
 /* ------------------------------------------------------------------ */
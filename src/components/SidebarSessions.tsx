@@ -8,9 +8,10 @@ import { useProjectStore } from "../stores/projectStore";
 import { useActionMenuStore } from "../stores/actionMenuStore";
 import { archiveTab, pinTab } from "../lib/sessions";
 import { getTabPrefix } from "../lib/sessionTypes";
-import { isRemotePath, remoteHostLabel } from "../lib/remote";
+import { isRemotePath, remoteHostLabel, splitRemotePath } from "../lib/remote";
+import { useRemoteHealth } from "../hooks/useRemoteHealth";
 import { THEMES } from "../lib/themes";
-import type { SplitDirection, PaneState, Project, TerminalSession, ThemeName } from "../types";
+import type { HostHealth, SplitDirection, PaneState, Project, TerminalSession, ThemeName } from "../types";
 
 function paneAreaFor(projectPath: string, pane: 1 | 2): HTMLElement | null {
   return document.querySelector<HTMLElement>(
@@ -41,6 +42,13 @@ function computeDropZone(el: HTMLElement, x: number, y: number): DropZone {
   return null;
 }
 
+function describeHealth(health: HostHealth | undefined): string {
+  if (!health) return "checking connection...";
+  if (health.state === "disconnected") return `disconnected${health.error ? `: ${health.error}` : ""}`;
+  const latency = health.latencyMs !== null ? ` (${health.latencyMs}ms)` : "";
+  return health.state === "degraded" && health.error ? `degraded: ${health.error}` : `${health.state}${latency}`;
+}
+
 interface SessionGroup {
   path: string;
   name: string;
@@ -110,6 +118,7 @@ export function SidebarSessions() {
   const tabStatuses = useSessionStore((s) => s.tabStatuses);
   const completedTabs = useSessionStore((s) => s.completedTabs);
   const sessionTitles = useSessionStore((s) => s.sessionTitles);
+  const remoteHealth = useRemoteHealth();
   const projectSplits = useSessionStore((s) => s.projectSplits);
   const activateSession = useSessionStore((s) => s.activateSession);
   const setActiveProject = useSessionStore((s) => s.setActiveProject);
@@ -268,6 +277,8 @@ export function SidebarSessions() {
           "--sidebar-project-text-tertiary": theme.css["--text-tertiary"],
         } as CSSProperties;
         const isActiveProject = group.path === activeProjectPath;
+        const authority = splitRemotePath(group.path)?.authority;
+        const health = authority ? remoteHealth[authority] : undefined;
         // the launcher is showing for this project, so the header itself is the selected row
         const isSelected = isActiveProject && activeSessionId === null;
         const headerClasses = ["sidebar-project-header", isActiveProject && "active", isSelected && "selected"]
@@ -284,7 +295,14 @@ export function SidebarSessions() {
                 <span className="sidebar-project-prefix">~/</span>
                 <span className="sidebar-project-name">{group.name}</span>
                 {isRemotePath(group.path) && (
-                  <span className="sidebar-project-remote">@{remoteHostLabel(group.path)}</span>
+                  <span
+                    className={`sidebar-project-remote${health ? ` sidebar-project-remote--${health.state}` : ""}`}
+                    title={describeHealth(health)}
+                  >
+                    @{remoteHostLabel(group.path)}
+                    {health?.state === "degraded" && "~"}
+                    {health?.state === "disconnected" && "!"}
+                  </span>
                 )}
                 {group.pinned && <span className="sidebar-project-pinned" title="pinned">[*]</span>}
                 {group.sessions.length > 0 && (
@@ -10,7 +10,7 @@ import {
   closePtySession,
   createPtySession,
   detachPtySessionStream,
-  saveClipboardImage,
+  saveClipboardImageForProject,
   resizePtySession,
   writePtySession,
 } from "../lib/pty";
@@ -266,11 +266,11 @@ export function TerminalView({ tabId, projectPath, projectName, sessionType, age
       const rows = terminal.rows;
 
       const ensureSessionAndAttach = async () => {
-        let sid = useSessionStore
+        const existing = useSessionStore
           .getState()
           .sessions
-          .find((session) => session.id === tabId)
-          ?.sessionId ?? null;
+          .find((session) => session.id === tabId);
+        let sid = existing?.sessionId ?? null;
 
         if (!sid) {
           let effectiveAgentSessionId = agentSessionId;
@@ -290,6 +290,9 @@ export function TerminalView({ tabId, projectPath, projectName, sessionType, age
               resumeSession,
               additionalArgs,
             }),
+            persistent: !ephemeral
+              && useProjectStore.getState().projects.find((p) => p.path === projectPath)?.persistentSessions === true,
+            detachedName: existing?.detachedName ?? null,
           });
           sid = created.sessionId;
           if (cleanedUp || spawnGenerationRef.current !== spawnGeneration) {
@@ -297,7 +300,7 @@ export function TerminalView({ tabId, projectPath, projectName, sessionType, age
             return;
           }
           updateSessionPtyId(tabId, sid);
-          updateSession(tabId, { hasStarted: true });
+          updateSession(tabId, { hasStarted: true, detachedName: created.detachedName ?? undefined });
         }
 
         if (cleanedUp || spawnGenerationRef.current !== spawnGeneration) return;
@@ -382,7 +385,15 @@ export function TerminalView({ tabId, projectPath, projectName, sessionType, age
 
       const buffer = await imageBlob.arrayBuffer();
       const data = Array.from(new Uint8Array(buffer));
-      const filePath = await saveClipboardImage(data, mimeType);
+      if (isRemotePath(projectPath)) showScreenshotStatus("uploading screenshot...");
+      let filePath: string;
+      try {
+        filePath = await saveClipboardImageForProject(projectPath, data, mimeType);
+      } catch (err) {
+        console.error("Failed to save screenshot:", err);
+        showScreenshotStatus("screenshot failed");
+        return;
+      }
       await writePtySession(sid, textEncoder.encode(filePath));
       showScreenshotStatus("screenshot pasted");
     };
@@ -0,0 +1,30 @@
+import { useEffect } from "react";
+import { listen } from "@tauri-apps/api/event";
+import { useGitStore } from "../stores/gitStore";
+import { useSessionStore } from "../stores/sessionStore";
+import { unwatchGitStatus, watchGitStatus } from "../lib/git";
+import { GitStatus } from "../types";
+
+// keep git status for the active project current regardless of which right panel tab is open;
+// the backend watches the repo and pushes changes instead of being polled
+export function useGitStatusWatch() {
+  const activeProjectPath = useSessionStore((s) => s.activeProjectPath);
+  const fetchStatus = useGitStore((s) => s.fetchStatus);
+  const applyStatus = useGitStore((s) => s.applyStatus);
+
+  useEffect(() => {
+    if (!activeProjectPath) return;
+
+    const unlisten = listen<{ projectPath: string; status: GitStatus }>("git-status-changed", (event) => {
+      if (event.payload.projectPath !== activeProjectPath) return;
+      applyStatus(activeProjectPath, event.payload.status);
+    });
+    watchGitStatus(activeProjectPath).catch(() => {});
+    fetchStatus(activeProjectPath);
+
+    return () => {
+      unlisten.then((fn) => fn());
+      unwatchGitStatus(activeProjectPath).catch(() => {});
+    };
+  }, [activeProjectPath, fetchStatus, applyStatus]);
+}
@@ -0,0 +1,33 @@
+import { useEffect, useState } from "react";
+import { listen } from "@tauri-apps/api/event";
+import { useProjectStore } from "../stores/projectStore";
+import { getRemoteHealth, isRemotePath, unwatchRemoteHealth, watchRemoteHealth } from "../lib/remote";
+import type { HostHealth } from "../types";
+
+// connection health for every host with a remote project open in the sidebar, keyed by
+// authority; the backend pings each host while it's watched and pushes changes
+export function useRemoteHealth(): Record<string, HostHealth> {
+  const projects = useProjectStore((s) => s.projects);
+  const [health, setHealth] = useState<Record<string, HostHealth>>({});
+  const remotePaths = projects.map((p) => p.path).filter(isRemotePath);
+  const key = remotePaths.join("\n");
+
+  useEffect(() => {
+    if (remotePaths.length === 0) return;
+    const unlisten = listen<HostHealth>("remote-health-changed", (event) => {
+      setHealth((prev) => ({ ...prev, [event.payload.authority]: event.payload }));
+    });
+    for (const path of remotePaths) watchRemoteHealth(path).catch(() => {});
+    getRemoteHealth()
+      .then((list) => setHealth(Object.fromEntries(list.map((h) => [h.authority, h]))))
+      .catch(() => {});
+
+    return () => {
+      unlisten.then((fn) => fn());
+      for (const path of remotePaths) unwatchRemoteHealth(path).catch(() => {});
+    };
+    // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, [key]);
+
+  return health;
+}
@@ -99,6 +99,11 @@ export function unwatchFile(tabId: string, filePath: string): Promise<void> {
   return invoke<void>("unwatch_file", { tabId, filePath });
 }
 
+/** Makes the next listing of a remote project fetch its file tree again. */
+export function refreshRemoteFileIndex(projectPath: string): Promise<void> {
+  return invoke<void>("refresh_remote_file_index", { projectPath });
+}
+
 export function readDirectory(
   projectPath: string,
   dirPath?: string,
@@ -1,30 +1,96 @@
-import { invoke } from "@tauri-apps/api/core";
-import { DiffStat, GenerateResult, GitFileEntry, GitStatus } from "../types";
+import { Channel, invoke } from "@tauri-apps/api/core";
+import { BlameLine, CommitEvent, DiffStat, GeneratePrResult, GenerateResult, GitFileEntry, GitStatus, LineHistoryEntry, NestedRepoStatus, SubmoduleInfo, UndoEntry } from "../types";
 
 export function getGitStatus(projectPath: string): Promise<GitStatus> {
   return invoke<GitStatus>("get_git_status", { projectPath });
 }
 
-export function getGitDiff(projectPath: string, filePath: string, status: string): Promise<string> {
-  return invoke<string>("get_git_diff", { projectPath, filePath, status });
+/** Starts pushing `git-status-changed` events for the project; ref-counted per caller. */
+export function watchGitStatus(projectPath: string): Promise<void> {
+  return invoke<void>("watch_git_status", { projectPath });
 }
 
-export function gitCommit(projectPath: string, files: string[], message: string): Promise<string> {
-  return invoke<string>("git_commit", { projectPath, files, message });
+export function unwatchGitStatus(projectPath: string): Promise<void> {
+  return invoke<void>("unwatch_git_status", { projectPath });
 }
 
-export function gitRevert(projectPath: string, files: GitFileEntry[]): Promise<void> {
-  return invoke<void>("git_revert", { projectPath, files });
+/** Status of a nested repo or submodule, by its path relative to the project, with the repos nested inside it. */
+export function getNestedGitStatus(projectPath: string, repoPath: string): Promise<NestedRepoStatus> {
+  return invoke<NestedRepoStatus>("get_nested_git_status", { projectPath, repoPath });
 }
 
-export function getGitDiffStats(projectPath: string, files: GitFileEntry[]): Promise<DiffStat[]> {
-  return invoke<DiffStat[]>("get_git_diff_stats", { projectPath, files });
+export function listGitSubmodules(projectPath: string): Promise<SubmoduleInfo[]> {
+  return invoke<SubmoduleInfo[]>("list_git_submodules", { projectPath });
 }
 
-export function gitPush(projectPath: string): Promise<string> {
-  return invoke<string>("git_push", { projectPath });
+// repoPath scopes an operation to a nested repo or submodule inside the project
+
+export function getGitDiff(projectPath: string, filePath: string, status: string, repoPath?: string): Promise<string> {
+  return invoke<string>("get_git_diff", { projectPath, filePath, status, repoPath: repoPath ?? null });
+}
+
+/** Signing follows settings; hook output streams to `onEvent` while the commit runs. */
+export function gitCommit(
+  projectPath: string,
+  files: string[],
+  message: string,
+  onEvent: (event: CommitEvent) => void,
+  options?: { noVerify?: boolean; repoPath?: string },
+): Promise<string> {
+  const channel = new Channel<CommitEvent>();
+  channel.onmessage = onEvent;
+  return invoke<string>("git_commit", {
+    projectPath,
+    files,
+    message,
+    repoPath: options?.repoPath ?? null,
+    noVerify: options?.noVerify ?? false,
+    onEvent: channel,
+  });
+}
+
+export function gitRevert(projectPath: string, files: GitFileEntry[], repoPath?: string): Promise<void> {
+  return invoke<void>("git_revert", { projectPath, files, repoPath: repoPath ?? null });
+}
+
+export function gitReset(projectPath: string, target: string, mode: "soft" | "mixed" | "hard", repoPath?: string): Promise<void> {
+  return invoke<void>("git_reset", { projectPath, target, mode, repoPath: repoPath ?? null });
+}
+
+export function gitDeleteBranch(projectPath: string, branch: string, force: boolean, repoPath?: string): Promise<void> {
+  return invoke<void>("git_delete_branch", { projectPath, branch, force, repoPath: repoPath ?? null });
+}
+
+/** Snapshots taken before revert/reset/branch delete, newest first. */
+export function getGitUndoHistory(projectPath: string, repoPath?: string): Promise<UndoEntry[]> {
+  return invoke<UndoEntry[]>("get_git_undo_history", { projectPath, repoPath: repoPath ?? null });
+}
+
+export function gitUndoLast(projectPath: string, repoPath?: string): Promise<UndoEntry> {
+  return invoke<UndoEntry>("git_undo_last", { projectPath, repoPath: repoPath ?? null });
+}
+
+export function getGitDiffStats(projectPath: string, files: GitFileEntry[], repoPath?: string): Promise<DiffStat[]> {
+  return invoke<DiffStat[]>("get_git_diff_stats", { projectPath, files, repoPath: repoPath ?? null });
+}
+
+export function gitPush(projectPath: string, repoPath?: string): Promise<string> {
+  return invoke<string>("git_push", { projectPath, repoPath: repoPath ?? null });
 }
 
 export function generateCommitMessage(projectPath: string, files: GitFileEntry[]): Promise<GenerateResult> {
   return invoke<GenerateResult>("generate_commit_message", { projectPath, files });
 }
+
+export function generatePrDescription(projectPath: string, targetBranch: string): Promise<GeneratePrResult> {
+  return invoke<GeneratePrResult>("generate_pr_description", { projectPath, targetBranch });
+}
+
+/** Per-line authorship; pass a 1-based inclusive range to blame only part of the file. */
+export function gitBlame(projectPath: string, filePath: string, startLine?: number, endLine?: number): Promise<BlameLine[]> {
+  return invoke<BlameLine[]>("git_blame", { projectPath, filePath, startLine: startLine ?? null, endLine: endLine ?? null });
+}
+
+export function gitLineHistory(projectPath: string, filePath: string, startLine: number, endLine: number): Promise<LineHistoryEntry[]> {
+  return invoke<LineHistoryEntry[]>("git_line_history", { projectPath, filePath, startLine, endLine });
+}
@@ -7,10 +7,15 @@ export interface CreatePtySessionRequest {
   rows: number;
   sessionType: string;
   command: string;
+  /** remote only: run inside tmux/dtach on the host */
+  persistent?: boolean;
+  /** reattach to this detached session, or restart the command under its name */
+  detachedName?: string | null;
 }
 
 export interface CreatePtySessionResponse {
   sessionId: string;
+  detachedName: string | null;
 }
 
 export interface AttachPtySessionStreamResponse {
@@ -88,6 +93,15 @@ export function saveClipboardImage(data: number[], mimeType: string): Promise<st
   return invoke<string>("save_clipboard_image", { data, mimeType });
 }
 
+/** Remote projects get the image uploaded to the host; the returned path is valid there. */
+export function saveClipboardImageForProject(
+  projectPath: string,
+  data: number[],
+  mimeType: string,
+): Promise<string> {
+  return invoke<string>("save_clipboard_image_for_project", { projectPath, data, mimeType });
+}
+
 export function exitApp(): Promise<void> {
   return invoke("exit_app");
 }
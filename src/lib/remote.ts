@@ -1,9 +1,43 @@
-import { invoke } from "@tauri-apps/api/core";
-import type { RemoteConfig, RemoteSpec } from "../types";
+import { Channel, invoke } from "@tauri-apps/api/core";
+import type {
+  CredentialsState,
+  DetachedSession,
+  ForwardStatus,
+  HostHealth,
+  ListeningPort,
+  PortForward,
+  RemoteConfig,
+  RemoteSpec,
+  SshHost,
+  StreamChunk,
+  StreamEnd,
+  TransferProgress,
+} from "../types";
 
 export const SSH_SCHEME = "ssh://";
+export const DOCKER_SCHEME = "docker://";
+export const WSL_SCHEME = "wsl://";
+
+/**
+ * Each scheme with the prefix its authorities carry, which keeps a container or distro
+ * apart from a same-named ssh host.
+ */
+const SCHEMES: [scheme: string, authorityPrefix: string][] = [
+  [SSH_SCHEME, ""],
+  [DOCKER_SCHEME, "docker:"],
+  [WSL_SCHEME, "wsl:"],
+];
+
+function schemeOf(path: string): [scheme: string, authorityPrefix: string] | undefined {
+  return SCHEMES.find(([scheme]) => path.startsWith(scheme));
+}
 
 export function isRemotePath(path: string): boolean {
+  return schemeOf(path) !== undefined;
+}
+
+/** Only ssh projects forward ports; wsl ones are on localhost already. */
+export function isSshPath(path: string): boolean {
   return path.startsWith(SSH_SCHEME);
 }
 
@@ -16,16 +50,21 @@ export function isRootPath(path: string): boolean {
   return path === "/" || /^[A-Za-z]:\/?$/.test(path);
 }
 
-/** Splits "ssh://user@host:2222/srv/app" into its authority and path. */
+/**
+ * Splits "ssh://user@host:2222/srv/app" into its authority and path; container and wsl
+ * paths like "docker://app/work" get a marked authority, "docker:app".
+ */
 export function splitRemotePath(path: string): { authority: string; path: string } | null {
-  if (!isRemotePath(path)) return null;
-  const rest = path.slice(SSH_SCHEME.length);
+  const found = schemeOf(path);
+  if (!found) return null;
+  const [scheme, prefix] = found;
+  const rest = path.slice(scheme.length);
   const slash = rest.indexOf("/");
-  if (slash === -1) return { authority: rest, path: "/" };
+  if (slash === -1) return { authority: prefix + rest, path: "/" };
   const tail = rest.slice(slash);
   // windows paths keep their drive letter: "/C:/app" is really "C:/app"
   const normalized = isWindowsPath(tail.slice(1)) ? tail.slice(1) : tail;
-  return { authority: rest.slice(0, slash), path: normalized };
+  return { authority: prefix + rest.slice(0, slash), path: normalized };
 }
 
 export function remoteAuthority(spec: RemoteSpec): string {
@@ -38,11 +77,28 @@ export function remoteUrl(spec: RemoteSpec, path: string): string {
   return `${SSH_SCHEME}${remoteAuthority(spec)}${path.startsWith("/") ? path : `/${path}`}`;
 }
 
-/** Host portion of a remote project path, for badges and titles. */
+export function containerUrl(container: string, user: string | undefined, path: string): string {
+  return `${DOCKER_SCHEME}${user ? `${user}@` : ""}${container}${path.startsWith("/") ? path : `/${path}`}`;
+}
+
+/** "\\wsl.localhost\Ubuntu\home\me" (or "\\wsl$\...") as "wsl://Ubuntu/home/me". */
+export function wslUrlFromUnc(path: string): string | null {
+  const match = /^[\\/]{2}wsl(?:\.localhost|\$)[\\/]([^\\/]+)(.*)$/i.exec(path);
+  if (!match) return null;
+  return `${WSL_SCHEME}${match[1]}${match[2].replace(/\\/g, "/") || "/"}`;
+}
+
+/** Host portion of a remote project path (the container or distro name for those), for badges and titles. */
 export function remoteHostLabel(path: string): string | null {
   const parsed = splitRemotePath(path);
   if (!parsed) return null;
-  return parsed.authority.split("@").pop() ?? parsed.authority;
+  const authority = parsed.authority.slice(schemeOf(path)![1].length);
+  return authority.split("@").pop() ?? authority;
+}
+
+/** The "docker://" url of the running dev container started for a local folder. */
+export function resolveDevcontainer(localPath: string): Promise<string> {
+  return invoke<string>("resolve_devcontainer", { localPath });
 }
 
 /** How a project path reads in the UI — remote paths lose the scheme noise. */
@@ -73,6 +129,33 @@ export function listRemoteDirs(spec: RemoteSpec, path?: string): Promise<RemoteL
   return invoke<RemoteListing>("list_remote_dirs", { spec, path: path ?? null });
 }
 
+export interface ToolStatus {
+  name: string;
+  /** null when the tool isn't on the host's PATH */
+  path: string | null;
+  version: string | null;
+}
+
+export interface HostDiagnosis {
+  os: string;
+  shell: string;
+  loginShell: string | null;
+  windows: boolean;
+  tools: ToolStatus[];
+  missing: string[];
+  /** One line per feature that won't work, or works worse, without what's missing. */
+  degraded: string[];
+}
+
+/** Checks a host for git, claude, tmux and the other tools remote projects rely on. */
+export function diagnoseRemoteHost(spec: RemoteSpec): Promise<HostDiagnosis> {
+  return invoke<HostDiagnosis>("diagnose_remote_host", { spec });
+}
+
+export function listSshHosts(): Promise<SshHost[]> {
+  return invoke<SshHost[]>("list_ssh_hosts");
+}
+
 export function loadRemotes(): Promise<RemoteConfig[]> {
   return invoke<RemoteConfig[]>("load_remotes");
 }
@@ -81,12 +164,139 @@ export function saveRemotes(remotes: RemoteConfig[]): Promise<void> {
   return invoke("save_remotes", { remotes });
 }
 
+export function getRemoteCredentialsState(): Promise<CredentialsState> {
+  return invoke<CredentialsState>("get_remote_credentials_state");
+}
+
+/** Reads saved secrets out of the OS keyring, which may prompt to unlock itself. */
+export function unlockRemoteCredentials(): Promise<CredentialsState> {
+  return invoke<CredentialsState>("unlock_remote_credentials");
+}
+
+export function storeRemoteCredentials(
+  authority: string,
+  secrets: { password?: string; passphrase?: string },
+): Promise<void> {
+  return invoke<void>("store_remote_credentials", {
+    authority,
+    password: secrets.password ?? null,
+    passphrase: secrets.passphrase ?? null,
+  });
+}
+
+export function forgetRemoteCredentials(authority: string): Promise<void> {
+  return invoke<void>("forget_remote_credentials", { authority });
+}
+
 /** Records the key for an authority so the backend can reconnect to it later. */
 export async function rememberRemote(spec: RemoteSpec): Promise<void> {
   const authority = remoteAuthority(spec);
   const existing = await loadRemotes();
   const prior = existing.find((r) => r.authority === authority);
   const next = existing.filter((r) => r.authority !== authority);
-  next.push({ authority, keyPath: spec.keyPath || prior?.keyPath || null });
+  next.push({
+    authority,
+    keyPath: spec.keyPath || prior?.keyPath || null,
+    forwards: prior?.forwards ?? [],
+    sshOptions: prior?.sshOptions ?? [],
+    channels: prior?.channels ?? null,
+  });
   await saveRemotes(next);
 }
+
+/** Copies a local file to an ssh:// path; the remote copy is checksum-verified before it lands. */
+export function uploadRemoteFile(
+  localPath: string,
+  remotePath: string,
+  onProgress: (progress: TransferProgress) => void,
+): Promise<void> {
+  const channel = new Channel<TransferProgress>();
+  channel.onmessage = onProgress;
+  return invoke<void>("upload_remote_file", { localPath, remotePath, onProgress: channel });
+}
+
+export function downloadRemoteFile(
+  remotePath: string,
+  localPath: string,
+  onProgress: (progress: TransferProgress) => void,
+): Promise<void> {
+  const channel = new Channel<TransferProgress>();
+  channel.onmessage = onProgress;
+  return invoke<void>("download_remote_file", { remotePath, localPath, onProgress: channel });
+}
+
+/** Ports a remote project's own processes are listening on, e.g. a dev server. */
+export function detectRemotePorts(projectPath: string): Promise<ListeningPort[]> {
+  return invoke<ListeningPort[]>("detect_remote_ports", { projectPath });
+}
+
+/** Progress arrives as `port-forward-changed` events. */
+export function openPortForward(projectPath: string, forward: PortForward): Promise<void> {
+  return invoke<void>("open_port_forward", { projectPath, forward });
+}
+
+export function closePortForward(projectPath: string, forward: PortForward): Promise<void> {
+  return invoke<void>("close_port_forward", { projectPath, forward });
+}
+
+export function listPortForwards(projectPath: string): Promise<ForwardStatus[]> {
+  return invoke<ForwardStatus[]>("list_port_forwards", { projectPath });
+}
+
+export function forwardKey(forward: PortForward): string {
+  return `${forward.direction}:${forward.localPort}:${forward.remoteHost ?? "localhost"}:${forward.remotePort}`;
+}
+
+export function getRemoteHealth(): Promise<HostHealth[]> {
+  return invoke<HostHealth[]>("get_remote_health");
+}
+
+/** Changes arrive as `remote-health-changed` events; hosts are pinged while watched. */
+export function watchRemoteHealth(projectPath: string): Promise<void> {
+  return invoke<void>("watch_remote_health", { projectPath });
+}
+
+export function unwatchRemoteHealth(projectPath: string): Promise<void> {
+  return invoke<void>("unwatch_remote_health", { projectPath });
+}
+
+/** Sessions left running in tmux/dtach on the host that were started in this project. */
+export function listDetachedSessions(projectPath: string): Promise<DetachedSession[]> {
+  return invoke<DetachedSession[]>("list_detached_sessions", { projectPath });
+}
+
+export function killDetachedSession(projectPath: string, name: string): Promise<void> {
+  return invoke<void>("kill_detached_session", { projectPath, name });
+}
+
+/** Fails commands still waiting for a free channel to the project's host. */
+export function cancelRemoteCommands(projectPath: string): Promise<void> {
+  return invoke<void>("cancel_remote_commands", { projectPath });
+}
+
+export interface RemoteRun {
+  /** Resolves once the command exits, is cancelled, or times out. */
+  done: Promise<StreamEnd>;
+  /** Kills the command's process group on the host. */
+  cancel: () => Promise<boolean>;
+}
+
+/** Runs a shell command in a remote project, handing output over as it arrives. */
+export function runRemoteCommand(
+  projectPath: string,
+  command: string,
+  onOutput: (chunk: StreamChunk) => void,
+  timeoutSecs?: number,
+): RemoteRun {
+  const runId = crypto.randomUUID();
+  const channel = new Channel<StreamChunk>();
+  channel.onmessage = onOutput;
+  const done = invoke<StreamEnd>("run_remote_command", {
+    projectPath,
+    command,
+    runId,
+    timeoutSecs: timeoutSecs ?? null,
+    onOutput: channel,
+  });
+  return { done, cancel: () => invoke<boolean>("cancel_remote_run", { runId }) };
+}
@@ -3,7 +3,7 @@ import { useProjectStore } from "../stores/projectStore";
 import { closePtySession } from "./pty";
 import { destroyPiSession } from "./pi";
 import { supportsAgentSessionResume } from "./sessionTypes";
-import type { TerminalSession } from "../types";
+import type { DetachedSession, TerminalSession } from "../types";
 
 export function spawnNewSession(type: string = "claude", projectPath?: string) {
   const { activeProjectPath, addSession, setActiveProject } = useSessionStore.getState();
@@ -26,6 +26,23 @@ export function spawnNewSession(type: string = "claude", projectPath?: string) {
   }, "start");
 }
 
+/** Open a tab on a session left running in tmux/dtach on a remote host. */
+export function attachDetachedSession(projectPath: string, detached: DetachedSession) {
+  const { addSession, setActiveProject } = useSessionStore.getState();
+  const project = useProjectStore.getState().projects.find((p) => p.path === projectPath);
+  setActiveProject(projectPath);
+  addSession({
+    id: generateTabId(),
+    projectName: project?.name ?? projectPath.split(/[/\\]/).pop() ?? "Unknown",
+    projectPath,
+    sessionId: null,
+    hasStarted: true,
+    createdAt: detached.createdAtMs ?? Date.now(),
+    sessionType: detached.sessionType,
+    detachedName: detached.name,
+  }, "start");
+}
+
 /** Open a file as an editor tab. If already open for this project, focuses it instead.
  *  @param preview - if true (default), opens as a preview tab that gets replaced by subsequent opens */
 export function openFileTab(filePath: string, fileName: string, preview = true) {
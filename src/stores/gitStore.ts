@@ -1,6 +1,6 @@
 import { create } from "zustand";
-import { DiffStat, GitFileEntry, GitStatus } from "../types";
-import { getGitDiff, getGitDiffStats, getGitStatus, gitCommit, gitPush, gitRevert, generateCommitMessage as generateCommitMessageIpc } from "../lib/git";
+import { CommitEvent, DiffStat, GitFileEntry, GitStatus, UndoEntry } from "../types";
+import { getGitDiff, getGitDiffStats, getGitStatus, getGitUndoHistory, gitCommit, gitPush, gitRevert, gitUndoLast, generateCommitMessage as generateCommitMessageIpc } from "../lib/git";
 import { useSettingsStore } from "./settingsStore";
 
 export function fileKey(file: GitFileEntry): string {
@@ -29,13 +29,21 @@ interface GitStore {
   commitMessage: string;
   committing: boolean;
   reverting: boolean;
+  /** snapshots of destructive operations per project, newest first */
+  undoHistory: Record<string, UndoEntry[]>;
+  undoing: boolean;
   commitDialogOpen: boolean;
   diffStats: DiffStat[];
   diffStatsLoading: boolean;
   commitError: string | null;
+  /** hook currently running during a commit, and the output streamed so far */
+  commitHook: string | null;
+  commitOutput: string[];
+  noVerify: boolean;
   pushing: boolean;
   generatingMessage: boolean;
   fetchStatus: (projectPath: string) => Promise<void>;
+  applyStatus: (projectPath: string, status: GitStatus) => void;
   toggleGroup: (group: string) => void;
   setViewMode: (mode: "file" | "tree") => void;
   openDiff: (projectPath: string, file: GitFileEntry, scopedContent?: ScopedDiffContent) => Promise<void>;
@@ -45,8 +53,11 @@ interface GitStore {
   deselectAllInGroup: (files: GitFileEntry[]) => void;
   clearSelection: () => void;
   setCommitMessage: (msg: string) => void;
+  setNoVerify: (noVerify: boolean) => void;
   commitSelected: (projectPath: string) => Promise<void>;
   revertFiles: (projectPath: string, files: GitFileEntry[]) => Promise<void>;
+  fetchUndoHistory: (projectPath: string) => Promise<void>;
+  undoLast: (projectPath: string) => Promise<void>;
   openCommitDialog: (projectPath: string) => Promise<void>;
   closeCommitDialog: () => void;
   commitAndPush: (projectPath: string) => Promise<void>;
@@ -54,6 +65,9 @@ interface GitStore {
   generateCommitMessage: (projectPath: string) => Promise<void>;
 }
 
+// capped so a chatty hook can't grow the dialog without bound
+const MAX_COMMIT_OUTPUT_LINES = 500;
+
 function selectedCount(sel: Record<string, boolean>): number {
   let count = 0;
   for (const k in sel) {
@@ -76,10 +90,15 @@ export const useGitStore = create<GitStore>((set, get) => ({
   commitMessage: "",
   committing: false,
   reverting: false,
+  undoHistory: {},
+  undoing: false,
   commitDialogOpen: false,
   diffStats: [],
   diffStatsLoading: false,
   commitError: null,
+  commitHook: null,
+  commitOutput: [],
+  noVerify: false,
   pushing: false,
   generatingMessage: false,
 
@@ -90,20 +109,7 @@ export const useGitStore = create<GitStore>((set, get) => ({
     });
     try {
       const status = await getGitStatus(projectPath);
-      set((state) => {
-        // Prune selected files to only keys that still exist
-        const validKeys = new Set(status.files.map((f) => fileKey(f)));
-        const pruned: Record<string, boolean> = {};
-        for (const key in state.selectedFiles) {
-          if (state.selectedFiles[key] && validKeys.has(key)) {
-            pruned[key] = true;
-          }
-        }
-        return {
-          statuses: { ...state.statuses, [projectPath]: status },
-          selectedFiles: pruned,
-        };
-      });
+      get().applyStatus(projectPath, status);
     } catch {
       // silently ignore — project may not exist or git not available
     } finally {
@@ -111,6 +117,22 @@ export const useGitStore = create<GitStore>((set, get) => ({
     }
   },
 
+  applyStatus: (projectPath: string, status: GitStatus) =>
+    set((state) => {
+      // Prune selected files to only keys that still exist
+      const validKeys = new Set(status.files.map((f) => fileKey(f)));
+      const pruned: Record<string, boolean> = {};
+      for (const key in state.selectedFiles) {
+        if (state.selectedFiles[key] && validKeys.has(key)) {
+          pruned[key] = true;
+        }
+      }
+      return {
+        statuses: { ...state.statuses, [projectPath]: status },
+        selectedFiles: pruned,
+      };
+    }),
+
   toggleGroup: (group: string) =>
     set((state) => ({
       collapsedGroups: {
@@ -166,6 +188,8 @@ export const useGitStore = create<GitStore>((set, get) => ({
 
   setCommitMessage: (msg: string) => set({ commitMessage: msg }),
 
+  setNoVerify: (noVerify: boolean) => set({ noVerify }),
+
   commitSelected: async (projectPath: string) => {
     const { selectedFiles: sel, commitMessage, statuses } = get();
     if (selectedCount(sel) === 0 || !commitMessage.trim()) return;
@@ -184,9 +208,9 @@ export const useGitStore = create<GitStore>((set, get) => ({
 
     const uniquePaths = [...new Set(filePaths)];
 
-    set({ committing: true, commitError: null });
+    set({ committing: true, commitError: null, commitHook: null, commitOutput: [] });
     try {
-      await gitCommit(projectPath, uniquePaths, commitMessage.trim());
+      await gitCommit(projectPath, uniquePaths, commitMessage.trim(), onCommitEvent, { noVerify: get().noVerify });
       set({ selectedFiles: {}, commitMessage: "", commitDialogOpen: false });
       await get().fetchStatus(projectPath);
     } catch (e) {
@@ -212,6 +236,27 @@ export const useGitStore = create<GitStore>((set, get) => ({
       throw e;
     } finally {
       set({ reverting: false });
+      get().fetchUndoHistory(projectPath);
+    }
+  },
+
+  fetchUndoHistory: async (projectPath: string) => {
+    try {
+      const history = await getGitUndoHistory(projectPath);
+      set((state) => ({ undoHistory: { ...state.undoHistory, [projectPath]: history } }));
+    } catch {
+      // not a repo, or git unavailable
+    }
+  },
+
+  undoLast: async (projectPath: string) => {
+    set({ undoing: true });
+    try {
+      await gitUndoLast(projectPath);
+      await get().fetchStatus(projectPath);
+    } finally {
+      set({ undoing: false });
+      get().fetchUndoHistory(projectPath);
     }
   },
 
@@ -233,7 +278,7 @@ export const useGitStore = create<GitStore>((set, get) => ({
     }
   },
 
-  closeCommitDialog: () => set({ commitDialogOpen: false, commitError: null }),
+  closeCommitDialog: () => set({ commitDialogOpen: false, commitError: null, commitHook: null, commitOutput: [] }),
 
   commitAndPush: async (projectPath: string) => {
     const { selectedFiles: sel, commitMessage, statuses } = get();
@@ -252,9 +297,9 @@ export const useGitStore = create<GitStore>((set, get) => ({
 
     const uniquePaths = [...new Set(filePaths)];
 
-    set({ committing: true, commitError: null });
+    set({ committing: true, commitError: null, commitHook: null, commitOutput: [] });
     try {
-      await gitCommit(projectPath, uniquePaths, commitMessage.trim());
+      await gitCommit(projectPath, uniquePaths, commitMessage.trim(), onCommitEvent, { noVerify: get().noVerify });
     } catch (e) {
       set({ committing: false, commitError: e instanceof Error ? e.message : String(e) });
       return;
@@ -296,3 +341,13 @@ export const useGitStore = create<GitStore>((set, get) => ({
     }
   },
 }));
+
+function onCommitEvent(event: CommitEvent) {
+  if (event.type === "Hook") {
+    useGitStore.setState({ commitHook: event.data.name });
+  } else {
+    useGitStore.setState((state) => ({
+      commitOutput: [...state.commitOutput, event.data.line].slice(-MAX_COMMIT_OUTPUT_LINES),
+    }));
+  }
+}
@@ -2,6 +2,7 @@ import { create } from "zustand";
 import { Project, ThemeName } from "../types";
 import { loadProjects, saveProjects } from "../lib/config";
 import { THEMES } from "../lib/themes";
+import { cancelRemoteCommands, splitRemotePath } from "../lib/remote";
 import { useSessionStore } from "./sessionStore";
 
 const THEME_NAMES = Object.keys(THEMES) as ThemeName[];
@@ -15,6 +16,7 @@ interface ProjectStore {
   reorderProjects: (paths: string[]) => Promise<void>;
   updateProjectTheme: (path: string, theme: ThemeName) => Promise<void>;
   togglePinned: (path: string) => Promise<void>;
+  togglePersistentSessions: (path: string) => Promise<void>;
 }
 
 export const useProjectStore = create<ProjectStore>((set, get) => ({
@@ -59,6 +61,11 @@ export const useProjectStore = create<ProjectStore>((set, get) => ({
     const updated = get().projects.filter((p) => p.path !== path);
     await saveProjects(updated);
     useSessionStore.getState().removeProjectSessions(path);
+    // the last project on a host leaves nothing waiting on its queued commands
+    const authority = splitRemotePath(path)?.authority;
+    if (authority && !updated.some((p) => splitRemotePath(p.path)?.authority === authority)) {
+      cancelRemoteCommands(path).catch(() => {});
+    }
     set({ projects: updated });
   },
 
@@ -84,4 +91,12 @@ export const useProjectStore = create<ProjectStore>((set, get) => ({
     await saveProjects(updated);
     set({ projects: updated });
   },
+
+  togglePersistentSessions: async (path: string) => {
+    const updated = get().projects.map((p) =>
+      p.path === path ? { ...p, persistentSessions: !p.persistentSessions } : p
+    );
+    await saveProjects(updated);
+    set({ projects: updated });
+  },
 }));
@@ -79,8 +79,8 @@ function enqueuePersistence(state: PersistedSessionState): Promise<void> {
   return persistChain;
 }
 
-function toPersistedSession({ id, projectName, projectPath, agentSessionId, hasStarted, createdAt, sessionType }: TerminalSession): PersistedSession {
-  return { id, projectName, projectPath, agentSessionId, hasStarted, createdAt, sessionType };
+function toPersistedSession({ id, projectName, projectPath, agentSessionId, hasStarted, createdAt, sessionType, detachedName }: TerminalSession): PersistedSession {
+  return { id, projectName, projectPath, agentSessionId, hasStarted, createdAt, sessionType, detachedName };
 }
 
 /** restored from disk or from the archive: no pty yet, resume only if it ever ran */
@@ -31,6 +31,8 @@ export interface Project {
   theme: ThemeName;
   /** pinned projects keep a sidebar section even with no live sessions */
   pinned?: boolean;
+  /** remote sessions run inside tmux/dtach on the host, so they survive disconnects */
+  persistentSessions?: boolean;
 }
 
 /** Connection details for an ssh host, entered in the add-project dialog. */
@@ -40,11 +42,80 @@ export interface RemoteSpec {
   port?: number;
   keyPath?: string;
   password?: string;
+  /** unlocks an encrypted `keyPath` key */
+  passphrase?: string;
+}
+
+/** Remote secrets in the OS keyring stay unread until unlocked. */
+export interface CredentialsState {
+  unlocked: boolean;
+  /** authorities with saved secrets; only known once unlocked */
+  stored: string[];
 }
 
 export interface RemoteConfig {
   authority: string;
   keyPath: string | null;
+  /** Forwards offered for every project on this host. */
+  forwards?: PortForward[];
+  /** Extra ssh `-o` options, e.g. "ForwardAgent=yes". */
+  sshOptions?: string[];
+  /** Command channels kept open to the host; unset means the default. */
+  channels?: number | null;
+}
+
+/** A host alias from ~/.ssh/config; ssh applies its settings when the alias is the host. */
+export interface SshHost {
+  alias: string;
+  hostName: string | null;
+  user: string | null;
+  port: number | null;
+  identityFile: string | null;
+  proxyJump: string | null;
+}
+
+export interface PortForward {
+  /** local is `ssh -L` (open a remote port here), remote is `ssh -R`. */
+  direction: "local" | "remote";
+  localPort: number;
+  remotePort: number;
+  remoteHost?: string | null;
+}
+
+export type ForwardState = "opening" | "open" | "closed" | "failed";
+
+export interface ForwardStatus {
+  projectPath: string;
+  forward: PortForward;
+  state: ForwardState;
+  error: string | null;
+}
+
+/** A session the app left running in tmux/dtach on a remote host. */
+export interface DetachedSession {
+  name: string;
+  backend: "tmux" | "dtach";
+  sessionType: string;
+  createdAtMs: number | null;
+  attached: boolean;
+}
+
+export type HealthState = "connected" | "degraded" | "disconnected";
+
+/** Connection health of one remote host, from pings over its command channel. */
+export interface HostHealth {
+  authority: string;
+  state: HealthState;
+  latencyMs: number | null;
+  error: string | null;
+  checkedAtMs: number;
+}
+
+export interface ListeningPort {
+  port: number;
+  address: string;
+  process: string | null;
+  pid: number | null;
 }
 
 export interface PinnedFile {
@@ -67,9 +138,11 @@ export interface TerminalSession {
   filePath?: string;
   fileName?: string;
   isPreview?: boolean;
+  /** the tmux/dtach session this remote terminal runs in, reattached after a restart */
+  detachedName?: string;
 }
 
-export type PersistedSession = Pick<TerminalSession, "id" | "projectName" | "projectPath" | "agentSessionId" | "hasStarted" | "createdAt" | "sessionType">;
+export type PersistedSession = Pick<TerminalSession, "id" | "projectName" | "projectPath" | "agentSessionId" | "hasStarted" | "createdAt" | "sessionType" | "detachedName">;
 
 export interface PersistedSessionState {
   sessions: PersistedSession[];
@@ -85,6 +158,38 @@ export type PtyOutputEvent =
   | { type: "Closed"; data: { reason: string } }
   | { type: "Error"; data: { message: string } };
 
+export type CommitEvent =
+  | { type: "Hook"; data: { name: string } }
+  | { type: "Output"; data: { line: string } };
+
+export type StreamChunk =
+  | { type: "Stdout"; data: { bytes: number[] } }
+  | { type: "Stderr"; data: { bytes: number[] } };
+
+/** How a streamed remote command finished. */
+export type StreamEnd =
+  | { type: "Exited"; data: { code: number } }
+  | { type: "Cancelled" }
+  | { type: "TimedOut" };
+
+export interface TransferProgress {
+  transferred: number;
+  total: number;
+}
+
+export interface UndoEntry {
+  commit: string;
+  op: "revert" | "reset" | "deleteBranch";
+  summary: string;
+  timestamp: number;
+  head: string | null;
+  paths: string[];
+  branch: string | null;
+  tip: string | null;
+  mode: string | null;
+  index: string | null;
+}
+
 export interface GitFileEntry {
   path: string;
   status: string; // "M", "A", "D", "R", "?", "S" (subrepo)
@@ -96,6 +201,22 @@ export interface GitStatus {
   files: GitFileEntry[];
 }
 
+/** A nested repo's status, with the repos nested inside it (paths relative to the project). */
+export interface NestedRepoStatus {
+  path: string;
+  status: GitStatus;
+  nested: NestedRepoStatus[];
+}
+
+export interface SubmoduleInfo {
+  path: string;
+  commit: string;
+  describe: string | null;
+  state: "clean" | "outOfSync" | "uninitialized" | "conflict";
+  /** uncommitted changes inside the submodule's own worktree */
+  dirty: boolean;
+}
+
 export interface FileTreeEntry {
   name: string;
   path: string;
@@ -109,10 +230,49 @@ export interface DiffStat {
   deletions: number;
 }
 
+/** How much of a file's diff made it into a generation prompt. */
+export interface SummarisedFile {
+  path: string;
+  detail: "hunks" | "stat";
+}
+
 export interface GenerateResult {
   prompt: string;
   message: string;
   model: string;
+  summarisedFiles: SummarisedFile[];
+}
+
+export interface GeneratePrResult {
+  prompt: string;
+  title: string;
+  body: string;
+  model: string;
+  /** merge base commit the diff was taken from */
+  base: string;
+  commitCount: number;
+  summarisedFiles: SummarisedFile[];
+}
+
+export interface BlameLine {
+  line: number;
+  commit: string;
+  author: string;
+  authorMail: string;
+  /** unix seconds */
+  timestamp: number;
+  summary: string;
+  uncommitted: boolean;
+  content: string;
+}
+
+export interface LineHistoryEntry {
+  commit: string;
+  author: string;
+  authorMail: string;
+  timestamp: number;
+  summary: string;
+  diff: string;
 }
 
 export type ThemeName = "midnight" | "ember" | "arctic" | "forest" | "crimson" | "sakura" | "amber";
@@ -156,6 +316,16 @@ export interface Settings {
   soundEnabled: boolean;
   defaultSessionType: string;
   sessionTypes: SessionTypeConfig[];
+  /** token budget for diffs sent to commit/PR message generation */
+  diffTokenBudget: number;
+  /** sign commits made from the commit dialog (`git commit -S`) */
+  signCommits: boolean;
+  /** key id for signing; empty uses git's configured default */
+  signingKey: string | null;
+  /** read saved remote credentials from the OS keyring at launch */
+  unlockCredentialsOnStart: boolean;
+  /** fetch a remote project's whole file tree at once instead of a folder at a time */
+  remoteFileIndex: boolean;
 }
 
 export const DEFAULT_SETTINGS: Settings = {
@@ -175,4 +345,9 @@ export const DEFAULT_SETTINGS: Settings = {
   soundEnabled: true,
   defaultSessionType: "claude",
   sessionTypes: [...DEFAULT_SESSION_TYPES],
+  diffTokenBudget: 25000,
+  signCommits: false,
+  signingKey: null,
+  unlockCredentialsOnStart: false,
+  remoteFileIndex: true,
 };